@@ -0,0 +1,38 @@
+-- Initial schema
+-- Uses IF NOT EXISTS so databases created before versioned migrations are adopted as-is
+
+CREATE TABLE IF NOT EXISTS cards (
+    id INTEGER PRIMARY KEY,
+    name_en TEXT NOT NULL,
+    name_de TEXT NOT NULL,
+    book INTEGER NOT NULL,
+    page INTEGER NOT NULL,
+    side TEXT NOT NULL,
+    entry INTEGER NOT NULL,
+    img_url TEXT NOT NULL,
+    owned BOOLEAN NOT NULL CHECK (owned IN (0,1)),
+    created_at DATETIME DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE TABLE IF NOT EXISTS expansions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    abbreviation TEXT NOT NULL UNIQUE,
+    cards INTEGER NOT NULL CHECK (cards > 0),
+    secret_cards INTEGER NOT NULL CHECK (secret_cards >= 0)
+);
+
+CREATE TABLE IF NOT EXISTS card_expansions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    card_id INTEGER NOT NULL,
+    expansion_id INTEGER NOT NULL,
+    card_number TEXT NOT NULL,
+    rarity TEXT NOT NULL,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+    FOREIGN KEY (expansion_id) REFERENCES expansions(id) ON DELETE CASCADE,
+    UNIQUE(card_id, expansion_id, card_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_card_expansions_card_id ON card_expansions(card_id);
+CREATE INDEX IF NOT EXISTS idx_card_expansions_expansion_id ON card_expansions(expansion_id);
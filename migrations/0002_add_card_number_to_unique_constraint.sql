@@ -0,0 +1,29 @@
+-- Update card_expansions UNIQUE constraint to include card_number
+-- SQLite doesn't support ALTER TABLE to modify constraints, so the table is recreated
+
+CREATE TABLE card_expansions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    card_id INTEGER NOT NULL,
+    expansion_id INTEGER NOT NULL,
+    card_number TEXT NOT NULL,
+    rarity TEXT NOT NULL,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
+    FOREIGN KEY (expansion_id) REFERENCES expansions(id) ON DELETE CASCADE,
+    UNIQUE(card_id, expansion_id, card_number)
+);
+
+-- Copy data from old table to new table
+INSERT INTO card_expansions_new (id, card_id, expansion_id, card_number, rarity, created_at)
+SELECT id, card_id, expansion_id, card_number, rarity, created_at
+FROM card_expansions;
+
+-- Drop old table
+DROP TABLE card_expansions;
+
+-- Rename new table to original name
+ALTER TABLE card_expansions_new RENAME TO card_expansions;
+
+-- Recreate indexes
+CREATE INDEX idx_card_expansions_card_id ON card_expansions(card_id);
+CREATE INDEX idx_card_expansions_expansion_id ON card_expansions(expansion_id);
@@ -0,0 +1,27 @@
+-- Append-only history of ownership and card expansion changes
+-- `payload` holds the JSON encoded change, `reverts` the batch an undo reverted
+
+CREATE TABLE card_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    batch INTEGER NOT NULL,
+    card_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    profile TEXT NOT NULL,
+    reverts INTEGER,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX idx_card_events_card_id ON card_events(card_id, id);
+CREATE INDEX idx_card_events_batch ON card_events(batch);
+CREATE INDEX idx_card_events_reverts ON card_events(reverts);
+
+CREATE TRIGGER card_events_no_update BEFORE UPDATE ON card_events
+BEGIN
+    SELECT RAISE(ABORT, 'card_events is append-only');
+END;
+
+CREATE TRIGGER card_events_no_delete BEFORE DELETE ON card_events
+BEGIN
+    SELECT RAISE(ABORT, 'card_events is append-only');
+END;
@@ -0,0 +1,93 @@
+-- Full-text search index over card names, expansions and card numbers
+-- The rowid is the card id, rows are rebuilt by the triggers below whenever a
+-- card, one of its expansion entries or an expansion it is listed in changes.
+-- `names` holds every stored language, separated by spaces.
+
+CREATE VIRTUAL TABLE card_search USING fts5(
+    names,
+    expansions,
+    card_numbers,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+INSERT INTO card_search (rowid, names, expansions, card_numbers)
+SELECT
+    c.id,
+    c.name_en || ' ' || c.name_de,
+    (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+     FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+     WHERE ce.card_id = c.id),
+    (SELECT group_concat(ce.card_number, ' ') FROM card_expansions ce WHERE ce.card_id = c.id)
+FROM cards c;
+
+-- Cards
+
+CREATE TRIGGER card_search_cards_insert AFTER INSERT ON cards
+BEGIN
+    DELETE FROM card_search WHERE rowid = NEW.id;
+    INSERT INTO card_search (rowid, names, expansions, card_numbers)
+    SELECT
+        NEW.id,
+        NEW.name_en || ' ' || NEW.name_de,
+        (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+         FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+         WHERE ce.card_id = NEW.id),
+        (SELECT group_concat(ce.card_number, ' ') FROM card_expansions ce WHERE ce.card_id = NEW.id);
+END;
+
+CREATE TRIGGER card_search_cards_update AFTER UPDATE OF name_en, name_de ON cards
+BEGIN
+    UPDATE card_search SET names = NEW.name_en || ' ' || NEW.name_de WHERE rowid = NEW.id;
+END;
+
+CREATE TRIGGER card_search_cards_delete AFTER DELETE ON cards
+BEGIN
+    DELETE FROM card_search WHERE rowid = OLD.id;
+END;
+
+-- Expansion entries of a card
+
+CREATE TRIGGER card_search_card_expansions_insert AFTER INSERT ON card_expansions
+BEGIN
+    UPDATE card_search SET
+        expansions = (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+                      FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+                      WHERE ce.card_id = NEW.card_id),
+        card_numbers = (SELECT group_concat(ce.card_number, ' ')
+                        FROM card_expansions ce WHERE ce.card_id = NEW.card_id)
+    WHERE rowid = NEW.card_id;
+END;
+
+CREATE TRIGGER card_search_card_expansions_update AFTER UPDATE ON card_expansions
+BEGIN
+    UPDATE card_search SET
+        expansions = (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+                      FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+                      WHERE ce.card_id = NEW.card_id),
+        card_numbers = (SELECT group_concat(ce.card_number, ' ')
+                        FROM card_expansions ce WHERE ce.card_id = NEW.card_id)
+    WHERE rowid = NEW.card_id;
+END;
+
+CREATE TRIGGER card_search_card_expansions_delete AFTER DELETE ON card_expansions
+BEGIN
+    UPDATE card_search SET
+        expansions = (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+                      FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+                      WHERE ce.card_id = OLD.card_id),
+        card_numbers = (SELECT group_concat(ce.card_number, ' ')
+                        FROM card_expansions ce WHERE ce.card_id = OLD.card_id)
+    WHERE rowid = OLD.card_id;
+END;
+
+-- Renamed expansions
+
+CREATE TRIGGER card_search_expansions_update AFTER UPDATE OF name, abbreviation ON expansions
+BEGIN
+    UPDATE card_search SET
+        expansions = (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+                      FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+                      WHERE ce.card_id = card_search.rowid)
+    WHERE rowid IN (SELECT card_id FROM card_expansions WHERE expansion_id = NEW.id);
+END;
@@ -0,0 +1,19 @@
+-- Pokemon types and card tags for the `type` and `tag` filters
+-- Types belong to the Pokedex number, not to a stored card, so `type:fire`
+-- also matches cards that were never looked up. They are fetched from PokeAPI
+-- on startup while the table is empty, or with `--fetch-types`; `slot` 1 is the
+-- primary type. Tags are lowercase labels on stored cards such as `signed`.
+
+CREATE TABLE pokemon_types (
+    pokemon_id INTEGER NOT NULL,
+    slot INTEGER NOT NULL CHECK (slot IN (1, 2)),
+    type TEXT NOT NULL,
+    PRIMARY KEY (pokemon_id, slot)
+);
+
+CREATE TABLE card_tags (
+    card_id INTEGER NOT NULL,
+    tag TEXT NOT NULL CHECK (tag = lower(tag) AND tag != ''),
+    PRIMARY KEY (card_id, tag),
+    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+-- Named filter queries shown as virtual binders
+-- Only the query text is stored, results are computed on every read.
+
+CREATE TABLE saved_queries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    query TEXT NOT NULL,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime'))
+);
@@ -0,0 +1,28 @@
+-- Row versions for optimistic concurrency control
+-- Every change to a card or to one of its prints bumps `cards.version`, so a
+-- client that loaded a card together with its prints sends back one number
+-- and the write is rejected if anything changed in between.
+
+ALTER TABLE cards ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- Writes that set the version themselves are left alone
+CREATE TRIGGER cards_bump_version AFTER UPDATE ON cards
+WHEN NEW.version = OLD.version
+BEGIN
+    UPDATE cards SET version = OLD.version + 1 WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER card_expansions_insert_bump_version AFTER INSERT ON card_expansions
+BEGIN
+    UPDATE cards SET version = version + 1 WHERE id = NEW.card_id;
+END;
+
+CREATE TRIGGER card_expansions_update_bump_version AFTER UPDATE ON card_expansions
+BEGIN
+    UPDATE cards SET version = version + 1 WHERE id IN (OLD.card_id, NEW.card_id);
+END;
+
+CREATE TRIGGER card_expansions_delete_bump_version AFTER DELETE ON card_expansions
+BEGIN
+    UPDATE cards SET version = version + 1 WHERE id = OLD.card_id;
+END;
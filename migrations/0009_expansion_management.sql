@@ -0,0 +1,26 @@
+-- Expansions managed from the app instead of the seed file
+-- Archived expansions keep their prints but are no longer offered for new ones.
+-- Every create, edit and archive is written to the append-only `expansion_events`.
+
+ALTER TABLE expansions ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0 CHECK (archived IN (0,1));
+
+CREATE TABLE expansion_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    expansion_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    profile TEXT NOT NULL,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime'))
+);
+
+CREATE INDEX idx_expansion_events_expansion_id ON expansion_events(expansion_id, id);
+
+CREATE TRIGGER expansion_events_no_update BEFORE UPDATE ON expansion_events
+BEGIN
+    SELECT RAISE(ABORT, 'expansion_events is append-only');
+END;
+
+CREATE TRIGGER expansion_events_no_delete BEFORE DELETE ON expansion_events
+BEGIN
+    SELECT RAISE(ABORT, 'expansion_events is append-only');
+END;
@@ -0,0 +1,177 @@
+-- Series, release date, region, set code and symbol per expansion
+-- `release_date` is an ISO date (YYYY-MM-DD), `region` one of EN, DE or JP.
+-- Only the series of the seeded sets is known, the rest is filled in on the
+-- expansions page.
+
+ALTER TABLE expansions ADD COLUMN series TEXT NOT NULL DEFAULT '';
+ALTER TABLE expansions ADD COLUMN release_date TEXT;
+ALTER TABLE expansions ADD COLUMN region TEXT NOT NULL DEFAULT 'EN' CHECK (region IN ('EN', 'DE', 'JP'));
+ALTER TABLE expansions ADD COLUMN set_code TEXT;
+ALTER TABLE expansions ADD COLUMN symbol_url TEXT;
+
+UPDATE expansions SET series = 'Base' WHERE name IN (
+    'Base Set',
+    'Jungle',
+    'Fossil',
+    'Base Set 2',
+    'Team Rocket',
+    'Gym Heroes',
+    'Gym Challenge'
+);
+
+UPDATE expansions SET series = 'Neo' WHERE name IN (
+    'Neo Genesis',
+    'Neo Discovery',
+    'Neo Revelation',
+    'Neo Destiny'
+);
+
+UPDATE expansions SET series = 'Legendary Collection & e-Card' WHERE name IN (
+    'Legendary Collection',
+    'Expedition Base Set',
+    'Aquapolis',
+    'Skyridge'
+);
+
+UPDATE expansions SET series = 'EX' WHERE name IN (
+    'EX Ruby & Sapphire',
+    'EX Sandstorm',
+    'EX Dragon',
+    'EX Team Magma vs Team Aqua',
+    'EX Hidden Legends',
+    'EX FireRed & LeafGreen',
+    'EX Team Rocket Returns',
+    'EX Deoxys',
+    'EX Emerald',
+    'EX Unseen Forces',
+    'EX Delta Species',
+    'EX Legend Maker',
+    'EX Holon Phantoms',
+    'EX Crystal Guardians',
+    'EX Dragon Frontiers',
+    'EX Power Keepers'
+);
+
+UPDATE expansions SET series = 'Diamond & Pearl' WHERE name IN (
+    'Diamond & Pearl',
+    'Mysterious Treasures',
+    'Secret Wonders',
+    'Great Encounters',
+    'Majestic Dawn',
+    'Legends Awakened',
+    'Stormfront'
+);
+
+UPDATE expansions SET series = 'Platinum' WHERE name IN (
+    'Platinum',
+    'Rising Rivals',
+    'Supreme Victors',
+    'Platinum—Arceus'
+);
+
+UPDATE expansions SET series = 'HeartGold & SoulSilver' WHERE name IN (
+    'HeartGold & SoulSilver',
+    'HS—Unleashed',
+    'HS—Undaunted',
+    'HS—Triumphant'
+);
+
+UPDATE expansions SET series = 'Call of Legends' WHERE name IN (
+    'Call of Legends'
+);
+
+UPDATE expansions SET series = 'Black & White' WHERE name IN (
+    'Black & White',
+    'Emerging Powers',
+    'Noble Victories',
+    'Next Destinies',
+    'Dark Explorers',
+    'Dragons Exalted',
+    'Dragon Vault',
+    'Boundaries Crossed',
+    'Plasma Storm',
+    'Plasma Freeze',
+    'Plasma Blast',
+    'Legendary Treasures'
+);
+
+UPDATE expansions SET series = 'XY' WHERE name IN (
+    'Kalos Starter Set',
+    'XY',
+    'Flashfire',
+    'Furious Fists',
+    'Phantom Forces',
+    'Primal Clash',
+    'Double Crisis',
+    'Roaring Skies',
+    'Ancient Origins',
+    'BREAKthrough',
+    'BREAKpoint',
+    'Generations',
+    'Fates Collide',
+    'Steam Siege',
+    'Evolutions'
+);
+
+UPDATE expansions SET series = 'Sun & Moon' WHERE name IN (
+    'Sun & Moon',
+    'Guardians Rising',
+    'Burning Shadows',
+    'Shining Legends',
+    'Crimson Invasion',
+    'Ultra Prism',
+    'Forbidden Light',
+    'Celestial Storm',
+    'Dragon Majesty',
+    'Lost Thunder',
+    'Team Up',
+    'Detective Pikachu',
+    'Unbroken Bonds',
+    'Unified Minds',
+    'Hidden Fates',
+    'Cosmic Eclipse'
+);
+
+UPDATE expansions SET series = 'Sword & Shield' WHERE name IN (
+    'Sword & Shield',
+    'Rebel Clash',
+    'Darkness Ablaze',
+    'Champion''s Path',
+    'Vivid Voltage',
+    'Shining Fates',
+    'Battle Styles',
+    'Chilling Reign',
+    'Evolving Skies',
+    'Celebrations',
+    'Fusion Strike',
+    'Brilliant Stars',
+    'Astral Radiance',
+    'Pokémon GO',
+    'Lost Origin',
+    'Silver Tempest',
+    'Crown Zenith'
+);
+
+UPDATE expansions SET series = 'Scarlet & Violet' WHERE name IN (
+    'Scarlet & Violet',
+    'Paldea Evolved',
+    'Obsidian Flames',
+    '151',
+    'Paradox Rift',
+    'Paldean Fates',
+    'Temporal Forces',
+    'Twilight Masquerade',
+    'Shrouded Fable',
+    'Stellar Crown',
+    'Surging Sparks',
+    'Prismatic Evolutions',
+    'Journey Together',
+    'Destined Rivals',
+    'Black Bolt',
+    'White Flare'
+);
+
+UPDATE expansions SET series = 'Mega Evolution' WHERE name IN (
+    'Mega Evolution',
+    'Phantasmal Flames'
+);
@@ -0,0 +1,5 @@
+-- How the cards of each expansion are numbered, as JSON
+-- NULL is the plain `number/total` scheme. Subsets with prefixes of their own
+-- (TG, RC, SV, ...) and zero padding come from the expansion catalog.
+
+ALTER TABLE expansions ADD COLUMN number_scheme TEXT;
@@ -0,0 +1,10 @@
+-- German expansion names and the UI language of every profile
+-- `name_de` is NULL where the German name is unknown or the same as the
+-- English one, the UI shows `name` then. Languages are ISO 639-1 codes.
+
+ALTER TABLE expansions ADD COLUMN name_de TEXT;
+
+CREATE TABLE profile_settings (
+    profile TEXT PRIMARY KEY,
+    language TEXT NOT NULL DEFAULT 'en' CHECK (language IN ('en', 'de'))
+);
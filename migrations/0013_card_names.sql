@@ -0,0 +1,35 @@
+-- Pokemon names in every language, replacing `name_en` and `name_de`
+-- `names` is a JSON object keyed by ISO 639-1 code, e.g.
+-- {"de": "bisasam", "en": "bulbasaur", "ja": "フシギダネ"}. Languages missing
+-- from the species data are left out. The search index gets every name.
+
+ALTER TABLE cards ADD COLUMN names TEXT NOT NULL DEFAULT '{}' CHECK (json_valid(names));
+
+UPDATE cards SET names = json_object('en', name_en, 'de', name_de);
+
+-- The old columns can only be dropped once no trigger mentions them
+
+DROP TRIGGER card_search_cards_insert;
+DROP TRIGGER card_search_cards_update;
+
+ALTER TABLE cards DROP COLUMN name_en;
+ALTER TABLE cards DROP COLUMN name_de;
+
+CREATE TRIGGER card_search_cards_insert AFTER INSERT ON cards
+BEGIN
+    DELETE FROM card_search WHERE rowid = NEW.id;
+    INSERT INTO card_search (rowid, names, expansions, card_numbers)
+    SELECT
+        NEW.id,
+        (SELECT group_concat(value, ' ') FROM json_each(NEW.names)),
+        (SELECT group_concat(e.name || ' ' || e.abbreviation, ' ')
+         FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+         WHERE ce.card_id = NEW.id),
+        (SELECT group_concat(ce.card_number, ' ') FROM card_expansions ce WHERE ce.card_id = NEW.id);
+END;
+
+CREATE TRIGGER card_search_cards_update AFTER UPDATE OF names ON cards
+BEGIN
+    UPDATE card_search SET names = (SELECT group_concat(value, ' ') FROM json_each(NEW.names))
+    WHERE rowid = NEW.id;
+END;
@@ -1,247 +1,50 @@
 use crate::card::Card;
+use crate::config::ClientConfig;
+#[cfg(feature = "server")]
+use crate::db;
 use crate::expansion::{CardExpansion, Expansion};
+#[cfg(feature = "server")]
+use crate::history::{self, Recorder};
+use crate::history::{CardChange, CardEvent};
+use crate::i18n::Language;
+use crate::page_view::PocketSummary;
+use crate::paging::{CardPage, CardPageRequest};
 use crate::{log_db_op, log_ownership_change, log_server_fn};
 use anyhow::Result;
 use dioxus::prelude::*;
-use std::collections::HashMap;
-#[cfg(feature = "dev")]
-use std::fs;
 
+/// Runs migrations and seeds the expansions table
+///
+/// Called once by [`db::init`] before the server accepts requests.
 #[cfg(feature = "server")]
-thread_local! {
-    static DB: std::sync::LazyLock<rusqlite::Connection> = std::sync::LazyLock::new(|| {
-        #[cfg(not(feature = "dev"))]
-        let conn = rusqlite::Connection::open("db/production.db").expect("Failed to open database");
-
-        #[cfg(feature = "dev")]
-        fs::create_dir_all("db_dev/").unwrap();
-        #[cfg(feature = "dev")]
-        let conn = rusqlite::Connection::open("db_dev/dev.db").expect("Failed to open database");
-
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS cards (
-                id INTEGER PRIMARY KEY,
-                name_en TEXT NOT NULL,
-                name_de TEXT NOT NULL,
-                book INTEGER NOT NULL,
-                page INTEGER NOT NULL,
-                side TEXT NOT NULL,
-                entry INTEGER NOT NULL,
-                img_url TEXT NOT NULL,
-                owned BOOLEAN NOT NULL CHECK (owned IN (0,1)),
-                created_at DATETIME DEFAULT (datetime('now', 'localtime'))
-            );
-
-            CREATE TABLE IF NOT EXISTS expansions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                abbreviation TEXT NOT NULL UNIQUE,
-                cards INTEGER NOT NULL CHECK (cards > 0),
-                secret_cards INTEGER NOT NULL CHECK (secret_cards >= 0)
-            );
-
-            CREATE TABLE IF NOT EXISTS card_expansions (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                card_id INTEGER NOT NULL,
-                expansion_id INTEGER NOT NULL,
-                card_number TEXT NOT NULL,
-                rarity TEXT NOT NULL,
-                created_at DATETIME DEFAULT (datetime('now', 'localtime')),
-                FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
-                FOREIGN KEY (expansion_id) REFERENCES expansions(id) ON DELETE CASCADE,
-                UNIQUE(card_id, expansion_id, card_number)
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_card_expansions_card_id ON card_expansions(card_id);
-            CREATE INDEX IF NOT EXISTS idx_card_expansions_expansion_id ON card_expansions(expansion_id);
+pub(crate) fn setup_database(conn: &mut rusqlite::Connection) -> Result<()> {
+    crate::migrations::run(conn)?;
 
-            CREATE TABLE IF NOT EXISTS migrations (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL UNIQUE,
-                applied_at DATETIME DEFAULT (datetime('now', 'localtime'))
-            );",
-        )
-        .expect("failed to create database tables");
+    // Load expansions from SQL file if needed
+    init_expansions_if_needed(conn)?;
 
-        // Run migrations
-        run_migrations(&conn).expect("failed to run migrations");
-
-        // Load expansions from SQL file if needed
-        init_expansions_if_needed(&conn).expect("failed to initialize expansions");
-
-        conn
-    });
+    Ok(())
 }
 
-#[cfg(feature = "server")]
-fn run_migrations(conn: &rusqlite::Connection) -> Result<()> {
-    // Migration 1: Update card_expansions UNIQUE constraint to include card_number
-    let migration_name = "add_card_number_to_unique_constraint";
-
-    // Check if migration has already been applied
-    let already_applied: bool = conn
-        .query_row(
-            "SELECT COUNT(*) FROM migrations WHERE name = ?",
-            [migration_name],
-            |row| {
-                let count: i64 = row.get(0)?;
-                Ok(count > 0)
-            },
-        )
-        .unwrap_or(false);
-
-    if !already_applied {
-        tracing::info!(migration = migration_name, "Running migration");
-
-        // Check if the old constraint exists by trying to insert a duplicate
-        // If it fails with UNIQUE constraint, we need to migrate
-        let needs_migration = conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name='card_expansions'")
-            .and_then(|mut stmt| {
-                stmt.query_row([], |row| {
-                    let sql: String = row.get(0)?;
-                    // Check if the old constraint format exists
-                    Ok(sql.contains("UNIQUE(card_id, expansion_id)")
-                        && !sql.contains("UNIQUE(card_id, expansion_id, card_number)"))
-                })
-            })
-            .unwrap_or(false);
-
-        if needs_migration {
-            tracing::info!("Migrating card_expansions table to new UNIQUE constraint");
-
-            // SQLite doesn't support ALTER TABLE to modify constraints,
-            // so we need to recreate the table
-            conn.execute_batch(
-                "BEGIN TRANSACTION;
-
-                -- Create new table with updated constraint
-                CREATE TABLE card_expansions_new (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    card_id INTEGER NOT NULL,
-                    expansion_id INTEGER NOT NULL,
-                    card_number TEXT NOT NULL,
-                    rarity TEXT NOT NULL,
-                    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
-                    FOREIGN KEY (card_id) REFERENCES cards(id) ON DELETE CASCADE,
-                    FOREIGN KEY (expansion_id) REFERENCES expansions(id) ON DELETE CASCADE,
-                    UNIQUE(card_id, expansion_id, card_number)
-                );
-
-                -- Copy data from old table to new table
-                INSERT INTO card_expansions_new (id, card_id, expansion_id, card_number, rarity, created_at)
-                SELECT id, card_id, expansion_id, card_number, rarity, created_at
-                FROM card_expansions;
-
-                -- Drop old table
-                DROP TABLE card_expansions;
-
-                -- Rename new table to original name
-                ALTER TABLE card_expansions_new RENAME TO card_expansions;
-
-                -- Recreate indexes
-                CREATE INDEX idx_card_expansions_card_id ON card_expansions(card_id);
-                CREATE INDEX idx_card_expansions_expansion_id ON card_expansions(expansion_id);
-
-                COMMIT;"
-            )?;
-
-            tracing::info!("Migration completed successfully");
-        } else {
-            tracing::info!("Migration not needed - schema already up to date");
-        }
-
-        // Mark migration as applied (OR IGNORE handles concurrent execution)
-        conn.execute(
-            "INSERT OR IGNORE INTO migrations (name) VALUES (?)",
-            [migration_name],
-        )?;
-    } else {
-        tracing::debug!(migration = migration_name, "Migration already applied");
-    }
-
-    // Migration 2: Update expansion data (abbreviations, card counts, etc.)
-    let migration_name_2 = "update_expansion_data";
-
-    let already_applied_2: bool = conn
-        .query_row(
-            "SELECT COUNT(*) FROM migrations WHERE name = ?",
-            [migration_name_2],
-            |row| {
-                let count: i64 = row.get(0)?;
-                Ok(count > 0)
-            },
-        )
-        .unwrap_or(false);
-
-    if !already_applied_2 {
-        tracing::info!(migration = migration_name_2, "Running migration");
-
-        conn.execute_batch(
-            "BEGIN TRANSACTION;
-
-            -- Base Series (1999-2000)
-            UPDATE expansions SET abbreviation = 'JU', cards = 64, secret_cards = 0 WHERE name = 'Jungle';
-            UPDATE expansions SET abbreviation = 'FO', cards = 62, secret_cards = 0 WHERE name = 'Fossil';
-            UPDATE expansions SET abbreviation = 'B2', cards = 130, secret_cards = 0 WHERE name = 'Base Set 2';
-            UPDATE expansions SET abbreviation = 'G1', cards = 132, secret_cards = 0 WHERE name = 'Gym Heroes';
-            UPDATE expansions SET abbreviation = 'G2', cards = 132, secret_cards = 0 WHERE name = 'Gym Challenge';
-
-            -- Neo Series (2000-2002)
-            UPDATE expansions SET abbreviation = 'N1', cards = 111, secret_cards = 0 WHERE name = 'Neo Genesis';
-            UPDATE expansions SET abbreviation = 'N2', cards = 75, secret_cards = 0 WHERE name = 'Neo Discovery';
-            UPDATE expansions SET abbreviation = 'N3', cards = 64, secret_cards = 2 WHERE name = 'Neo Revelation';
-            UPDATE expansions SET abbreviation = 'N4', cards = 105, secret_cards = 8 WHERE name = 'Neo Destiny';
-
-            -- EX Series (2003-2007)
-            UPDATE expansions SET abbreviation = 'RG', cards = 112, secret_cards = 4 WHERE name = 'EX FireRed & LeafGreen';
-
-            -- HeartGold & SoulSilver Series (2010-2011)
-            UPDATE expansions SET abbreviation = 'HS', cards = 123, secret_cards = 0 WHERE name = 'HeartGold & SoulSilver';
-
-            -- Sun & Moon Series (2017-2019)
-            UPDATE expansions SET abbreviation = 'SUM', cards = 149, secret_cards = 14 WHERE name = 'Sun & Moon';
-
-            -- Scarlet & Violet Series (2023-2025)
-            UPDATE expansions SET abbreviation = 'JTG', cards = 159, secret_cards = 31 WHERE name = 'Journey Together';
-            UPDATE expansions SET abbreviation = 'DRI', cards = 182, secret_cards = 62 WHERE name = 'Destined Rivals';
-            UPDATE expansions SET abbreviation = 'BLK', cards = 86, secret_cards = 86 WHERE name = 'Black Bolt';
-            UPDATE expansions SET abbreviation = 'WHF', cards = 86, secret_cards = 87 WHERE name = 'White Flare';
-
-            -- Mega Evolution Series (2025-)
-            UPDATE expansions SET abbreviation = 'MEG', cards = 132, secret_cards = 56 WHERE name = 'Mega Evolution';
-            UPDATE expansions SET abbreviation = 'PFL', cards = 94, secret_cards = 36 WHERE name = 'Phantasmal Flames';
-
-            COMMIT;",
-        )?;
-
-        tracing::info!("Expansion data migration completed successfully");
-
-        // Mark migration as applied (OR IGNORE handles concurrent execution)
-        conn.execute(
-            "INSERT OR IGNORE INTO migrations (name) VALUES (?)",
-            [migration_name_2],
-        )?;
-    } else {
-        tracing::debug!(migration = migration_name_2, "Migration already applied");
-    }
-
-    Ok(())
+/// Fresh in-memory database, migrated and seeded like [`setup_database`] does
+#[cfg(all(test, feature = "server"))]
+pub(crate) fn test_connection() -> rusqlite::Connection {
+    let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+    setup_database(&mut conn).unwrap();
+    conn
 }
 
 #[cfg(feature = "server")]
-fn init_expansions_if_needed(conn: &rusqlite::Connection) -> Result<()> {
+fn init_expansions_if_needed(conn: &mut rusqlite::Connection) -> Result<()> {
     // Check if expansions table is empty
     let count: i64 = conn.query_row("SELECT COUNT(*) FROM expansions", [], |row| row.get(0))?;
 
     if count == 0 {
-        tracing::info!("Loading expansions from SQL file");
-        let sql_content = include_str!("../db_seed/expansions.sql");
-        conn.execute_batch(sql_content)?;
-
-        let new_count: i64 =
-            conn.query_row("SELECT COUNT(*) FROM expansions", [], |row| row.get(0))?;
-        tracing::info!(count = new_count, "Loaded expansions");
+        tracing::info!("Loading expansions from the catalog");
+        let catalog = crate::catalog::parse(crate::catalog::EMBEDDED)?;
+        let diff = crate::catalog::diff(conn, &catalog)?;
+        let added = crate::catalog::apply(conn, &diff)?;
+        tracing::info!(count = added, "Loaded expansions");
     }
 
     Ok(())
@@ -251,8 +54,7 @@ fn init_expansions_if_needed(conn: &rusqlite::Connection) -> Result<()> {
 pub async fn validate_password(password: String) -> Result<bool, ServerFnError> {
     log_server_fn!("validate_password", password_length = password.len());
 
-    let correct_password = std::env::var("APP_PASSWORD").unwrap();
-    let is_valid = password == correct_password;
+    let is_valid = password_matches(&password)?;
 
     if is_valid {
         tracing::info!("authentication successful");
@@ -263,6 +65,103 @@ pub async fn validate_password(password: String) -> Result<bool, ServerFnError>
     Ok(is_valid)
 }
 
+/// Whether `password` is the login password
+///
+/// Dev builds may run without one configured and then accept any password,
+/// other builds refuse to start without it, see [`crate::config`].
+#[cfg(feature = "server")]
+fn password_matches(password: &str) -> Result<bool, ServerFnError> {
+    match crate::config::get().auth.password.as_deref() {
+        Some(expected) => Ok(expected == password),
+        None if cfg!(feature = "dev") => Ok(true),
+        None => {
+            tracing::error!("authentication failed - no password configured");
+            Err(ServerFnError::ServerError {
+                message: "No login password is configured on the server".to_string(),
+                code: 500,
+                details: None,
+            })
+        }
+    }
+}
+
+/// Re-checks the login password for admin-only server functions
+///
+/// The login itself only flips a client-side flag, so destructive endpoints
+/// ask for the password again.
+#[cfg(feature = "server")]
+fn require_password(password: &str) -> Result<(), ServerFnError> {
+    if password_matches(password)? {
+        return Ok(());
+    }
+    tracing::warn!("admin request rejected - incorrect password");
+    Err(ServerFnError::ServerError {
+        message: "Incorrect password".to_string(),
+        code: 401,
+        details: None,
+    })
+}
+
+/// Turns a failed card write into a server error
+///
+/// Writes refused because the card moved on keep their [`crate::ownership::EditConflict`]
+/// as status 409, constraint violations are a 422 [`crate::ownership::EditProblem`]
+/// so they are not retried, everything else is a 500 with `details`.
+#[cfg(feature = "server")]
+fn write_error(err: anyhow::Error, details: &str) -> ServerFnError {
+    use crate::ownership::{constraint_problem, EditProblem};
+
+    if let Some(problem) = constraint_problem(&err) {
+        return EditProblem::into_error(&[problem]);
+    }
+    match err.downcast_ref::<crate::ownership::EditConflict>() {
+        Some(conflict) => conflict.into_error(),
+        None => ServerFnError::ServerError {
+            message: err.to_string(),
+            code: 500,
+            details: Some(details.into()),
+        },
+    }
+}
+
+/// Binder layout and name languages the client renders with
+#[server(endpoint = "get_client_config")]
+pub async fn get_client_config() -> Result<ClientConfig, ServerFnError> {
+    log_server_fn!("get_client_config");
+    Ok(crate::config::client())
+}
+
+/// UI language the acting profile chose, `None` if it never chose one
+#[server(endpoint = "get_language_db")]
+pub async fn get_language_db() -> Result<Option<Language>, ServerFnError> {
+    log_server_fn!("get_language_db");
+    let profile = history::current_profile().await;
+    db::run(move |db| crate::i18n::stored_language(db, &profile))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch language from DB".into()),
+        })
+}
+
+/// Remembers the UI language of the acting profile
+#[server(endpoint = "set_language_db")]
+pub async fn set_language_db(language: Language) -> Result<(), ServerFnError> {
+    log_server_fn!("set_language_db", language = language.code());
+    let profile = history::current_profile().await;
+    db::run(move |db| {
+        log_db_op!("UPSERT", table = "profile_settings", profile = &profile);
+        crate::i18n::store_language(db, &profile, language)
+    })
+    .await
+    .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+        message: e.to_string(),
+        code: 500,
+        details: Some("could not store language in DB".into()),
+    })
+}
+
 #[server(endpoint = "get_card_by_id_remote")]
 pub async fn get_card_by_id_remote(id: usize) -> Result<Card, ServerFnError> {
     log_server_fn!("get_card_by_id_remote", card_id = id);
@@ -272,7 +171,7 @@ pub async fn get_card_by_id_remote(id: usize) -> Result<Card, ServerFnError> {
             let result = Card::try_from_index(index).await;
             match &result {
                 Ok(card) => {
-                    tracing::info!(card_id = id, name = %card.name_en.0, "fetched card from remote API")
+                    tracing::info!(card_id = id, name = %card.primary_name(), "fetched card from remote API")
                 }
                 Err(e) => {
                     tracing::error!(card_id = id, error = %e, "failed to fetch card from remote API")
@@ -298,43 +197,47 @@ pub async fn get_card_by_name_remote(name: String) -> Result<Card, ServerFnError
     let result = Card::try_from_name(Name::new(name.as_str())).await;
     match &result {
         Ok(card) => {
-            tracing::info!(card_id = card.index.0, name = %card.name_en.0, "fetched card by name from remote API")
+            tracing::info!(card_id = card.index.0, name = %card.primary_name(), "fetched card by name from remote API")
         }
         Err(e) => {
             tracing::error!(name = %name, error = %e, "failed to fetch card by name from remote API")
         }
     }
-    result.map_err(|e| e.into())
+    result.map_err(|e| match e.downcast::<crate::names::NameNotFound>() {
+        Ok(not_found) => not_found.into_error(),
+        Err(e) => e.into(),
+    })
 }
 
 #[server(endpoint = "get_card_by_id_db")]
 pub async fn get_card_by_id_db(id: usize) -> Result<Card> {
     log_server_fn!("get_card_by_id_db", card_id = id);
 
-    let result = DB.with(|db| {
+    let result = db::run(move |db| {
         log_db_op!("SELECT", table = "cards", card_id = id);
         db.prepare(
-            "SELECT id, name_en, name_de, book, page, side, entry, img_url, owned  FROM cards WHERE id = ?",
+            "SELECT id, names, book, page, side, entry, img_url, owned, version FROM cards WHERE id = ?",
         )?
         .query_row([id], |row| {
             Ok(Card {
                 index: row.get(0)?,
-                name_en: row.get(1)?,
-                name_de: row.get(2)?,
-                book: row.get(3)?,
-                page: row.get(4)?,
-                side: row.get(5)?,
-                entry: row.get(6)?,
-                img_url: row.get(7)?,
-                owned: row.get(8)?,
+                names: row.get(1)?,
+                book: row.get(2)?,
+                page: row.get(3)?,
+                side: row.get(4)?,
+                entry: row.get(5)?,
+                img_url: row.get(6)?,
+                owned: row.get(7)?,
+                version: row.get(8)?,
             })
         })
         .map_err(|e| e.into())
-    });
+    })
+    .await;
 
     match &result {
         Ok(card) => {
-            tracing::debug!(card_id = id, name = %card.name_en.0, "card fetched from database")
+            tracing::debug!(card_id = id, name = %card.primary_name(), "card fetched from database")
         }
         Err(e) => tracing::debug!(card_id = id, error = %e, "card not found in database"),
     }
@@ -346,30 +249,40 @@ pub async fn get_card_by_id_db(id: usize) -> Result<Card> {
 pub async fn get_card_by_name_db(name: String) -> Result<Card> {
     log_server_fn!("get_card_by_name_db", name = &name);
 
-    let result = DB.with(|db| {
-        log_db_op!("SELECT", table = "cards", name = &name);
-        db.prepare(
-            "SELECT id, name_en, name_de, book, page, side, entry, img_url, owned  FROM cards WHERE name_de = ? COLLATE NOCASE OR name_en = ? COLLATE NOCASE",
-        )?
-        .query_row([&name, &name], |row| {
-            Ok(Card {
-                index: row.get(0)?,
-                name_en: row.get(1)?,
-                name_de: row.get(2)?,
-                book: row.get(3)?,
-                page: row.get(4)?,
-                side: row.get(5)?,
-                entry: row.get(6)?,
-                img_url: row.get(7)?,
-                owned: row.get(8)?,
-            })
-        })
-        .map_err(|e| e.into())
-    });
+    let lookup = name.clone();
+    let result = db::run(move |db| {
+        log_db_op!("SELECT", table = "cards", name = &lookup);
+        // Names are compared normalized, which SQL can't do, but the table
+        // holds at most one row per Pokemon
+        let cards = db
+            .prepare(
+                "SELECT id, names, book, page, side, entry, img_url, owned, version FROM cards ORDER BY id",
+            )?
+            .query_map([], |row| {
+                Ok(Card {
+                    index: row.get(0)?,
+                    names: row.get(1)?,
+                    book: row.get(2)?,
+                    page: row.get(3)?,
+                    side: row.get(4)?,
+                    entry: row.get(5)?,
+                    img_url: row.get(6)?,
+                    owned: row.get(7)?,
+                    version: row.get(8)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        cards
+            .into_iter()
+            .find(|card| card.names.matches(&lookup))
+            .ok_or_else(|| anyhow::anyhow!("No card named `{lookup}` in the database"))
+    })
+    .await;
 
     match &result {
         Ok(card) => {
-            tracing::debug!(card_id = card.index.0, name = %card.name_en.0, "card fetched from database by name")
+            tracing::debug!(card_id = card.index.0, name = %card.primary_name(), "card fetched from database by name")
         }
         Err(e) => tracing::debug!(name = %name, error = %e, "card not found in database by name"),
     }
@@ -377,127 +290,187 @@ pub async fn get_card_by_name_db(name: String) -> Result<Card> {
     result
 }
 
-#[server(endpoint = "get_cards_with_timestamp_db")]
-pub async fn get_cards_with_timestamp_db() -> Result<Vec<(Card, String)>> {
-    log_server_fn!("get_cards_with_timestamp_db");
-    let result = DB.with(|db| {
-        log_db_op!("SELECT", table = "cards", operation = "get_all_with_timestamp");
-        Ok(db
-            .prepare(
-                "SELECT id, name_en, name_de, book, page, side, entry, img_url, owned, created_at FROM cards",
-            )?
-            .query_map([], |row| {
-                Ok((
-                    Card {
-                        index: row.get(0)?,
-                        name_en: row.get(1)?,
-                        name_de: row.get(2)?,
-                        book: row.get(3)?,
-                        page: row.get(4)?,
-                        side: row.get(5)?,
-                        entry: row.get(6)?,
-                        img_url: row.get(7)?,
-                        owned: row.get(8)?,
-                    },
-                    row.get(9)?
-                ))
-            })?
-            .collect::<Result<Vec<(Card, String)>, rusqlite::Error>>()?)
-    });
-    match &result {
-        Ok(cards) => tracing::debug!(count = cards.len(), "fetched cards with timestamps"),
-        Err(e) => tracing::error!(error = %e, "failed to fetch cards with timestamps"),
-    }
-    result
-}
-
+/// Stores a card fetched from the API and returns it with its row version
+///
+/// A card that is already stored under another version than `card.version`
+/// is left alone and status 409 returned, see [`crate::ownership::EditConflict::from_error`].
 #[server(endpoint = "save_card_db")]
-pub async fn save_card_db(card: Card) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+pub async fn save_card_db(card: Card) -> Result<Card, ServerFnError> {
+    use crate::ownership::{check_version, stored_card};
+    use rusqlite::{params, OptionalExtension, TransactionBehavior};
 
     log_server_fn!("save_card_db", card_id = card.index.0, owned = card.owned.0);
 
-    let result = DB.with(|f| {
-        log_db_op!("INSERT OR REPLACE", table = "cards", card_id = card.index.0);
-        f.execute(
-            "INSERT OR REPLACE INTO cards (id, name_en, name_de, book, page, side, entry, img_url, owned) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![card.index, card.name_en, card.name_de, card.book, card.page, card.side, card.entry, card.img_url, card.owned],
-        )
-    });
+    let row = card.clone();
+    let profile = history::current_profile().await;
+    let result = db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if let Some(conflict) = check_version(&tx, row.index.0, row.version)? {
+            return Err(conflict.into());
+        }
+        let old_owned = tx
+            .query_row("SELECT owned FROM cards WHERE id = ?", [row.index.0], |row| {
+                row.get::<_, bool>(0)
+            })
+            .optional()?
+            .unwrap_or(false);
+
+        // An upsert instead of INSERT OR REPLACE keeps created_at and the version
+        log_db_op!("UPSERT", table = "cards", card_id = row.index.0);
+        tx.execute(
+            "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET names = excluded.names, book = excluded.book, page = excluded.page,
+                 side = excluded.side, entry = excluded.entry, img_url = excluded.img_url, owned = excluded.owned",
+            params![row.index, row.names, row.book, row.page, row.side, row.entry, row.img_url, row.owned],
+        )?;
+
+        if old_owned != row.owned.0 {
+            log_ownership_change!(row.index.0, old_owned, row.owned.0);
+            Recorder::new(&tx, &profile)?.record(
+                &tx,
+                row.index.0,
+                &CardChange::Ownership {
+                    before: old_owned,
+                    after: row.owned.0,
+                },
+            )?;
+        }
+
+        let stored = stored_card(&tx, row.index.0)?
+            .ok_or_else(|| anyhow::anyhow!("card {} vanished while saving", row.index.0))?;
+        tx.commit()?;
+        Ok(stored)
+    })
+    .await;
+    publish_changes().await;
 
     match result {
-        Ok(_) => {
+        Ok(stored) => {
             tracing::info!(
                 card_id = card.index.0,
                 owned = card.owned.0,
+                version = stored.version,
                 "card saved to database"
             );
-            Ok(())
+            Ok(stored)
         }
         Err(err) => {
             tracing::error!(card_id = card.index.0, error = %err, "failed to save card to database");
-            Err(ServerFnError::ServerError {
-                message: err.to_string(),
-                code: 500,
-                details: Some("could not save card to DB".into()),
-            })
+            Err(write_error(err, "could not save card to DB"))
         }
     }
 }
 
-#[server(endpoint = "get_all_owned_cards_db")]
-pub async fn get_all_owned_cards_db() -> Result<HashMap<usize, Card>, ServerFnError> {
-    log_server_fn!("get_all_owned_cards_db");
+/// One page of stored cards, filtered and sorted
+///
+/// Invalid requests and cursors return status 400, invalid `query` filters
+/// 400 with the parse error, see [`crate::query::QueryError::from_error`].
+#[server(endpoint = "get_cards_page_db")]
+pub async fn get_cards_page_db(request: CardPageRequest) -> Result<CardPage, ServerFnError> {
+    use crate::paging::InvalidCursor;
+    use crate::query::QueryError;
 
-    let result = DB.with(|db| {
-        log_db_op!("SELECT", table = "cards", filter = "owned = 1");
-        let mut stmt = db.prepare(
-            "SELECT id, name_en, name_de, book, page, side, entry, img_url, owned  FROM cards WHERE owned = 1",
-        )?;
+    log_server_fn!(
+        "get_cards_page_db",
+        page = request.page,
+        page_size = request.page_size,
+        sort = request.sort,
+    );
 
-        let cards = stmt
-            .query_map([], |row| {
-                let card = Card {
-                    index: row.get(0)?,
-                    name_en: row.get(1)?,
-                    name_de: row.get(2)?,
-                    book: row.get(3)?,
-                    page: row.get(4)?,
-                    side: row.get(5)?,
-                    entry: row.get(6)?,
-                    img_url: row.get(7)?,
-                    owned: row.get(8)?,
-                };
-                Ok((card.index.0, card))
-            })?
-            .collect::<Result<HashMap<usize, Card>, rusqlite::Error>>()?;
+    if let Some(message) = request.problem() {
+        return Err(ServerFnError::ServerError {
+            message,
+            code: 400,
+            details: None,
+        });
+    }
 
-        Ok(cards)
-    });
+    let result = db::run(move |db| {
+        log_db_op!("SELECT", table = "cards", filter = &request.filter);
+        crate::paging::page(db, &request)
+    })
+    .await;
 
-    match &result {
-        Ok(cards) => {
-            tracing::info!(count = cards.len(), "fetched owned cards from database");
-            result.map_err(|e: anyhow::Error| ServerFnError::ServerError {
+    match result {
+        Ok(page) => {
+            tracing::debug!(
+                count = page.entries.len(),
+                total = page.total,
+                "fetched page of cards from database"
+            );
+            Ok(page)
+        }
+        Err(e) => {
+            if let Some(error) = e.downcast_ref::<QueryError>() {
+                return Err(error.clone().into_error());
+            }
+            if e.downcast_ref::<InvalidCursor>().is_some() {
+                return Err(ServerFnError::ServerError {
+                    message: e.to_string(),
+                    code: 400,
+                    details: None,
+                });
+            }
+            tracing::error!(error = %e, "failed to fetch page of cards from database");
+            Err(ServerFnError::ServerError {
                 message: e.to_string(),
                 code: 500,
-                details: Some("could not fetch owned cards from DB".into()),
+                details: Some("could not fetch cards from DB".into()),
             })
         }
+    }
+}
+
+/// Summaries of the pockets `first..=last`, everything the book view renders
+///
+/// Empty or reversed ranges and ranges over [`crate::page_view::MAX_POCKETS`]
+/// pockets return status 400.
+#[server(endpoint = "get_page_view_db")]
+pub async fn get_page_view_db(
+    first: usize,
+    last: usize,
+) -> Result<Vec<PocketSummary>, ServerFnError> {
+    use crate::page_view::MAX_POCKETS;
+
+    log_server_fn!("get_page_view_db", first = first, last = last);
+
+    if first == 0 || first > last || last - first >= MAX_POCKETS {
+        return Err(ServerFnError::ServerError {
+            message: format!(
+                "Invalid pocket range {first}-{last}, at most {MAX_POCKETS} pockets from 1"
+            ),
+            code: 400,
+            details: None,
+        });
+    }
+
+    let result = db::run(move |db| {
+        log_db_op!("SELECT", table = "cards", first = first, last = last);
+        crate::page_view::summaries(db, first, last)
+    })
+    .await;
+
+    match result {
+        Ok(summaries) => {
+            tracing::debug!(count = summaries.len(), "fetched pocket summaries");
+            Ok(summaries)
+        }
         Err(e) => {
-            tracing::error!(error = %e, "failed to fetch owned cards from database");
+            tracing::error!(error = %e, "failed to fetch pocket summaries from database");
             Err(ServerFnError::ServerError {
                 message: e.to_string(),
                 code: 500,
-                details: Some("could not fetch owned cards from DB".into()),
+                details: Some("could not fetch page view from DB".into()),
             })
         }
     }
 }
 
+/// Updates a stored card if it is still at `card.version`, 409 otherwise
 #[server(endpoint = "update_card_db")]
 pub async fn update_card_db(card: Card) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+    use crate::ownership::check_version;
+    use rusqlite::{params, OptionalExtension, TransactionBehavior};
 
     log_server_fn!(
         "update_card_db",
@@ -505,29 +478,46 @@ pub async fn update_card_db(card: Card) -> Result<(), ServerFnError> {
         owned = card.owned.0
     );
 
-    // Track ownership change - fetch old state first
-    let old_owned = DB.with(|db| {
-        db.query_row(
-            "SELECT owned FROM cards WHERE id = ?",
-            [card.index.0],
-            |row| row.get::<_, bool>(0),
-        )
-        .ok()
-    });
-
-    if let Some(old) = old_owned {
-        if old != card.owned.0 {
-            log_ownership_change!(card.index.0, old, card.owned.0);
+    let row = card.clone();
+    let profile = history::current_profile().await;
+    let result = db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if let Some(conflict) = check_version(&tx, row.index.0, row.version)? {
+            return Err(conflict.into());
         }
-    }
 
-    let result = DB.with(|f| {
-        log_db_op!("UPDATE", table = "cards", card_id = card.index.0);
-        f.execute(
-            "UPDATE cards SET name_en = ?1, name_de = ?2, book = ?3, page = ?4, side = ?5, entry = ?6, img_url = ?7, owned = ?8  WHERE id = ?9",
-            params![card.name_en, card.name_de, card.book, card.page, card.side, card.entry, card.img_url, card.owned, card.index],
-        )
-    });
+        // Track ownership change - fetch old state first
+        let old_owned = tx
+            .query_row("SELECT owned FROM cards WHERE id = ?", [row.index.0], |row| {
+                row.get::<_, bool>(0)
+            })
+            .optional()?;
+
+        log_db_op!("UPDATE", table = "cards", card_id = row.index.0);
+        tx.execute(
+            "UPDATE cards SET names = ?1, book = ?2, page = ?3, side = ?4, entry = ?5, img_url = ?6, owned = ?7  WHERE id = ?8",
+            params![row.names, row.book, row.page, row.side, row.entry, row.img_url, row.owned, row.index],
+        )?;
+
+        if let Some(old) = old_owned {
+            if old != row.owned.0 {
+                log_ownership_change!(row.index.0, old, row.owned.0);
+                Recorder::new(&tx, &profile)?.record(
+                    &tx,
+                    row.index.0,
+                    &CardChange::Ownership {
+                        before: old,
+                        after: row.owned.0,
+                    },
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    })
+    .await;
+    publish_changes().await;
 
     match result {
         Ok(_) => {
@@ -540,11 +530,7 @@ pub async fn update_card_db(card: Card) -> Result<(), ServerFnError> {
         }
         Err(err) => {
             tracing::error!(card_id = card.index.0, error = %err, "failed to update card in database");
-            Err(ServerFnError::ServerError {
-                message: err.to_string(),
-                code: 500,
-                details: Some("could not update card in DB".into()),
-            })
+            Err(write_error(err, "could not update card in DB"))
         }
     }
 }
@@ -554,36 +540,19 @@ pub async fn update_card_db(card: Card) -> Result<(), ServerFnError> {
 #[server(endpoint = "get_all_expansions_db")]
 pub async fn get_all_expansions_db() -> Result<Vec<Expansion>, ServerFnError> {
     log_server_fn!("get_all_expansions_db");
-    DB.with(|db| {
-        let mut stmt = db.prepare(
-            "SELECT id, name, abbreviation, cards, secret_cards FROM expansions ORDER BY name",
-        )?;
-
-        let expansions = stmt
-            .query_map([], |row| {
-                Ok(Expansion {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    abbreviation: row.get(2)?,
-                    cards: row.get(3)?,
-                    secret_cards: row.get(4)?,
-                })
-            })?
-            .collect::<Result<Vec<Expansion>, rusqlite::Error>>()?;
-
-        Ok(expansions)
-    })
-    .map_err(|e: anyhow::Error| ServerFnError::ServerError {
-        message: e.to_string(),
-        code: 500,
-        details: Some("could not fetch expansions from DB".into()),
-    })
+    db::run(|db| crate::expansion_admin::list(db))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch expansions from DB".into()),
+        })
 }
 
 #[server(endpoint = "get_card_expansions_db")]
 pub async fn get_card_expansions_db(card_id: usize) -> Result<Vec<CardExpansion>, ServerFnError> {
     log_server_fn!("get_card_expansions_db", card_id = card_id);
-    DB.with(|db| {
+    db::run(move |db| {
         let mut stmt = db.prepare(
             "SELECT id, card_id, expansion_id, card_number, rarity FROM card_expansions WHERE card_id = ?",
         )?;
@@ -602,6 +571,7 @@ pub async fn get_card_expansions_db(card_id: usize) -> Result<Vec<CardExpansion>
 
         Ok(card_expansions)
     })
+    .await
     .map_err(|e: anyhow::Error| ServerFnError::ServerError {
         message: e.to_string(),
         code: 500,
@@ -609,17 +579,27 @@ pub async fn get_card_expansions_db(card_id: usize) -> Result<Vec<CardExpansion>
     })
 }
 
+/// Adds a print to a card that is still at `card_version`, 409 otherwise
 #[server(endpoint = "save_card_expansion_db")]
-pub async fn save_card_expansion_db(card_expansion: CardExpansion) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+pub async fn save_card_expansion_db(
+    card_expansion: CardExpansion,
+    card_version: u32,
+) -> Result<(), ServerFnError> {
+    use crate::ownership::check_version;
+    use rusqlite::{params, TransactionBehavior};
 
     log_server_fn!(
         "save_card_expansion_db",
         card_id = card_expansion.card_id,
         expansion_id = card_expansion.expansion_id
     );
-    DB.with(|f| {
-        f.execute(
+    let profile = history::current_profile().await;
+    db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if let Some(conflict) = check_version(&tx, card_expansion.card_id, card_version)? {
+            return Err(conflict.into());
+        }
+        tx.execute(
             "INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES (?1, ?2, ?3, ?4)",
             params![
                 card_expansion.card_id,
@@ -627,19 +607,37 @@ pub async fn save_card_expansion_db(card_expansion: CardExpansion) -> Result<(),
                 card_expansion.card_number,
                 card_expansion.rarity
             ],
-        )
+        )?;
+
+        let inserted = CardExpansion {
+            id: Some(tx.last_insert_rowid() as usize),
+            ..card_expansion
+        };
+        Recorder::new(&tx, &profile)?.record(
+            &tx,
+            inserted.card_id,
+            &CardChange::ExpansionAdded {
+                expansion: inserted,
+            },
+        )?;
+
+        tx.commit()?;
+        Ok(())
     })
-    .map_err(|err| ServerFnError::ServerError {
-        message: err.to_string(),
-        code: 500,
-        details: Some("could not save card expansion to DB".into()),
-    })?;
+    .await
+    .map_err(|err| write_error(err, "could not save card expansion to DB"))?;
+    publish_changes().await;
     Ok(())
 }
 
+/// Changes a print of a card that is still at `card_version`, 409 otherwise
 #[server(endpoint = "update_card_expansion_db")]
-pub async fn update_card_expansion_db(card_expansion: CardExpansion) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+pub async fn update_card_expansion_db(
+    card_expansion: CardExpansion,
+    card_version: u32,
+) -> Result<(), ServerFnError> {
+    use crate::ownership::check_version;
+    use rusqlite::{params, TransactionBehavior};
 
     log_server_fn!(
         "update_card_expansion_db",
@@ -655,58 +653,906 @@ pub async fn update_card_expansion_db(card_expansion: CardExpansion) -> Result<(
         });
     }
 
-    DB.with(|f| {
-        f.execute(
+    let profile = history::current_profile().await;
+    db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let id = card_expansion.id.unwrap();
+        let before = history::card_expansion(&tx, id)?;
+        let card_id = before.as_ref().map_or(card_expansion.card_id, |b| b.card_id);
+        if let Some(conflict) = check_version(&tx, card_id, card_version)? {
+            return Err(conflict.into());
+        }
+
+        tx.execute(
             "UPDATE card_expansions SET expansion_id = ?1, card_number = ?2, rarity = ?3 WHERE id = ?4",
             params![
                 card_expansion.expansion_id,
                 card_expansion.card_number,
                 card_expansion.rarity,
-                card_expansion.id.unwrap(),
+                id,
             ],
-        )
+        )?;
+
+        if let Some(before) = before {
+            let after = CardExpansion {
+                card_id: before.card_id,
+                ..card_expansion
+            };
+            if before != after {
+                Recorder::new(&tx, &profile)?.record(
+                    &tx,
+                    before.card_id,
+                    &CardChange::ExpansionUpdated { before, after },
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
     })
-    .map_err(|err| ServerFnError::ServerError {
-        message: err.to_string(),
-        code: 500,
-        details: Some("could not update card expansion in DB".into()),
-    })?;
+    .await
+    .map_err(|err| write_error(err, "could not update card expansion in DB"))?;
+    publish_changes().await;
     Ok(())
 }
 
+/// Removes a print of a card that is still at `card_version`, 409 otherwise
 #[server(endpoint = "delete_card_expansion_db")]
-pub async fn delete_card_expansion_db(id: usize) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+pub async fn delete_card_expansion_db(id: usize, card_version: u32) -> Result<(), ServerFnError> {
+    use crate::ownership::check_version;
+    use rusqlite::{params, TransactionBehavior};
 
     log_server_fn!("delete_card_expansion_db", expansion_id = id);
     tracing::info!(expansion_id = id, "deleting card expansion");
-    DB.with(|f| f.execute("DELETE FROM card_expansions WHERE id = ?1", params![id]))
-        .map_err(|err| ServerFnError::ServerError {
-            message: err.to_string(),
-            code: 500,
-            details: Some("could not delete card expansion from DB".into()),
-        })?;
+    let profile = history::current_profile().await;
+    db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let before = history::card_expansion(&tx, id)?;
+        if let Some(expansion) = &before {
+            if let Some(conflict) = check_version(&tx, expansion.card_id, card_version)? {
+                return Err(conflict.into());
+            }
+        }
+        tx.execute("DELETE FROM card_expansions WHERE id = ?1", params![id])?;
+
+        if let Some(expansion) = before {
+            Recorder::new(&tx, &profile)?.record(
+                &tx,
+                expansion.card_id,
+                &CardChange::ExpansionRemoved { expansion },
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| write_error(err, "could not delete card expansion from DB"))?;
+    publish_changes().await;
     Ok(())
 }
 
+/// Removes all prints of a card that is still at `card_version`, 409 otherwise
 #[server(endpoint = "delete_all_card_expansions_db")]
-pub async fn delete_all_card_expansions_db(card_id: usize) -> Result<(), ServerFnError> {
-    use rusqlite::params;
+pub async fn delete_all_card_expansions_db(
+    card_id: usize,
+    card_version: u32,
+) -> Result<(), ServerFnError> {
+    use crate::ownership::check_version;
+    use rusqlite::{params, TransactionBehavior};
 
     log_server_fn!("delete_all_card_expansions_db", card_id = card_id);
     tracing::info!(card_id = card_id, "deleting all expansions for card");
-    DB.with(|f| {
-        f.execute(
+    let profile = history::current_profile().await;
+    db::run(move |f| {
+        let tx = f.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if let Some(conflict) = check_version(&tx, card_id, card_version)? {
+            return Err(conflict.into());
+        }
+        let removed: Vec<usize> = tx
+            .prepare("SELECT id FROM card_expansions WHERE card_id = ?1 ORDER BY id")?
+            .query_map([card_id], |row| row.get(0))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        let removed = removed
+            .into_iter()
+            .map(|id| history::card_expansion(&tx, id))
+            .collect::<Result<Vec<_>>>()?;
+
+        tx.execute(
             "DELETE FROM card_expansions WHERE card_id = ?1",
             params![card_id],
-        )
+        )?;
+
+        if !removed.is_empty() {
+            let recorder = Recorder::new(&tx, &profile)?;
+            for expansion in removed.into_iter().flatten() {
+                recorder.record(&tx, card_id, &CardChange::ExpansionRemoved { expansion })?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    })
+    .await
+    .map_err(|err| write_error(err, "could not delete card expansions from DB"))?;
+    publish_changes().await;
+    Ok(())
+}
+
+// ==================== Expansion Management Server Functions ====================
+
+use crate::expansion_admin::{ExpansionDraft, ExpansionEvent};
+
+/// Number of changes the expansion page lists
+#[cfg(feature = "server")]
+const EXPANSION_CHANGES: usize = 50;
+
+/// Maps management errors, names or abbreviations in use become 409
+#[cfg(feature = "server")]
+fn expansion_error(e: anyhow::Error) -> ServerFnError {
+    let code = if e
+        .downcast_ref::<crate::expansion_admin::DuplicateExpansion>()
+        .is_some()
+    {
+        409
+    } else {
+        tracing::error!(error = %e, "expansion change failed");
+        500
+    };
+    ServerFnError::ServerError {
+        message: e.to_string(),
+        code,
+        details: None,
+    }
+}
+
+#[cfg(feature = "server")]
+fn checked_draft(draft: &ExpansionDraft) -> Result<ExpansionDraft, ServerFnError> {
+    draft.check().map_err(|message| ServerFnError::ServerError {
+        message,
+        code: 422,
+        details: None,
+    })
+}
+
+#[cfg(feature = "server")]
+fn no_expansion(id: usize) -> ServerFnError {
+    ServerFnError::ServerError {
+        message: format!("No expansion with ID {id}"),
+        code: 404,
+        details: None,
+    }
+}
+
+/// Adds an expansion
+///
+/// Incomplete drafts return status 422, names or abbreviations already in
+/// use 409.
+#[server(endpoint = "create_expansion_db")]
+pub async fn create_expansion_db(
+    password: String,
+    draft: ExpansionDraft,
+) -> Result<Expansion, ServerFnError> {
+    log_server_fn!("create_expansion_db", name = &draft.name);
+    require_password(&password)?;
+    let draft = checked_draft(&draft)?;
+
+    let profile = history::current_profile().await;
+    db::run(move |db| {
+        log_db_op!("INSERT", table = "expansions", name = &draft.name);
+        let expansion = crate::expansion_admin::create(db, &draft, &profile)?;
+        crate::sync::publish_expansion(&profile, &expansion);
+        Ok(expansion)
+    })
+    .await
+    .map_err(expansion_error)
+}
+
+/// Replaces name, abbreviation and card counts of an expansion
+#[server(endpoint = "update_expansion_db")]
+pub async fn update_expansion_db(
+    password: String,
+    id: usize,
+    draft: ExpansionDraft,
+) -> Result<Expansion, ServerFnError> {
+    log_server_fn!("update_expansion_db", id = id, name = &draft.name);
+    require_password(&password)?;
+    let draft = checked_draft(&draft)?;
+
+    let profile = history::current_profile().await;
+    db::run(move |db| {
+        log_db_op!("UPDATE", table = "expansions", id = id);
+        let expansion = crate::expansion_admin::update(db, id, &draft, &profile)?;
+        if let Some(expansion) = &expansion {
+            crate::sync::publish_expansion(&profile, expansion);
+        }
+        Ok(expansion)
+    })
+    .await
+    .map_err(expansion_error)?
+    .ok_or_else(|| no_expansion(id))
+}
+
+/// Archives an expansion, or restores it with `archived: false`
+#[server(endpoint = "set_expansion_archived_db")]
+pub async fn set_expansion_archived_db(
+    password: String,
+    id: usize,
+    archived: bool,
+) -> Result<Expansion, ServerFnError> {
+    log_server_fn!("set_expansion_archived_db", id = id, archived = archived);
+    require_password(&password)?;
+
+    let profile = history::current_profile().await;
+    db::run(move |db| {
+        log_db_op!("UPDATE", table = "expansions", id = id, archived = archived);
+        let expansion = crate::expansion_admin::set_archived(db, id, archived, &profile)?;
+        if let Some(expansion) = &expansion {
+            crate::sync::publish_expansion(&profile, expansion);
+        }
+        Ok(expansion)
+    })
+    .await
+    .map_err(expansion_error)?
+    .ok_or_else(|| no_expansion(id))
+}
+
+/// The latest changes to any expansion, newest first
+#[server(endpoint = "get_expansion_changes_db")]
+pub async fn get_expansion_changes_db() -> Result<Vec<ExpansionEvent>, ServerFnError> {
+    log_server_fn!("get_expansion_changes_db");
+    db::run(|db| crate::expansion_admin::changes(db, EXPANSION_CHANGES))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch expansion changes from DB".into()),
+        })
+}
+
+// ==================== Search Server Functions ====================
+
+use crate::search::SearchHit;
+
+/// Ranked prefix search over stored names, expansions and card numbers
+#[server(endpoint = "search_cards_db")]
+pub async fn search_cards_db(query: String) -> Result<Vec<SearchHit>, ServerFnError> {
+    log_server_fn!("search_cards_db", query = &query);
+
+    let lookup = query.clone();
+    db::run(move |db| {
+        log_db_op!("SELECT", table = "card_search", query = &lookup);
+        crate::search::search(db, &lookup, crate::search::MAX_SUGGESTIONS)
+    })
+    .await
+    .map_err(|e| {
+        tracing::error!(query = %query, error = %e, "card search failed");
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not search cards in DB".into()),
+        }
+    })
+}
+
+use crate::query::QueryResult;
+
+/// Filters every Pokedex number with the search box query language
+///
+/// Invalid queries return status 400 with the offending token as JSON
+/// `details`, read it with [`crate::query::QueryError::from_error`].
+#[server(endpoint = "query_cards_db")]
+pub async fn query_cards_db(query: String) -> Result<QueryResult, ServerFnError> {
+    log_server_fn!("query_cards_db", query = &query);
+
+    let expr = crate::query::parse(&query).map_err(|e| {
+        tracing::debug!(query = %query, error = %e, "invalid card query");
+        e.into_error()
+    })?;
+
+    let input = query.clone();
+    db::run(move |db| {
+        crate::query::check_types(db, &input)?;
+        log_db_op!("SELECT", table = "cards");
+        crate::query::run(db, &expr, crate::query::MAX_QUERY_RESULTS)
+    })
+    .await
+    .map_err(|e| match e.downcast::<crate::query::QueryError>() {
+        Ok(e) => e.into_error(),
+        Err(e) => {
+            tracing::error!(query = %query, error = %e, "card query failed");
+            ServerFnError::ServerError {
+                message: e.to_string(),
+                code: 500,
+                details: Some("could not query cards in DB".into()),
+            }
+        }
     })
-    .map_err(|err| ServerFnError::ServerError {
-        message: err.to_string(),
+}
+
+// ==================== Saved Search Server Functions ====================
+
+use crate::saved_queries::{SavedBinder, SavedQuery, SavedQueryCount};
+
+/// Saved searches with the number of cards each matches right now
+#[server(endpoint = "get_saved_query_counts_db")]
+pub async fn get_saved_query_counts_db() -> Result<Vec<SavedQueryCount>, ServerFnError> {
+    log_server_fn!("get_saved_query_counts_db");
+    db::run(|db| crate::saved_queries::counts(db))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not count saved searches in DB".into()),
+        })
+}
+
+#[server(endpoint = "list_saved_queries_db")]
+pub async fn list_saved_queries_db() -> Result<Vec<SavedQuery>, ServerFnError> {
+    log_server_fn!("list_saved_queries_db");
+    db::run(|db| crate::saved_queries::list(db))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch saved searches from DB".into()),
+        })
+}
+
+/// Number of cards one saved search matches right now
+#[server(endpoint = "get_saved_query_count_db")]
+pub async fn get_saved_query_count_db(id: usize) -> Result<SavedQueryCount, ServerFnError> {
+    log_server_fn!("get_saved_query_count_db", id = id);
+    let count = db::run(move |db| {
+        let Some(saved) = crate::saved_queries::get(db, id)? else {
+            return Ok(None);
+        };
+        let total = crate::saved_queries::matches(db, &saved, 0)?.total;
+        Ok(Some(SavedQueryCount { saved, total }))
+    })
+    .await
+    .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+        message: e.to_string(),
         code: 500,
-        details: Some("could not delete card expansions from DB".into()),
+        details: Some("could not count saved search in DB".into()),
     })?;
-    Ok(())
+
+    count.ok_or_else(|| ServerFnError::ServerError {
+        message: format!("No saved search with ID {id}"),
+        code: 404,
+        details: None,
+    })
+}
+
+/// Stores a named filter query
+///
+/// Invalid queries return status 400 like [`query_cards_db`], empty or too
+/// long names 422 and names already in use 409.
+#[server(endpoint = "save_query_db")]
+pub async fn save_query_db(name: String, query: String) -> Result<SavedQuery, ServerFnError> {
+    use crate::saved_queries::{check_name, DuplicateName};
+
+    log_server_fn!("save_query_db", name = &name, query = &query);
+
+    let name = check_name(&name).map_err(|message| ServerFnError::ServerError {
+        message,
+        code: 422,
+        details: None,
+    })?;
+    let query = query.trim().to_string();
+    crate::query::parse(&query).map_err(|e| e.into_error())?;
+
+    db::run(move |db| {
+        log_db_op!("INSERT", table = "saved_queries", name = &name);
+        crate::saved_queries::create(db, &name, &query)
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        let code = if e.downcast_ref::<DuplicateName>().is_some() {
+            409
+        } else {
+            tracing::error!(error = %e, "failed to save search");
+            500
+        };
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code,
+            details: None,
+        }
+    })
+}
+
+#[server(endpoint = "delete_saved_query_db")]
+pub async fn delete_saved_query_db(id: usize) -> Result<(), ServerFnError> {
+    log_server_fn!("delete_saved_query_db", id = id);
+    let deleted = db::run(move |db| {
+        log_db_op!("DELETE", table = "saved_queries", id = id);
+        crate::saved_queries::delete(db, id)
+    })
+    .await
+    .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+        message: e.to_string(),
+        code: 500,
+        details: Some("could not delete saved search from DB".into()),
+    })?;
+
+    if deleted {
+        Ok(())
+    } else {
+        Err(ServerFnError::ServerError {
+            message: format!("No saved search with ID {id}"),
+            code: 404,
+            details: None,
+        })
+    }
+}
+
+/// A saved search and the cards it matches, as shown by its virtual binder
+#[server(endpoint = "get_saved_binder_db")]
+pub async fn get_saved_binder_db(id: usize) -> Result<SavedBinder, ServerFnError> {
+    log_server_fn!("get_saved_binder_db", id = id);
+    let binder = db::run(move |db| {
+        let Some(saved) = crate::saved_queries::get(db, id)? else {
+            return Ok(None);
+        };
+        let result = crate::saved_queries::matches(db, &saved, crate::query::MAX_QUERY_RESULTS)?;
+        Ok(Some(SavedBinder { saved, result }))
+    })
+    .await
+    .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+        message: e.to_string(),
+        code: 500,
+        details: Some("could not load saved search from DB".into()),
+    })?;
+
+    binder.ok_or_else(|| ServerFnError::ServerError {
+        message: format!("No saved search with ID {id}"),
+        code: 404,
+        details: None,
+    })
+}
+
+// ==================== Ownership Server Functions ====================
+
+use crate::bulk::{BulkReport, BulkRequest};
+use crate::ownership::{OwnershipEdit, SavedOwnership};
+
+/// Saves a card and its complete expansion list in one transaction
+///
+/// Rejected edits return status 422 with the problems as JSON `details`,
+/// read them with [`crate::ownership::EditProblem::from_error`]. Edits based
+/// on an outdated card version return 409, see [`crate::ownership::EditConflict::from_error`].
+#[server(endpoint = "save_card_ownership_db")]
+pub async fn save_card_ownership_db(edit: OwnershipEdit) -> Result<SavedOwnership, ServerFnError> {
+    use crate::ownership::{EditProblem, SaveOutcome};
+
+    log_server_fn!(
+        "save_card_ownership_db",
+        card_id = edit.card.index.0,
+        owned = edit.card.owned.0,
+        expansions = edit.expansions.len()
+    );
+
+    let card_id = edit.card.index.0;
+    let profile = history::current_profile().await;
+    let outcome = db::run(move |db| crate::ownership::save(db, edit, &profile))
+        .await
+        .map_err(|e: anyhow::Error| {
+            tracing::error!(card_id, error = %e, "failed to save card ownership");
+            write_error(e, "could not save card ownership to DB")
+        })?;
+    publish_changes().await;
+
+    match outcome {
+        SaveOutcome::Saved(saved) => {
+            tracing::info!(
+                card_id,
+                owned = saved.card.owned.0,
+                expansions = saved.expansions.len(),
+                "card ownership saved"
+            );
+            Ok(saved)
+        }
+        SaveOutcome::Rejected(problems) => {
+            tracing::warn!(card_id, problems = ?problems, "card ownership edit rejected");
+            Err(EditProblem::into_error(&problems))
+        }
+        SaveOutcome::Conflict(conflict) => {
+            tracing::warn!(
+                card_id,
+                expected = conflict.expected,
+                current = conflict.current.card.version,
+                "card ownership edit is based on an outdated version"
+            );
+            Err(conflict.into_error())
+        }
+    }
+}
+
+/// Marks many cards owned or unowned in one transaction
+///
+/// Cards that fail are reported in the result and don't roll back the
+/// others. Requests over [`crate::bulk::MAX_BULK_CARDS`] are rejected.
+#[server(endpoint = "bulk_update_ownership_db")]
+pub async fn bulk_update_ownership_db(request: BulkRequest) -> Result<BulkReport, ServerFnError> {
+    use crate::card::Index;
+
+    log_server_fn!(
+        "bulk_update_ownership_db",
+        cards = request.indices.len(),
+        action = request.action
+    );
+
+    if let Some(problem) = request.problem() {
+        tracing::warn!(problem = %problem, "bulk ownership request rejected");
+        return Err(ServerFnError::ServerError {
+            message: problem,
+            code: 400,
+            details: None,
+        });
+    }
+
+    let db_error = |e: anyhow::Error| {
+        tracing::error!(error = %e, "failed to apply bulk ownership update");
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not apply bulk ownership update to DB".into()),
+        }
+    };
+
+    // Card data is fetched before the transaction starts, so the write
+    // lock is not held while waiting on PokeAPI
+    let mut fetched = std::collections::HashMap::new();
+    if let crate::bulk::BulkAction::MarkOwned { .. } = request.action {
+        let lookup = request.clone();
+        let missing = db::run(move |db| crate::bulk::missing(db, &lookup))
+            .await
+            .map_err(db_error)?;
+        for index in missing {
+            let result = match Index::try_new(index) {
+                Ok(index) => Card::try_from_index(index).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &result {
+                tracing::warn!(card_id = index, error = %e, "failed to fetch card for bulk update");
+            }
+            fetched.insert(
+                index,
+                result.map_err(|e| format!("Could not fetch card data: {e}")),
+            );
+        }
+    }
+
+    let profile = history::current_profile().await;
+    let report = db::run(move |db| crate::bulk::apply(db, &request, &fetched, &profile))
+        .await
+        .map_err(db_error)?;
+    publish_changes().await;
+
+    tracing::info!(
+        changed = report.changed(),
+        unchanged = report.unchanged(),
+        failed = report.failures().count(),
+        "bulk ownership update applied"
+    );
+    Ok(report)
+}
+
+// ==================== Offline Server Functions ====================
+
+use crate::offline::Snapshot;
+
+/// The whole collection in one response, kept by clients for offline use
+#[server(endpoint = "get_offline_snapshot_db")]
+pub async fn get_offline_snapshot_db() -> Result<Snapshot, ServerFnError> {
+    log_server_fn!("get_offline_snapshot_db");
+
+    db::run(|db| {
+        log_db_op!("SELECT", table = "cards");
+        crate::offline::snapshot(db)
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        tracing::error!(error = %e, "failed to build offline snapshot");
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not read collection for offline use".into()),
+        }
+    })
+}
+
+// ==================== History Server Functions ====================
+
+/// Recorded changes of a card, newest first
+#[server(endpoint = "get_card_history_db")]
+pub async fn get_card_history_db(card_id: usize) -> Result<Vec<CardEvent>, ServerFnError> {
+    log_server_fn!("get_card_history_db", card_id = card_id);
+    db::run(move |db| history::timeline(db, card_id))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch card history from DB".into()),
+        })
+}
+
+/// Reverts the last recorded change of a card
+///
+/// Returns the card as it is after the undo, or `None` if there was nothing
+/// left to undo.
+#[server(endpoint = "undo_last_card_change_db")]
+pub async fn undo_last_card_change_db(card_id: usize) -> Result<Option<Card>, ServerFnError> {
+    log_server_fn!("undo_last_card_change_db", card_id = card_id);
+
+    let profile = history::current_profile().await;
+    let undone = db::run(move |db| history::undo_last(db, card_id, &profile))
+        .await
+        .map_err(|e: anyhow::Error| {
+            let code = if e.downcast_ref::<history::UndoConflict>().is_some() {
+                409
+            } else {
+                500
+            };
+            tracing::error!(card_id, error = %e, code, "failed to undo card change");
+            ServerFnError::ServerError {
+                message: e.to_string(),
+                code,
+                details: None,
+            }
+        })?;
+    publish_changes().await;
+
+    match undone {
+        Some(_) => {
+            get_card_by_id_db(card_id)
+                .await
+                .map(Some)
+                .map_err(|e| ServerFnError::ServerError {
+                    message: e.to_string(),
+                    code: 500,
+                    details: Some("could not reload card after undo".into()),
+                })
+        }
+        None => Ok(None),
+    }
+}
+
+// ==================== Tag Server Functions ====================
+
+/// Tags of a stored card, sorted
+#[server(endpoint = "get_card_tags_db")]
+pub async fn get_card_tags_db(card_id: usize) -> Result<Vec<String>, ServerFnError> {
+    log_server_fn!("get_card_tags_db", card_id = card_id);
+    db::run(move |db| crate::tags::get(db, card_id))
+        .await
+        .map_err(|e: anyhow::Error| ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not fetch card tags from DB".into()),
+        })
+}
+
+/// Replaces the tags of a stored card and returns them as stored
+///
+/// Invalid tags return status 422, cards that are not stored 404.
+#[server(endpoint = "set_card_tags_db")]
+pub async fn set_card_tags_db(
+    card_id: usize,
+    tags: Vec<String>,
+) -> Result<Vec<String>, ServerFnError> {
+    use crate::tags::{check_tags, CardNotStored};
+
+    log_server_fn!("set_card_tags_db", card_id = card_id, tags = tags.len());
+
+    let tags = check_tags(&tags).map_err(|message| ServerFnError::ServerError {
+        message,
+        code: 422,
+        details: None,
+    })?;
+
+    db::run(move |db| {
+        log_db_op!("REPLACE", table = "card_tags", card_id = card_id);
+        crate::tags::set(db, card_id, &tags)
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        let code = if e.downcast_ref::<CardNotStored>().is_some() {
+            404
+        } else {
+            tracing::error!(card_id, error = %e, "failed to save card tags");
+            500
+        };
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code,
+            details: None,
+        }
+    })
+}
+
+// ==================== Live Sync Server Functions ====================
+
+use crate::sync::SyncEvent;
+use dioxus::fullstack::ServerEvents;
+
+/// Streams the history batches committed by this request to open clients
+#[cfg(feature = "server")]
+async fn publish_changes() {
+    match db::run(|db| crate::sync::publish(db)).await {
+        Ok(0) => {}
+        Ok(count) => tracing::debug!(count, "published changes to live sync"),
+        Err(e) => tracing::warn!(error = %e, "failed to publish changes to live sync"),
+    }
+}
+
+/// Server-sent events for every change to the collection, by any client
+#[get("/api/live_sync")]
+pub async fn live_sync() -> Result<ServerEvents<SyncEvent>, ServerFnError> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    log_server_fn!("live_sync");
+    let mut events = crate::sync::subscribe();
+
+    Ok(ServerEvents::new(move |mut tx| async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "live sync client lagged behind");
+                    SyncEvent::Lagged
+                }
+                Err(RecvError::Closed) => break,
+            };
+            // Fails once the client disconnected
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+// ==================== Backup Server Functions ====================
+
+use crate::backup::{BackupEntry, BackupFile, BackupStatus, RestoreReport};
+
+/// Maps backup errors, rejected uploads become client errors
+#[cfg(feature = "server")]
+fn backup_error(e: anyhow::Error) -> ServerFnError {
+    let code = if e.downcast_ref::<crate::backup::InvalidSnapshot>().is_some() {
+        400
+    } else {
+        500
+    };
+    tracing::error!(error = %e, code, "backup operation failed");
+    ServerFnError::ServerError {
+        message: format!("{e:#}"),
+        code,
+        details: None,
+    }
+}
+
+/// Download a snapshot of the database
+///
+/// Without `file_name` a fresh snapshot of the live database is taken,
+/// otherwise the named file from the backup directory is returned.
+#[server(endpoint = "download_backup")]
+pub async fn download_backup(
+    password: String,
+    file_name: Option<String>,
+) -> Result<BackupFile, ServerFnError> {
+    log_server_fn!("download_backup", file_name = file_name);
+    require_password(&password)?;
+
+    db::run(move |db| match file_name {
+        Some(file_name) => crate::backup::download_stored(&file_name),
+        None => crate::backup::download_snapshot(db),
+    })
+    .await
+    .map_err(backup_error)
+}
+
+/// List the snapshots stored on the server, newest first
+#[server(endpoint = "list_backups")]
+pub async fn list_backups(password: String) -> Result<Vec<BackupEntry>, ServerFnError> {
+    log_server_fn!("list_backups");
+    require_password(&password)?;
+
+    db::run(|_| crate::backup::list())
+        .await
+        .map_err(backup_error)
+}
+
+/// Retention settings and the outcome of the last scheduled backup
+#[server(endpoint = "get_backup_status")]
+pub async fn get_backup_status() -> Result<BackupStatus, ServerFnError> {
+    log_server_fn!("get_backup_status");
+    Ok(crate::backup::status())
+}
+
+/// Replace the database with an uploaded snapshot
+///
+/// The current database is kept as a `pre-restore` snapshot first.
+#[server(endpoint = "restore_backup")]
+pub async fn restore_backup(
+    password: String,
+    upload: BackupFile,
+) -> Result<RestoreReport, ServerFnError> {
+    log_server_fn!(
+        "restore_backup",
+        file_name = upload.file_name,
+        encoded_size = upload.data.len()
+    );
+    require_password(&password)?;
+
+    let profile = history::current_profile().await;
+    db::run(move |db| {
+        let report = crate::backup::restore(db, &upload)?;
+        if let Err(e) = crate::sync::publish_restore(db, &profile) {
+            tracing::warn!(error = %e, "failed to announce restore to live sync");
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(backup_error)
+}
+
+// ==================== Integrity Server Functions ====================
+
+use crate::integrity::{IntegrityReport, ViolationKind};
+
+/// List every row that disagrees with the layout or with other tables
+#[server(endpoint = "check_integrity_db")]
+pub async fn check_integrity_db(password: String) -> Result<IntegrityReport, ServerFnError> {
+    log_server_fn!("check_integrity_db");
+    require_password(&password)?;
+
+    db::run(|db| {
+        log_db_op!("SELECT", table = "cards");
+        crate::integrity::check(db)
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        tracing::error!(error = %e, "integrity check failed");
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not check the database".into()),
+        }
+    })
+}
+
+/// Fix every violation of one kind, returns how many rows changed
+#[server(endpoint = "repair_integrity_db")]
+pub async fn repair_integrity_db(
+    password: String,
+    kind: ViolationKind,
+) -> Result<usize, ServerFnError> {
+    log_server_fn!("repair_integrity_db", kind = kind);
+    require_password(&password)?;
+
+    if !kind.repairable() {
+        return Err(ServerFnError::ServerError {
+            message: format!("{} can't be repaired automatically", kind.title()),
+            code: 400,
+            details: None,
+        });
+    }
+
+    db::run(move |db| {
+        log_db_op!("REPAIR", table = "cards", kind = kind);
+        crate::integrity::repair(db, kind)
+    })
+    .await
+    .map_err(|e: anyhow::Error| {
+        tracing::error!(error = %e, kind = ?kind, "integrity repair failed");
+        ServerFnError::ServerError {
+            message: e.to_string(),
+            code: 500,
+            details: Some("could not repair the database".into()),
+        }
+    })
 }
 
 // ==================== Client Logging ====================
@@ -738,7 +1584,7 @@ use crate::statistics::{CollectionStats, ExpansionStats};
 pub async fn get_expansion_statistics_db() -> Result<Vec<ExpansionStats>, ServerFnError> {
     log_server_fn!("get_expansion_statistics_db");
 
-    DB.with(|db| {
+    db::run(|db| {
         log_db_op!(
             "SELECT",
             table = "expansions, card_expansions",
@@ -747,7 +1593,9 @@ pub async fn get_expansion_statistics_db() -> Result<Vec<ExpansionStats>, Server
 
         let mut stmt = db.prepare(
             "SELECT
-                e.id, e.name, e.abbreviation, e.cards, e.secret_cards,
+                e.id, e.name, e.abbreviation, e.cards, e.secret_cards, e.archived,
+                e.series, e.release_date, e.region, e.set_code, e.symbol_url, e.number_scheme,
+                e.name_de,
                 COUNT(DISTINCT CASE WHEN c.owned = 1 THEN ce.card_id END) as owned_count
              FROM expansions e
              LEFT JOIN card_expansions ce ON e.id = ce.expansion_id
@@ -758,14 +1606,8 @@ pub async fn get_expansion_statistics_db() -> Result<Vec<ExpansionStats>, Server
 
         let stats = stmt
             .query_map([], |row| {
-                let expansion = Expansion {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    abbreviation: row.get(2)?,
-                    cards: row.get(3)?,
-                    secret_cards: row.get(4)?,
-                };
-                let owned_count: usize = row.get(5)?;
+                let expansion = crate::expansion_admin::read_expansion(row)?;
+                let owned_count: usize = row.get(13)?;
 
                 Ok(ExpansionStats::new(expansion, owned_count))
             })?
@@ -773,6 +1615,7 @@ pub async fn get_expansion_statistics_db() -> Result<Vec<ExpansionStats>, Server
 
         Ok(stats)
     })
+    .await
     .map_err(|e: anyhow::Error| ServerFnError::ServerError {
         message: e.to_string(),
         code: 500,
@@ -785,7 +1628,7 @@ pub async fn get_expansion_statistics_db() -> Result<Vec<ExpansionStats>, Server
 pub async fn get_collection_statistics_db() -> Result<CollectionStats, ServerFnError> {
     log_server_fn!("get_collection_statistics_db");
 
-    DB.with(|db| {
+    db::run(|db| {
         log_db_op!(
             "SELECT",
             table = "cards, card_expansions",
@@ -823,11 +1666,12 @@ pub async fn get_collection_statistics_db() -> Result<CollectionStats, ServerFnE
 
         Ok(CollectionStats {
             total_cards_owned,
-            total_unique_pokemon: crate::MAX_POKEMON,
+            total_unique_pokemon: crate::config::layout().max_pokemon,
             total_expansion_cards,
             overall_completion_rate,
         })
     })
+    .await
     .map_err(|e: anyhow::Error| ServerFnError::ServerError {
         message: e.to_string(),
         code: 500,
@@ -1166,7 +2010,7 @@ mod tests {
 
         // Fetch CSV once at the beginning
         println!("Downloading CSV data for name extraction...");
-        let csv_content = match reqwest::get(crate::LANGUAGE_URL).await {
+        let csv_content = match reqwest::get(&crate::config::get().pokeapi.language_url).await {
             Ok(resp) => match resp.text().await {
                 Ok(text) => text,
                 Err(e) => {
@@ -1204,7 +2048,7 @@ mod tests {
                         Ok(card) => {
                             success_count += 1;
                             // Optionally print successful fetches (commented out to reduce noise)
-                            println!("✓ ID {}: {} ({})", id, card.name_en, card.name_de);
+                            println!("✓ ID {}: {:?}", id, card.names.0);
                         }
                         Err(err) => {
                             let error_msg = format!("Card creation failed - {}", err);
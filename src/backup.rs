@@ -0,0 +1,568 @@
+//! Online backup and restore of the collection database
+//!
+//! Snapshots are taken with SQLite's online backup API, so they are consistent
+//! even while other connections keep writing. Restoring validates the uploaded
+//! file first, saves the live database as a `pre-restore` snapshot and then
+//! copies the upload over it through the same API.
+//!
+//! A background thread additionally takes hourly and daily snapshots and
+//! prunes old ones according to the retention configured in `[backup]`.
+
+use serde::{Deserialize, Serialize};
+
+/// A database snapshot travelling between server and client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub file_name: String,
+    /// Raw SQLite file, base64 encoded
+    pub data: String,
+}
+
+/// A snapshot kept in the server's backup directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub file_name: String,
+    pub size_bytes: u64,
+}
+
+/// Outcome of a successful restore
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    /// Snapshot of the database as it was right before the restore
+    pub pre_restore_backup: String,
+    /// Highest migration version recorded in the uploaded snapshot
+    pub schema_version: u32,
+    pub card_count: usize,
+}
+
+/// How often a scheduled snapshot is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Schedule {
+    Hourly,
+    Daily,
+}
+
+impl Schedule {
+    pub const ALL: [Schedule; 2] = [Schedule::Hourly, Schedule::Daily];
+
+    /// File name prefix of snapshots taken for this schedule
+    pub fn label(&self) -> &'static str {
+        match self {
+            Schedule::Hourly => "hourly",
+            Schedule::Daily => "daily",
+        }
+    }
+}
+
+/// Result of the most recent scheduled backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupRun {
+    pub schedule: Schedule,
+    /// Snapshot written by the run, `None` if it failed
+    pub file_name: Option<String>,
+    /// Older snapshots removed by the retention policy
+    pub pruned: usize,
+    pub error: Option<String>,
+    /// Seconds between the end of the run and the status request
+    pub seconds_ago: u64,
+}
+
+/// Scheduler state shown on the admin page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupStatus {
+    pub last_run: Option<BackupRun>,
+    pub hourly_keep: usize,
+    pub daily_keep: usize,
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::migrations;
+    use anyhow::{Context, Result};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use rusqlite::{backup::Progress, Connection, DatabaseName, OptionalExtension};
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::{Duration, SystemTime};
+
+    /// How often the scheduler checks whether a snapshot is due
+    const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Last scheduled run and when it finished
+    static LAST_RUN: Mutex<Option<(BackupRun, SystemTime)>> = Mutex::new(None);
+
+    /// Tables a snapshot must contain to be accepted
+    const REQUIRED_TABLES: [&str; 3] = ["cards", "expansions", "card_expansions"];
+
+    /// An uploaded file that must not replace the live database
+    #[derive(Debug, thiserror::Error)]
+    #[error("{0}")]
+    pub struct InvalidSnapshot(pub String);
+
+    fn backup_dir() -> Result<&'static Path> {
+        let dir = &crate::config::get().backup.dir;
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// Picks `<label>-<timestamp>.db`, adding a counter if that name is taken
+    fn snapshot_path(conn: &Connection, label: &str) -> Result<PathBuf> {
+        let dir = backup_dir()?;
+        let timestamp: String =
+            conn.query_row("SELECT strftime('%Y%m%d-%H%M%S', 'now')", [], |row| {
+                row.get(0)
+            })?;
+
+        let mut path = dir.join(format!("{label}-{timestamp}.db"));
+        let mut counter = 1;
+        while path.exists() {
+            path = dir.join(format!("{label}-{timestamp}-{counter}.db"));
+            counter += 1;
+        }
+        Ok(path)
+    }
+
+    /// Writes a consistent copy of the live database into the backup directory
+    pub fn create_snapshot(conn: &Connection, label: &str) -> Result<PathBuf> {
+        let path = snapshot_path(conn, label)?;
+        conn.backup(DatabaseName::Main, &path, None)
+            .with_context(|| format!("Failed to write snapshot {}", path.display()))?;
+
+        tracing::info!(path = %path.display(), "database snapshot written");
+        Ok(path)
+    }
+
+    /// Takes a fresh snapshot and returns it encoded for download
+    ///
+    /// The temporary file is removed again, the download is the copy.
+    pub fn download_snapshot(conn: &Connection) -> Result<BackupFile> {
+        let path = create_snapshot(conn, "collection")?;
+        let file = encode(&path);
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!(path = %path.display(), error = %e, "failed to remove temporary snapshot");
+        }
+        file
+    }
+
+    /// Returns a snapshot that is already stored in the backup directory
+    pub fn download_stored(file_name: &str) -> Result<BackupFile> {
+        if !list()?.iter().any(|entry| entry.file_name == file_name) {
+            return Err(InvalidSnapshot(format!("No backup named {file_name:?}")).into());
+        }
+        encode(&backup_dir()?.join(file_name))
+    }
+
+    fn encode(path: &Path) -> Result<BackupFile> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(BackupFile {
+            file_name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            data: STANDARD.encode(bytes),
+        })
+    }
+
+    /// Lists stored snapshots, newest first
+    pub fn list() -> Result<Vec<BackupEntry>> {
+        Ok(scan()?.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Stored snapshots with their modification time, newest first
+    fn scan() -> Result<Vec<(SystemTime, BackupEntry)>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(backup_dir()?)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if path.extension().is_some_and(|ext| ext == "db") && metadata.is_file() {
+                entries.push((
+                    metadata.modified()?,
+                    BackupEntry {
+                        file_name: entry.file_name().to_string_lossy().into_owned(),
+                        size_bytes: metadata.len(),
+                    },
+                ));
+            }
+        }
+        entries.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(entries)
+    }
+
+    /// Checks that `path` is a collection database this build can migrate
+    ///
+    /// Returns the highest migration version recorded in the snapshot.
+    pub fn validate_snapshot(path: &Path) -> Result<u32> {
+        let invalid = |reason: String| anyhow::Error::from(InvalidSnapshot(reason));
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open snapshot {}", path.display()))?;
+
+        let check: String = conn
+            .query_row("PRAGMA quick_check", [], |row| row.get(0))
+            .map_err(|e| invalid(format!("Upload is not a SQLite database: {e}")))?;
+        if check != "ok" {
+            return Err(invalid(format!(
+                "Snapshot failed the integrity check: {check}"
+            )));
+        }
+
+        for table in REQUIRED_TABLES {
+            let exists = conn
+                .query_row(
+                    "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                    [table],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some();
+            if !exists {
+                return Err(invalid(format!(
+                    "Snapshot has no `{table}` table, it is not a collection database"
+                )));
+            }
+        }
+
+        let known = migrations::embedded()?;
+        let applied = migrations::applied(&conn)?;
+
+        if let Some(newer) = applied
+            .iter()
+            .find(|a| !known.iter().any(|m| m.name == a.name))
+        {
+            return Err(invalid(format!(
+                "Snapshot was written by a newer version of the app (unknown migration `{}`)",
+                newer.name
+            )));
+        }
+        migrations::plan(&known, &applied).map_err(|e| invalid(e.to_string()))?;
+
+        let version = applied
+            .iter()
+            .filter_map(|a| known.iter().find(|m| m.name == a.name))
+            .map(|m| m.version)
+            .max()
+            .unwrap_or(0);
+        Ok(version)
+    }
+
+    /// Replaces the live database with an uploaded snapshot
+    ///
+    /// The upload is validated before anything is touched. Snapshots taken by
+    /// older builds are migrated to the current schema right after the copy.
+    pub fn restore(conn: &mut Connection, upload: &BackupFile) -> Result<RestoreReport> {
+        let bytes = STANDARD
+            .decode(&upload.data)
+            .map_err(|e| InvalidSnapshot(format!("Upload is not valid base64: {e}")))?;
+
+        let upload_path = snapshot_path(conn, "upload")?;
+        std::fs::write(&upload_path, bytes)
+            .with_context(|| format!("Failed to store upload at {}", upload_path.display()))?;
+
+        let result = restore_from(conn, &upload_path);
+
+        if let Err(e) = std::fs::remove_file(&upload_path) {
+            tracing::warn!(path = %upload_path.display(), error = %e, "failed to remove uploaded snapshot");
+        }
+        result
+    }
+
+    fn restore_from(conn: &mut Connection, source: &Path) -> Result<RestoreReport> {
+        let schema_version = validate_snapshot(source)?;
+
+        let pre_restore = create_snapshot(conn, "pre-restore")?;
+
+        conn.restore(DatabaseName::Main, source, None::<fn(Progress)>)
+            .with_context(|| format!("Failed to restore from {}", source.display()))?;
+        crate::backend::setup_database(conn)?;
+
+        let card_count: i64 = conn.query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0))?;
+
+        tracing::warn!(
+            pre_restore_backup = %pre_restore.display(),
+            schema_version,
+            card_count,
+            "database restored from uploaded snapshot"
+        );
+
+        Ok(RestoreReport {
+            pre_restore_backup: pre_restore
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            schema_version,
+            card_count: card_count as usize,
+        })
+    }
+
+    impl Schedule {
+        fn interval(&self) -> Duration {
+            match self {
+                Schedule::Hourly => Duration::from_secs(60 * 60),
+                Schedule::Daily => Duration::from_secs(24 * 60 * 60),
+            }
+        }
+
+        /// Snapshots to keep, zero disables the schedule
+        fn keep(&self) -> usize {
+            let config = &crate::config::get().backup;
+            match self {
+                Schedule::Hourly => config.hourly_keep,
+                Schedule::Daily => config.daily_keep,
+            }
+        }
+
+        fn owns(&self, file_name: &str) -> bool {
+            file_name
+                .strip_prefix(self.label())
+                .is_some_and(|rest| rest.starts_with('-'))
+        }
+    }
+
+    /// Whether the newest snapshot of `schedule` is older than its interval
+    fn is_due(schedule: Schedule, entries: &[(SystemTime, BackupEntry)]) -> bool {
+        entries
+            .iter()
+            .find(|(_, entry)| schedule.owns(&entry.file_name))
+            .is_none_or(|(modified, _)| {
+                modified
+                    .elapsed()
+                    .map_or(true, |age| age >= schedule.interval())
+            })
+    }
+
+    /// Names of the snapshots of `schedule` beyond the newest `keep`
+    fn expired(
+        schedule: Schedule,
+        keep: usize,
+        entries: &[(SystemTime, BackupEntry)],
+    ) -> Vec<String> {
+        entries
+            .iter()
+            .filter(|(_, entry)| schedule.owns(&entry.file_name))
+            .skip(keep)
+            .map(|(_, entry)| entry.file_name.clone())
+            .collect()
+    }
+
+    /// Takes a snapshot for `schedule` and prunes the ones it no longer keeps
+    fn run_scheduled(schedule: Schedule) -> Result<(PathBuf, usize)> {
+        let conn = crate::db::get()?;
+        let path = create_snapshot(&conn, schedule.label())?;
+
+        let dir = backup_dir()?;
+        let mut pruned = 0;
+        for file_name in expired(schedule, schedule.keep(), &scan()?) {
+            let old = dir.join(&file_name);
+            match std::fs::remove_file(&old) {
+                Ok(()) => pruned += 1,
+                Err(e) => {
+                    tracing::warn!(path = %old.display(), error = %e, "failed to prune backup")
+                }
+            }
+        }
+        Ok((path, pruned))
+    }
+
+    fn record(schedule: Schedule, result: Result<(PathBuf, usize)>) {
+        let run = match result {
+            Ok((path, pruned)) => {
+                tracing::info!(
+                    schedule = schedule.label(),
+                    path = %path.display(),
+                    pruned,
+                    "scheduled backup finished"
+                );
+                BackupRun {
+                    schedule,
+                    file_name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned()),
+                    pruned,
+                    error: None,
+                    seconds_ago: 0,
+                }
+            }
+            Err(e) => {
+                tracing::error!(schedule = schedule.label(), error = ?e, "scheduled backup failed");
+                BackupRun {
+                    schedule,
+                    file_name: None,
+                    pruned: 0,
+                    error: Some(format!("{e:#}")),
+                    seconds_ago: 0,
+                }
+            }
+        };
+        if let Ok(mut last) = LAST_RUN.lock() {
+            *last = Some((run, SystemTime::now()));
+        }
+    }
+
+    /// Starts the background thread taking hourly and daily snapshots
+    ///
+    /// Due snapshots are derived from the files on disk, so a restart neither
+    /// skips nor duplicates a run.
+    pub fn start_scheduler() {
+        if Schedule::ALL.iter().all(|schedule| schedule.keep() == 0) {
+            tracing::info!("scheduled backups disabled");
+            return;
+        }
+
+        let spawned = std::thread::Builder::new()
+            .name("backup-scheduler".to_string())
+            .spawn(|| loop {
+                for schedule in Schedule::ALL {
+                    if schedule.keep() == 0 {
+                        continue;
+                    }
+                    match scan() {
+                        Ok(entries) if !is_due(schedule, &entries) => {}
+                        Ok(_) => record(schedule, run_scheduled(schedule)),
+                        Err(e) => record(schedule, Err(e)),
+                    }
+                }
+                std::thread::sleep(CHECK_INTERVAL);
+            });
+
+        match spawned {
+            Ok(_) => tracing::info!(
+                hourly_keep = Schedule::Hourly.keep(),
+                daily_keep = Schedule::Daily.keep(),
+                "backup scheduler started"
+            ),
+            Err(e) => tracing::error!(error = %e, "failed to start backup scheduler"),
+        }
+    }
+
+    /// Current scheduler configuration and the outcome of the last run
+    pub fn status() -> BackupStatus {
+        let last_run = LAST_RUN.lock().ok().and_then(|last| {
+            last.as_ref().map(|(run, finished)| BackupRun {
+                seconds_ago: finished.elapsed().map_or(0, |age| age.as_secs()),
+                ..run.clone()
+            })
+        });
+        BackupStatus {
+            last_run,
+            hourly_keep: Schedule::Hourly.keep(),
+            daily_keep: Schedule::Daily.keep(),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn collection_db(path: &Path) -> Connection {
+            let mut conn = Connection::open(path).unwrap();
+            crate::backend::setup_database(&mut conn).unwrap();
+            conn
+        }
+
+        fn temp_dir(name: &str) -> PathBuf {
+            let dir =
+                std::env::temp_dir().join(format!("fs-chaot-backup-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            dir
+        }
+
+        fn entry(file_name: &str, age: Duration) -> (SystemTime, BackupEntry) {
+            (
+                SystemTime::now() - age,
+                BackupEntry {
+                    file_name: file_name.to_string(),
+                    size_bytes: 0,
+                },
+            )
+        }
+
+        #[test]
+        fn test_schedule_is_due() {
+            let hour = Duration::from_secs(60 * 60);
+            let entries = vec![
+                entry("hourly-20261019-120000.db", hour / 2),
+                entry("daily-20261018-000000.db", hour * 30),
+            ];
+
+            assert!(!is_due(Schedule::Hourly, &entries));
+            assert!(is_due(Schedule::Daily, &entries));
+            assert!(is_due(Schedule::Hourly, &[]));
+        }
+
+        #[test]
+        fn test_expired_keeps_newest_of_schedule_only() {
+            let hour = Duration::from_secs(60 * 60);
+            let entries = vec![
+                entry("hourly-3.db", hour),
+                entry("pre-restore-1.db", hour * 2),
+                entry("hourly-2.db", hour * 3),
+                entry("daily-1.db", hour * 4),
+                entry("hourly-1.db", hour * 5),
+            ];
+
+            assert_eq!(
+                expired(Schedule::Hourly, 2, &entries),
+                vec!["hourly-1.db".to_string()]
+            );
+            assert!(expired(Schedule::Daily, 2, &entries).is_empty());
+        }
+
+        #[test]
+        fn test_validate_accepts_current_schema() {
+            let dir = temp_dir("valid");
+            let path = dir.join("snapshot.db");
+            drop(collection_db(&path));
+
+            let version = validate_snapshot(&path).unwrap();
+            let latest = migrations::embedded().unwrap().last().unwrap().version;
+            assert_eq!(version, latest);
+        }
+
+        #[test]
+        fn test_validate_rejects_foreign_database() {
+            let dir = temp_dir("foreign");
+            let path = dir.join("other.db");
+            Connection::open(&path)
+                .unwrap()
+                .execute_batch("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);")
+                .unwrap();
+
+            let err = validate_snapshot(&path).unwrap_err();
+            assert!(err.downcast_ref::<InvalidSnapshot>().is_some());
+        }
+
+        #[test]
+        fn test_validate_rejects_newer_schema() {
+            let dir = temp_dir("newer");
+            let path = dir.join("newer.db");
+            let conn = collection_db(&path);
+            conn.execute(
+                "INSERT INTO migrations (name, version, checksum) VALUES ('9999_from_the_future', 9999, 'x')",
+                [],
+            )
+            .unwrap();
+            drop(conn);
+
+            let err = validate_snapshot(&path).unwrap_err();
+            assert!(err.to_string().contains("newer version"));
+        }
+
+        #[test]
+        fn test_validate_rejects_garbage() {
+            let dir = temp_dir("garbage");
+            let path = dir.join("garbage.db");
+            std::fs::write(&path, b"definitely not sqlite, just some bytes").unwrap();
+
+            let err = validate_snapshot(&path).unwrap_err();
+            assert!(err.downcast_ref::<InvalidSnapshot>().is_some());
+        }
+    }
+}
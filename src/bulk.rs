@@ -0,0 +1,384 @@
+//! Ownership changes for many cards at once
+//!
+//! A bulk request marks a selection of pockets owned, adding the same
+//! expansion entry to each of them, or unowned. All cards are written in one
+//! transaction, but every card runs in its own savepoint, so a card that
+//! fails is rolled back and reported without discarding the others. Each card
+//! gets its own history batch, undo on a single card reverts only that card.
+
+use crate::card::Rarity;
+use crate::expansion::CardExpansion;
+use serde::{Deserialize, Serialize};
+
+/// Upper limit of cards in one request, cards missing from the database
+/// are fetched from PokeAPI one by one
+pub const MAX_BULK_CARDS: usize = 100;
+
+/// Card number stored when the template leaves it empty
+pub const UNKNOWN_CARD_NUMBER: &str = "?";
+
+/// Expansion entry added to every card marked owned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpansionTemplate {
+    pub expansion_id: usize,
+    /// Empty stores [`UNKNOWN_CARD_NUMBER`], to be corrected per card later
+    pub card_number: String,
+    pub rarity: Rarity,
+}
+
+impl ExpansionTemplate {
+    /// The entry this template adds to `card_id`
+    pub fn entry(&self, card_id: usize) -> CardExpansion {
+        let card_number = match self.card_number.trim() {
+            "" => UNKNOWN_CARD_NUMBER,
+            number => number,
+        };
+        CardExpansion {
+            id: None,
+            card_id,
+            expansion_id: self.expansion_id,
+            card_number: card_number.to_string(),
+            rarity: self.rarity.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkAction {
+    /// Adds the template entry unless the card already has it
+    MarkOwned { template: ExpansionTemplate },
+    /// Removes the card and all of its expansion entries
+    MarkUnowned,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkRequest {
+    pub indices: Vec<usize>,
+    pub action: BulkAction,
+}
+
+impl BulkRequest {
+    /// Requested indices without duplicates, in request order
+    pub fn indices(&self) -> Vec<usize> {
+        let mut seen = std::collections::HashSet::new();
+        self.indices
+            .iter()
+            .copied()
+            .filter(|index| seen.insert(*index))
+            .collect()
+    }
+
+    /// Why the request as a whole can't be applied
+    pub fn problem(&self) -> Option<String> {
+        match self.indices().len() {
+            0 => Some("No cards selected".to_string()),
+            n if n > MAX_BULK_CARDS => Some(format!(
+                "{n} cards selected, at most {MAX_BULK_CARDS} can be changed at once"
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BulkStatus {
+    Changed,
+    /// The card already was in the requested state
+    Unchanged,
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkResult {
+    pub index: usize,
+    pub status: BulkStatus,
+}
+
+/// Per-card outcome of a bulk request, in request order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BulkReport {
+    pub results: Vec<BulkResult>,
+}
+
+impl BulkReport {
+    fn count(&self, matches: impl Fn(&BulkStatus) -> bool) -> usize {
+        self.results.iter().filter(|r| matches(&r.status)).count()
+    }
+
+    pub fn changed(&self) -> usize {
+        self.count(|status| *status == BulkStatus::Changed)
+    }
+
+    pub fn unchanged(&self) -> usize {
+        self.count(|status| *status == BulkStatus::Unchanged)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &BulkResult> {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.status, BulkStatus::Failed { .. }))
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::card::{Bool, Card};
+    use crate::config;
+    use crate::ownership::{apply_edit, card_expansions, stored_card, OwnershipEdit, SaveOutcome};
+    use anyhow::Result;
+    use rusqlite::{Connection, TransactionBehavior};
+    use std::collections::HashMap;
+
+    fn in_binder(index: usize) -> bool {
+        (1..=config::layout().max_pokemon).contains(&index)
+    }
+
+    /// Indices of `request` inside the binder that have no `cards` row yet
+    pub fn missing(conn: &Connection, request: &BulkRequest) -> Result<Vec<usize>> {
+        let mut missing = Vec::new();
+        for index in request.indices().into_iter().filter(|i| in_binder(*i)) {
+            if stored_card(conn, index)?.is_none() {
+                missing.push(index);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Applies `request` in one transaction with a savepoint per card
+    ///
+    /// `fetched` holds the card data of [`missing`] cards, or why it could
+    /// not be fetched.
+    pub fn apply(
+        conn: &mut Connection,
+        request: &BulkRequest,
+        fetched: &HashMap<usize, Result<Card, String>>,
+        profile: &str,
+    ) -> Result<BulkReport> {
+        let mut tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let mut report = BulkReport::default();
+
+        for index in request.indices() {
+            let savepoint = tx.savepoint()?;
+            let status = match apply_one(&savepoint, index, &request.action, fetched, profile) {
+                Ok(BulkStatus::Changed) => {
+                    savepoint.commit()?;
+                    BulkStatus::Changed
+                }
+                // Dropping the savepoint rolls back whatever the card wrote
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::warn!(card_id = index, error = %e, "bulk ownership update failed for card");
+                    BulkStatus::Failed {
+                        reason: e.to_string(),
+                    }
+                }
+            };
+            report.results.push(BulkResult { index, status });
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    fn apply_one(
+        conn: &Connection,
+        index: usize,
+        action: &BulkAction,
+        fetched: &HashMap<usize, Result<Card, String>>,
+        profile: &str,
+    ) -> Result<BulkStatus> {
+        if !in_binder(index) {
+            return Ok(BulkStatus::Failed {
+                reason: format!("#{index} is outside the binder"),
+            });
+        }
+
+        let card = match (stored_card(conn, index)?, fetched.get(&index), action) {
+            (Some(card), _, _) => card,
+            (None, _, BulkAction::MarkUnowned) => return Ok(BulkStatus::Unchanged),
+            (None, Some(Ok(card)), _) => card.clone(),
+            (None, Some(Err(reason)), _) => {
+                return Ok(BulkStatus::Failed {
+                    reason: reason.clone(),
+                })
+            }
+            (None, None, _) => {
+                return Ok(BulkStatus::Failed {
+                    reason: "Card data is not available".to_string(),
+                })
+            }
+        };
+        let mut expansions = card_expansions(conn, index)?;
+
+        let edit = match action {
+            BulkAction::MarkUnowned => {
+                if !card.owned.0 && expansions.is_empty() {
+                    return Ok(BulkStatus::Unchanged);
+                }
+                OwnershipEdit {
+                    card: Card {
+                        owned: Bool(false),
+                        ..card
+                    },
+                    expansions: Vec::new(),
+                }
+            }
+            BulkAction::MarkOwned { template } => {
+                let entry = template.entry(index);
+                let present = expansions.iter().any(|e| {
+                    e.expansion_id == entry.expansion_id && e.card_number == entry.card_number
+                });
+                if card.owned.0 && present {
+                    return Ok(BulkStatus::Unchanged);
+                }
+                if !present {
+                    expansions.push(entry);
+                }
+                OwnershipEdit {
+                    card: Card {
+                        owned: Bool(true),
+                        ..card
+                    },
+                    expansions,
+                }
+            }
+        };
+
+        Ok(match apply_edit(conn, edit, profile)? {
+            SaveOutcome::Saved(_) => BulkStatus::Changed,
+            SaveOutcome::Rejected(problems) => BulkStatus::Failed {
+                reason: problems
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            },
+            SaveOutcome::Conflict(conflict) => BulkStatus::Failed {
+                reason: conflict.to_string(),
+            },
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::card::Index;
+
+        fn card(index: usize) -> Card {
+            Card {
+                index: Index(index),
+                ..Card::default()
+            }
+        }
+
+        fn mark_owned(indices: Vec<usize>, expansion_id: usize) -> BulkRequest {
+            BulkRequest {
+                indices,
+                action: BulkAction::MarkOwned {
+                    template: ExpansionTemplate {
+                        expansion_id,
+                        card_number: String::new(),
+                        rarity: Rarity::Common,
+                    },
+                },
+            }
+        }
+
+        fn fetched(indices: &[usize]) -> HashMap<usize, Result<Card, String>> {
+            indices.iter().map(|i| (*i, Ok(card(*i)))).collect()
+        }
+
+        fn owned_count(conn: &Connection) -> usize {
+            conn.query_row("SELECT COUNT(*) FROM cards WHERE owned = 1", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        }
+
+        #[test]
+        fn test_request_problem() {
+            assert!(mark_owned(vec![], 1).problem().is_some());
+            assert!(mark_owned(vec![1; MAX_BULK_CARDS + 1], 1)
+                .problem()
+                .is_none());
+            assert!(mark_owned((1..=MAX_BULK_CARDS + 1).collect(), 1)
+                .problem()
+                .is_some());
+        }
+
+        #[test]
+        fn test_mark_owned_and_unowned() {
+            let mut conn = test_connection();
+            let request = mark_owned(vec![1, 2, 3, 2], 1);
+            let missing = missing(&conn, &request).unwrap();
+            assert_eq!(missing, vec![1, 2, 3]);
+
+            let report = apply(&mut conn, &request, &fetched(&missing), "ash").unwrap();
+            assert_eq!(report.results.len(), 3);
+            assert_eq!(report.changed(), 3);
+            assert_eq!(owned_count(&conn), 3);
+            let entries = card_expansions(&conn, 2).unwrap();
+            assert_eq!(entries[0].card_number, UNKNOWN_CARD_NUMBER);
+
+            // Same template again is a no-op
+            let report = apply(&mut conn, &request, &HashMap::new(), "ash").unwrap();
+            assert_eq!(report.unchanged(), 3);
+
+            let request = BulkRequest {
+                indices: vec![2, 3, 4],
+                action: BulkAction::MarkUnowned,
+            };
+            let report = apply(&mut conn, &request, &HashMap::new(), "ash").unwrap();
+            assert_eq!(report.changed(), 2);
+            assert_eq!(report.unchanged(), 1);
+            assert_eq!(owned_count(&conn), 1);
+            assert!(card_expansions(&conn, 2).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_failed_cards_do_not_block_others() {
+            let mut conn = test_connection();
+            let request = mark_owned(vec![1, 2, 0], 1);
+            let mut fetched = fetched(&[1]);
+            fetched.insert(2, Err("PokeAPI unreachable".to_string()));
+
+            let report = apply(&mut conn, &request, &fetched, "ash").unwrap();
+            assert_eq!(report.changed(), 1);
+            let failed: Vec<usize> = report.failures().map(|r| r.index).collect();
+            assert_eq!(failed, vec![2, 0]);
+            assert_eq!(owned_count(&conn), 1);
+
+            // A rejected template rolls back only its own card
+            let report = apply(
+                &mut conn,
+                &mark_owned(vec![1], 999_999),
+                &HashMap::new(),
+                "ash",
+            )
+            .unwrap();
+            assert_eq!(report.failures().count(), 1);
+            assert_eq!(card_expansions(&conn, 1).unwrap().len(), 1);
+        }
+
+        #[test]
+        fn test_undo_reverts_single_card() {
+            let mut conn = test_connection();
+            let request = mark_owned(vec![1, 2], 1);
+            apply(&mut conn, &request, &fetched(&[1, 2]), "ash").unwrap();
+
+            crate::history::undo_last(&mut conn, 1, "ash").unwrap();
+            assert_eq!(owned_count(&conn), 1);
+            assert!(card_expansions(&conn, 1).unwrap().is_empty());
+            assert_eq!(card_expansions(&conn, 2).unwrap().len(), 1);
+        }
+    }
+}
@@ -1,26 +1,71 @@
-use crate::{
-    pokeapi::PokeApi, BASE_URL, CARDS_PER_BOOK, CARDS_PER_DOUBLE_PAGE, LANGUAGE_URL, SPRITE_URL,
-};
+use crate::{config, pokeapi::PokeApi};
 use anyhow::{anyhow, Result};
 #[cfg(feature = "server")]
 use rusqlite::{
     types::{FromSql, FromSqlResult, ToSqlOutput},
     ToSql,
 };
+use std::collections::BTreeMap;
 use std::fmt::Display;
-use strum::EnumIter;
+use strum::{EnumIter, IntoEnumIterator};
 
 #[derive(Default, Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
+#[serde(from = "CardFields")]
 pub struct Card {
     pub index: Index,
-    pub name_en: Name,
-    pub name_de: Name,
+    pub names: Names,
     pub book: Book,
     pub page: Page,
     pub side: Side,
     pub entry: Entry,
     pub img_url: String,
     pub owned: Bool,
+    /// Row version, bumped on every change to the card or its prints
+    pub version: u32,
+}
+
+/// A [`Card`] as read, also in the shape clients kept offline before names
+/// were stored in every language, with `name_en` and `name_de` fields
+#[derive(serde::Deserialize)]
+struct CardFields {
+    index: Index,
+    #[serde(default)]
+    names: Names,
+    name_en: Option<Name>,
+    name_de: Option<Name>,
+    book: Book,
+    page: Page,
+    side: Side,
+    entry: Entry,
+    img_url: String,
+    owned: Bool,
+    #[serde(default)]
+    version: u32,
+}
+
+impl From<CardFields> for Card {
+    fn from(fields: CardFields) -> Self {
+        let mut names = fields.names;
+        for (language, name) in [
+            (NameLanguage::En, fields.name_en),
+            (NameLanguage::De, fields.name_de),
+        ] {
+            if let Some(name) = name {
+                names.0.entry(language).or_insert(name);
+            }
+        }
+        Card {
+            index: fields.index,
+            names,
+            book: fields.book,
+            page: fields.page,
+            side: fields.side,
+            entry: fields.entry,
+            img_url: fields.img_url,
+            owned: fields.owned,
+            version: fields.version,
+        }
+    }
 }
 
 impl Card {
@@ -28,29 +73,29 @@ impl Card {
     pub fn new_debug() -> Self {
         Card {
             index: Index(1),
-            name_en: Name::new("some name"),
-            name_de: Name::new("ein Name"),
+            names: Names::from([
+                (NameLanguage::En, Name::new("some name")),
+                (NameLanguage::De, Name::new("ein Name")),
+            ]),
             book: Book(1),
             page: Page(1),
             side: Side::A,
             entry: Entry(1),
-            img_url: format!("{SPRITE_URL}1.png"),
+            img_url: format!("{}1.png", config::get().pokeapi.sprite_url),
             ..Default::default()
         }
     }
     pub async fn try_from_index(index: Index) -> Result<Self> {
-        let names = PokeApi::get_names(&index, BASE_URL, LANGUAGE_URL).await?;
-        let name_en = Name::new(names[0].as_str());
-        let name_de = Name::new(names[1].as_str());
+        let pokeapi = &config::get().pokeapi;
+        let names = PokeApi::get_names(&index, &pokeapi.base_url, &pokeapi.language_url).await?;
         let book = Book::from(&index);
         let page = Page::relative(&index);
         let side = Side::from(&index);
         let entry = Entry::new(&index, &Page::absolut(&index), &side);
-        let img_url = format!("{SPRITE_URL}{}.png", &index.0);
+        let img_url = format!("{}{}.png", pokeapi.sprite_url, &index.0);
         Ok(Self {
             index,
-            name_en,
-            name_de,
+            names,
             book,
             page,
             side,
@@ -61,10 +106,149 @@ impl Card {
     }
 
     pub async fn try_from_name(name: Name) -> Result<Self> {
-        let id = PokeApi::get_id(BASE_URL, LANGUAGE_URL, &name).await?;
+        let pokeapi = &config::get().pokeapi;
+        let id = PokeApi::get_id(&pokeapi.base_url, &pokeapi.language_url, &name).await?;
         let index = Index::try_new(id)?;
         Card::try_from_index(index).await
     }
+
+    /// Name in the configured primary language, falling back to English
+    pub fn primary_name(&self) -> &Name {
+        let primary = config::get().names.primary;
+        self.names
+            .get(primary)
+            .or_else(|| self.names.get(NameLanguage::En))
+            .or_else(|| self.names.0.values().next())
+            .unwrap_or(&NO_NAME)
+    }
+
+    /// Name in the configured secondary language, `None` if it is not stored
+    /// or spelled like the primary name
+    pub fn secondary_name(&self) -> Option<&Name> {
+        let secondary = config::get().names.secondary?;
+        self.names
+            .get(secondary)
+            .filter(|name| *name != self.primary_name())
+    }
+}
+
+static NO_NAME: Name = Name(String::new());
+
+/// Languages Pokemon names are kept in
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Deserialize,
+    serde::Serialize,
+    EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NameLanguage {
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+    Ja,
+    Ko,
+}
+
+impl NameLanguage {
+    /// ISO 639-1 code, also the key in the stored names
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+            Self::Fr => "fr",
+            Self::Es => "es",
+            Self::It => "it",
+            Self::Ja => "ja",
+            Self::Ko => "ko",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::iter().find(|language| language.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// `local_language_id` in the PokeAPI species CSV
+    ///
+    /// Japanese is the katakana spelling (`ja-Hrkt`) printed on Japanese cards.
+    pub fn pokeapi_id(&self) -> u8 {
+        match self {
+            Self::Ja => 1,
+            Self::Ko => 3,
+            Self::Fr => 5,
+            Self::De => 6,
+            Self::Es => 7,
+            Self::It => 8,
+            Self::En => 9,
+        }
+    }
+
+    pub fn from_pokeapi_id(id: u8) -> Option<Self> {
+        Self::iter().find(|language| language.pokeapi_id() == id)
+    }
+}
+
+impl Display for NameLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A Pokemon's names by language, stored as one JSON object
+#[derive(Default, Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
+pub struct Names(pub BTreeMap<NameLanguage, Name>);
+
+impl Names {
+    pub fn get(&self, language: NameLanguage) -> Option<&Name> {
+        self.0.get(&language)
+    }
+
+    pub fn insert(&mut self, language: NameLanguage, name: Name) {
+        self.0.insert(language, name);
+    }
+
+    /// Every stored name, in language order
+    pub fn all(&self) -> impl Iterator<Item = &Name> {
+        self.0.values()
+    }
+
+    /// Whether any stored name is `name` after [`crate::names::normalize`]
+    pub fn matches(&self, name: &str) -> bool {
+        self.all()
+            .any(|stored| crate::names::same_name(&stored.0, name))
+    }
+}
+
+impl<const N: usize> From<[(NameLanguage, Name); N]> for Names {
+    fn from(names: [(NameLanguage, Name); N]) -> Self {
+        Self(BTreeMap::from(names))
+    }
+}
+
+#[cfg(feature = "server")]
+impl ToSql for Names {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(json)))
+    }
+}
+
+#[cfg(feature = "server")]
+impl FromSql for Names {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?)
+            .map_err(|e| rusqlite::types::FromSqlError::Other(Box::new(e)))
+    }
 }
 
 #[derive(
@@ -92,22 +276,44 @@ pub enum Rarity {
     Promo,
 }
 
-impl Display for Rarity {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Rarity {
+    /// Text stored in the database, the same in every UI language
+    pub fn stored(&self) -> &'static str {
         match self {
-            Self::Common => f.write_str("Common ●"),
-            Self::Uncommon => f.write_str("Uncommon ♦"),
-            Self::Rare => f.write_str("Rare ★"),
-            Self::HoloRare => f.write_str("Holo Rare ★H"),
-            Self::ReverseHoloRare => f.write_str("Reverse Holo Rare ★H"),
-            Self::DoubleRare => f.write_str("Double Rare ★★"),
-            Self::UltraRare => f.write_str("Ultra Rare"),
-            Self::SecretRare => f.write_str("Secret Rare"),
-            Self::Promo => f.write_str("Promo"),
+            Self::Common => "Common ●",
+            Self::Uncommon => "Uncommon ♦",
+            Self::Rare => "Rare ★",
+            Self::HoloRare => "Holo Rare ★H",
+            Self::ReverseHoloRare => "Reverse Holo Rare ★H",
+            Self::DoubleRare => "Double Rare ★★",
+            Self::UltraRare => "Ultra Rare",
+            Self::SecretRare => "Secret Rare",
+            Self::Promo => "Promo",
+        }
+    }
+
+    fn message_key(&self) -> &'static str {
+        match self {
+            Self::Common => "rarity.common",
+            Self::Uncommon => "rarity.uncommon",
+            Self::Rare => "rarity.rare",
+            Self::HoloRare => "rarity.holo_rare",
+            Self::ReverseHoloRare => "rarity.reverse_holo_rare",
+            Self::DoubleRare => "rarity.double_rare",
+            Self::UltraRare => "rarity.ultra_rare",
+            Self::SecretRare => "rarity.secret_rare",
+            Self::Promo => "rarity.promo",
         }
     }
 }
 
+/// The name in the current UI language, see [`Rarity::stored`] for the stored text
+impl Display for Rarity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(crate::i18n::t(self.message_key()))
+    }
+}
+
 // impl FromStr for Rarity {
 //     type Err = Error;
 //     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -130,7 +336,7 @@ impl Display for Rarity {
 impl ToSql for Rarity {
     fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
         Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
-            self.to_string(),
+            self.stored().to_string(),
         )))
     }
 }
@@ -142,20 +348,12 @@ impl FromSql for Rarity {
     }
 }
 
+/// Reads the stored text, unknown text is common
 impl From<&str> for Rarity {
     fn from(value: &str) -> Self {
-        match value {
-            "Common ●" => Self::Common,
-            "Uncommon ♦" => Self::Uncommon,
-            "Rare ★" => Self::Rare,
-            "Holo Rare ★H" => Self::HoloRare,
-            "Reverse Holo Rare ★H" => Self::ReverseHoloRare,
-            "Double Rare ★★" => Self::DoubleRare,
-            "Ultra Rare" => Self::UltraRare,
-            "Secret Rare" => Self::SecretRare,
-            "Promo" => Self::Promo,
-            _ => Self::Common,
-        }
+        Self::iter()
+            .find(|rarity| rarity.stored() == value)
+            .unwrap_or_default()
     }
 }
 
@@ -252,7 +450,7 @@ impl Display for Book {
 
 impl From<&Index> for Book {
     fn from(value: &Index) -> Self {
-        Book((value.0 as f32 / CARDS_PER_BOOK as f32).ceil() as usize)
+        Book((value.0 as f32 / config::layout().cards_per_book as f32).ceil() as usize)
     }
 }
 
@@ -283,10 +481,11 @@ impl FromSql for Page {
 
 impl Page {
     pub fn relative_from_absolute(absolute: Self) -> Self {
-        let pages = CARDS_PER_BOOK / CARDS_PER_DOUBLE_PAGE;
+        let layout = config::layout();
+        let pages = layout.cards_per_book / layout.cards_per_double_page;
         let remainder = absolute.0 % pages;
         if remainder == 0 {
-            Self(CARDS_PER_DOUBLE_PAGE)
+            Self(layout.cards_per_double_page)
         } else {
             Self(remainder)
         }
@@ -294,16 +493,17 @@ impl Page {
 
     /// Calculates the absolut page number counting from 0
     pub fn absolut(index: &Index) -> Self {
-        Self((index.0 as f32 / CARDS_PER_DOUBLE_PAGE as f32).ceil() as usize)
+        Self((index.0 as f32 / config::layout().cards_per_double_page as f32).ceil() as usize)
     }
 
     /// Takes into the maximum cards per book into account and calculates the page relative to each book
     pub fn relative(index: &Index) -> Self {
-        let pages = (CARDS_PER_BOOK / CARDS_PER_DOUBLE_PAGE) as u16;
-        let page = (index.0 as f32 / CARDS_PER_DOUBLE_PAGE as f32).ceil() as u16;
+        let layout = config::layout();
+        let pages = (layout.cards_per_book / layout.cards_per_double_page) as u16;
+        let page = (index.0 as f32 / layout.cards_per_double_page as f32).ceil() as u16;
         let remainder = page % pages;
         if remainder == 0 {
-            Self(CARDS_PER_DOUBLE_PAGE)
+            Self(layout.cards_per_double_page)
         } else {
             Self(remainder as usize)
         }
@@ -364,7 +564,7 @@ impl From<&str> for Side {
 
 impl From<&Index> for Side {
     fn from(value: &Index) -> Self {
-        let rest = (value.0 as f32 / CARDS_PER_DOUBLE_PAGE as f32).fract();
+        let rest = (value.0 as f32 / config::layout().cards_per_double_page as f32).fract();
         if rest > 0.5 || rest == 0.0 {
             Self::B
         } else {
@@ -400,14 +600,15 @@ impl FromSql for Entry {
 
 impl Entry {
     pub fn new(index: &Index, page_absolut: &Page, side: &Side) -> Self {
-        let max_card_no = CARDS_PER_DOUBLE_PAGE * page_absolut.0;
-        let midpoint = max_card_no - (CARDS_PER_DOUBLE_PAGE / 2);
+        let layout = config::layout();
+        let max_card_no = layout.cards_per_double_page * page_absolut.0;
+        let midpoint = max_card_no - layout.cards_per_page();
         match side {
             Side::A => {
                 if page_absolut.0 == 1 {
                     Self(index.0)
                 } else {
-                    Self((CARDS_PER_DOUBLE_PAGE / 2) - (midpoint % index.0))
+                    Self(layout.cards_per_page() - (midpoint % index.0))
                 }
             }
             Side::B => Self(index.0 - midpoint),
@@ -0,0 +1,327 @@
+//! Card numbers as printed on the cards
+//!
+//! Most cards carry `number/total`, but subsets are numbered with a prefix of
+//! their own: the Trainer Gallery of Brilliant Stars runs `TG01/TG30`, the
+//! Radiant Collection of Generations `RC1/RC32`, the Shiny Vault of Hidden
+//! Fates `SV1/SV94` and Skyridge's holos `H1/H32`. Promos such as `SWSH001`
+//! have a prefix and no total. Every expansion has a [`NumberScheme`] that
+//! validates typed numbers and prints stored ones the way the card shows them.
+//!
+//! Numbers are stored without padding and with the subset prefix in the case
+//! of the scheme (`TG5`), so "tg05", "TG5" and "TG05/TG30" all store the same.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Most zero padding a scheme can ask for
+pub const MAX_DIGITS: usize = 4;
+
+/// Longest accepted subset prefix, in characters
+pub const MAX_PREFIX_LEN: usize = 6;
+
+/// A part of an expansion numbered with a prefix of its own
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NumberSubset {
+    /// Printed before the number, e.g. `TG`
+    pub prefix: String,
+    /// e.g. "Trainer Gallery"
+    #[serde(default)]
+    pub name: String,
+    /// Cards in the subset, `None` if open-ended like promos
+    #[serde(default)]
+    pub cards: Option<usize>,
+    /// Zero padding, 2 prints `TG05`
+    #[serde(default)]
+    pub digits: usize,
+}
+
+impl NumberSubset {
+    fn label(&self) -> &str {
+        match self.name.as_str() {
+            "" => &self.prefix,
+            name => name,
+        }
+    }
+}
+
+/// How the cards of an expansion are numbered
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NumberScheme {
+    /// Zero padding of the main numbers, 3 prints `049/198`
+    #[serde(default)]
+    pub digits: usize,
+    /// Every card belongs to a subset, as in promo expansions
+    #[serde(default)]
+    pub prefixed_only: bool,
+    #[serde(default)]
+    pub subsets: Vec<NumberSubset>,
+}
+
+/// A card number understood by a [`NumberScheme`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardNumber {
+    Main(usize),
+    Subset { prefix: String, number: usize },
+}
+
+/// The stored form, see the module documentation
+impl Display for CardNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardNumber::Main(number) => write!(f, "{number}"),
+            CardNumber::Subset { prefix, number } => write!(f, "{prefix}{number}"),
+        }
+    }
+}
+
+impl NumberScheme {
+    fn subset(&self, prefix: &str) -> Option<&NumberSubset> {
+        self.subsets
+            .iter()
+            .find(|subset| subset.prefix.eq_ignore_ascii_case(prefix))
+    }
+
+    /// Reads `input` ignoring case, zero padding and a trailing `/total`
+    pub fn parse(&self, input: &str) -> Result<CardNumber, String> {
+        let input = input.trim();
+        let printed = input.split('/').next().unwrap_or_default().trim();
+        let split = printed
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(printed.len());
+        let (prefix, digits) = printed.split_at(split);
+        let number = digits
+            .parse::<usize>()
+            .ok()
+            .filter(|number| *number > 0)
+            .ok_or_else(|| format!("`{input}` is not a card number"))?;
+
+        if prefix.is_empty() {
+            if self.prefixed_only {
+                let prefixes: Vec<&str> = self.subsets.iter().map(|s| s.prefix.as_str()).collect();
+                return Err(format!(
+                    "Numbers of this expansion start with {}",
+                    prefixes.join(" or ")
+                ));
+            }
+            return Ok(CardNumber::Main(number));
+        }
+        match self.subset(prefix) {
+            Some(subset) => Ok(CardNumber::Subset {
+                prefix: subset.prefix.clone(),
+                number,
+            }),
+            None => Err(format!("`{prefix}` is not a subset of this expansion")),
+        }
+    }
+
+    /// [`Self::parse`] plus a range check against the expansion's card counts
+    pub fn check(
+        &self,
+        input: &str,
+        cards: usize,
+        secret_cards: usize,
+    ) -> Result<CardNumber, String> {
+        let number = self.parse(input)?;
+        match &number {
+            CardNumber::Main(n) if *n > cards + secret_cards => Err(format!(
+                "{n} is beyond the {cards} cards and {secret_cards} secret cards of this expansion"
+            )),
+            CardNumber::Subset { prefix, number: n } => {
+                let subset = self.subset(prefix);
+                match subset.and_then(|s| s.cards).filter(|size| n > size) {
+                    Some(size) => Err(format!(
+                        "{prefix}{n} is beyond the {size} cards of the {}",
+                        subset.map(NumberSubset::label).unwrap_or(prefix)
+                    )),
+                    None => Ok(number),
+                }
+            }
+            CardNumber::Main(_) => Ok(number),
+        }
+    }
+
+    /// `number` as printed on the card, e.g. `049/198`, `TG05/TG30` or `SWSH001`
+    pub fn printed(&self, number: &CardNumber, cards: usize) -> String {
+        match number {
+            CardNumber::Main(n) => format!("{n:0width$}/{cards:0width$}", width = self.digits),
+            CardNumber::Subset { prefix, number } => {
+                let subset = self.subset(prefix);
+                let width = subset.map(|s| s.digits).unwrap_or_default();
+                match subset.and_then(|s| s.cards) {
+                    Some(size) => format!("{prefix}{number:0width$}/{prefix}{size:0width$}"),
+                    None => format!("{prefix}{number:0width$}"),
+                }
+            }
+        }
+    }
+
+    /// Example numbers for input placeholders, e.g. "001/198 or TG01/TG30"
+    pub fn hint(&self, cards: usize) -> String {
+        let mut examples = Vec::new();
+        if !self.prefixed_only {
+            examples.push(self.printed(&CardNumber::Main(1), cards));
+        }
+        examples.extend(self.subsets.iter().map(|subset| {
+            self.printed(
+                &CardNumber::Subset {
+                    prefix: subset.prefix.clone(),
+                    number: 1,
+                },
+                cards,
+            )
+        }));
+        examples.join(" or ")
+    }
+
+    /// Why the scheme can't be saved, `None` if it can
+    pub fn problem(&self) -> Option<String> {
+        if self.digits > MAX_DIGITS {
+            return Some(format!("Numbers are padded to at most {MAX_DIGITS} digits"));
+        }
+        if self.prefixed_only && self.subsets.is_empty() {
+            return Some("Prefixed numbers need at least one subset".to_string());
+        }
+        for (index, subset) in self.subsets.iter().enumerate() {
+            let prefix = &subset.prefix;
+            if prefix.is_empty()
+                || prefix.chars().count() > MAX_PREFIX_LEN
+                || !prefix.chars().all(|c| c.is_ascii_alphabetic())
+            {
+                return Some(format!(
+                    "Subset prefixes are 1 to {MAX_PREFIX_LEN} letters, not `{prefix}`"
+                ));
+            }
+            if subset.digits > MAX_DIGITS {
+                return Some(format!(
+                    "{} is padded to at most {MAX_DIGITS} digits",
+                    subset.label()
+                ));
+            }
+            if subset.cards == Some(0) {
+                return Some(format!("{} has no cards", subset.label()));
+            }
+            if self.subsets[..index]
+                .iter()
+                .any(|earlier| earlier.prefix.eq_ignore_ascii_case(prefix))
+            {
+                return Some(format!("The prefix `{prefix}` is used twice"));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+    use rusqlite::ToSql;
+
+    /// Stored as JSON, the default scheme as NULL
+    impl ToSql for NumberScheme {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            if self == &NumberScheme::default() {
+                return Ok(ToSqlOutput::Owned(rusqlite::types::Value::Null));
+            }
+            let json = serde_json::to_string(self)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+            Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(json)))
+        }
+    }
+
+    impl FromSql for NumberScheme {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            match value {
+                ValueRef::Null => Ok(NumberScheme::default()),
+                value => {
+                    serde_json::from_str(value.as_str()?).map_err(|e| FromSqlError::Other(e.into()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subset(prefix: &str, cards: Option<usize>, digits: usize) -> NumberSubset {
+        NumberSubset {
+            prefix: prefix.to_string(),
+            name: String::new(),
+            cards,
+            digits,
+        }
+    }
+
+    #[test]
+    fn test_parse_and_print() {
+        let brilliant_stars = NumberScheme {
+            digits: 3,
+            subsets: vec![subset("TG", Some(30), 2)],
+            ..NumberScheme::default()
+        };
+        for input in ["tg05", "TG5", " TG05/TG30 "] {
+            let number = brilliant_stars.check(input, 172, 14).unwrap();
+            assert_eq!(number.to_string(), "TG5");
+            assert_eq!(brilliant_stars.printed(&number, 172), "TG05/TG30");
+        }
+        let number = brilliant_stars.check("049", 172, 14).unwrap();
+        assert_eq!(number, CardNumber::Main(49));
+        assert_eq!(brilliant_stars.printed(&number, 172), "049/172");
+        assert_eq!(brilliant_stars.hint(172), "001/172 or TG01/TG30");
+
+        let generations = NumberScheme {
+            subsets: vec![subset("RC", Some(32), 0)],
+            ..NumberScheme::default()
+        };
+        let number = generations.check("RC12", 83, 0).unwrap();
+        assert_eq!(generations.printed(&number, 83), "RC12/RC32");
+
+        let promos = NumberScheme {
+            prefixed_only: true,
+            subsets: vec![subset("SWSH", None, 3)],
+            ..NumberScheme::default()
+        };
+        let number = promos.check("SWSH001", 1, 0).unwrap();
+        assert_eq!(promos.printed(&number, 1), "SWSH001");
+        assert!(promos.check("1", 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_check_rejects_unknown_and_out_of_range_numbers() {
+        let hidden_fates = NumberScheme {
+            subsets: vec![subset("SV", Some(94), 0)],
+            ..NumberScheme::default()
+        };
+        assert!(hidden_fates.check("68", 68, 1).is_ok());
+        assert!(hidden_fates.check("70", 68, 1).is_err());
+        assert!(hidden_fates.check("SV95", 68, 1).is_err());
+        assert!(hidden_fates.check("TG05", 68, 1).is_err());
+        for input in ["", "0", "SV", "abc", "12a"] {
+            assert!(hidden_fates.parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_problem() {
+        assert_eq!(NumberScheme::default().problem(), None);
+        let scheme = |subsets| NumberScheme {
+            subsets,
+            ..NumberScheme::default()
+        };
+        assert!(scheme(vec![subset("T1", None, 0)]).problem().is_some());
+        assert!(scheme(vec![subset("TG", Some(0), 0)]).problem().is_some());
+        assert!(scheme(vec![subset("TG", None, 0), subset("tg", None, 0)])
+            .problem()
+            .is_some());
+        assert!(NumberScheme {
+            prefixed_only: true,
+            ..NumberScheme::default()
+        }
+        .problem()
+        .is_some());
+    }
+}
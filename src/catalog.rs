@@ -0,0 +1,354 @@
+//! Canonical expansion list kept in `db_seed/catalog.toml`
+//!
+//! Corrections to expansions used to ship as one-off migrations such as
+//! `0003_update_expansion_data`. The catalog file is now the source of truth:
+//! [`diff`] compares it with the `expansions` table and [`apply`] adds the
+//! missing sets and corrects the stored ones in a single transaction. Sets
+//! that are only in the database, such as ones added on the expansions page,
+//! are never changed, and applying the same catalog twice changes nothing.
+//!
+//! Catalog entries are matched to stored sets by name, then by abbreviation,
+//! both ignoring case, so either one can be corrected at a time. Optional
+//! fields left out of an entry keep the stored value.
+
+use crate::card_number::NumberScheme;
+use crate::expansion::{Expansion, Region};
+use crate::expansion_admin::{self, ExpansionChange, ExpansionDraft};
+use anyhow::{bail, Context, Result};
+use rusqlite::{Connection, TransactionBehavior};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Display;
+
+/// The catalog compiled into the binary, also used to seed a new database
+pub const EMBEDDED: &str = include_str!("../db_seed/catalog.toml");
+
+/// Profile the catalog's changes are recorded under in `expansion_events`
+pub const PROFILE: &str = "catalog";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    /// Revision of the file, bumped with every edit
+    pub version: u32,
+    #[serde(default)]
+    pub expansions: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub name_de: Option<String>,
+    pub abbreviation: String,
+    pub cards: usize,
+    #[serde(default)]
+    pub secret_cards: usize,
+    #[serde(default)]
+    pub series: String,
+    pub release_date: Option<String>,
+    pub region: Option<Region>,
+    pub set_code: Option<String>,
+    pub symbol_url: Option<String>,
+    pub numbers: Option<NumberScheme>,
+}
+
+impl CatalogEntry {
+    /// The stored set with this entry's fields applied, a new set if `None`
+    fn draft(&self, stored: Option<&Expansion>) -> Result<ExpansionDraft, String> {
+        let mut draft = stored.map(ExpansionDraft::from).unwrap_or_default();
+        draft.name = self.name.clone();
+        draft.abbreviation = self.abbreviation.clone();
+        draft.cards = self.cards;
+        draft.secret_cards = self.secret_cards;
+        if !self.series.trim().is_empty() {
+            draft.series = self.series.clone();
+        }
+        if let Some(region) = self.region {
+            draft.region = region;
+        }
+        if let Some(numbers) = &self.numbers {
+            draft.numbers = numbers.clone();
+        }
+        for (field, value) in [
+            (&mut draft.name_de, &self.name_de),
+            (&mut draft.release_date, &self.release_date),
+            (&mut draft.set_code, &self.set_code),
+            (&mut draft.symbol_url, &self.symbol_url),
+        ] {
+            if value.is_some() {
+                *field = value.clone();
+            }
+        }
+        draft.check()
+    }
+}
+
+/// What applying a catalog would change
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CatalogDiff {
+    pub version: u32,
+    pub added: Vec<ExpansionDraft>,
+    /// Stored set and the same set after the update
+    pub updated: Vec<(Expansion, Expansion)>,
+    pub unchanged: usize,
+    /// Stored sets missing from the catalog, left as they are
+    pub local: Vec<Expansion>,
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty()
+    }
+}
+
+impl Display for CatalogDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Catalog version {}: {} to add, {} to update, {} unchanged, {} only stored locally",
+            self.version,
+            self.added.len(),
+            self.updated.len(),
+            self.unchanged,
+            self.local.len()
+        )?;
+        for draft in &self.added {
+            writeln!(f, "  + {} ({})", draft.name, draft.abbreviation)?;
+        }
+        for (before, after) in &self.updated {
+            let change = ExpansionChange::Updated {
+                before: Box::new(before.clone()),
+                after: Box::new(after.clone()),
+            };
+            writeln!(f, "  ~ {}", change.describe())?;
+        }
+        for expansion in &self.local {
+            writeln!(f, "  = {expansion} is not in the catalog and stays as is")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a catalog and checks every entry like a save on the expansions page
+pub fn parse(content: &str) -> Result<Catalog> {
+    let catalog: Catalog = toml::from_str(content).context("Invalid expansion catalog")?;
+
+    let mut names = HashSet::new();
+    let mut abbreviations = HashSet::new();
+    for (index, entry) in catalog.expansions.iter().enumerate() {
+        let draft = entry
+            .draft(None)
+            .map_err(|message| anyhow::anyhow!("Catalog entry {}: {message}", index + 1))?;
+        if !names.insert(draft.name.to_lowercase()) {
+            bail!(
+                "Catalog entry {}: the name `{}` is listed twice",
+                index + 1,
+                draft.name
+            );
+        }
+        if !abbreviations.insert(draft.abbreviation.to_lowercase()) {
+            bail!(
+                "Catalog entry {}: the abbreviation `{}` is listed twice",
+                index + 1,
+                draft.abbreviation
+            );
+        }
+    }
+    Ok(catalog)
+}
+
+/// Compares `catalog` with the stored expansions without changing them
+pub fn diff(conn: &Connection, catalog: &Catalog) -> Result<CatalogDiff> {
+    let stored = expansion_admin::list(conn)?;
+    let names: HashSet<String> = catalog
+        .expansions
+        .iter()
+        .map(|entry| entry.name.trim().to_lowercase())
+        .collect();
+    let same = |a: &str, b: &str| a.trim().to_lowercase() == b.trim().to_lowercase();
+
+    let mut matched = vec![false; stored.len()];
+    let mut diff = CatalogDiff {
+        version: catalog.version,
+        ..CatalogDiff::default()
+    };
+    for entry in &catalog.expansions {
+        // An abbreviation only matches a set whose name no other entry claims
+        let position = stored
+            .iter()
+            .position(|e| same(&e.name, &entry.name))
+            .or_else(|| {
+                stored.iter().enumerate().position(|(i, e)| {
+                    !matched[i]
+                        && same(&e.abbreviation, &entry.abbreviation)
+                        && !names.contains(&e.name.to_lowercase())
+                })
+            });
+        let draft = entry
+            .draft(position.map(|i| &stored[i]))
+            .map_err(|message| anyhow::anyhow!("Catalog entry `{}`: {message}", entry.name))?;
+        let Some(i) = position else {
+            diff.added.push(draft);
+            continue;
+        };
+        matched[i] = true;
+        let before = &stored[i];
+        let after = expansion_admin::apply(before.clone(), &draft);
+        if &after == before {
+            diff.unchanged += 1;
+        } else {
+            diff.updated.push((before.clone(), after));
+        }
+    }
+    diff.local = stored
+        .into_iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(expansion, _)| expansion)
+        .collect();
+    Ok(diff)
+}
+
+/// Writes `diff` in one transaction and returns the number of changed sets
+///
+/// Updates run before additions, so a new set can take over an abbreviation
+/// an updated one gives up.
+pub fn apply(conn: &mut Connection, diff: &CatalogDiff) -> Result<usize> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    for (before, after) in &diff.updated {
+        expansion_admin::replace(&tx, before.id, &ExpansionDraft::from(after), PROFILE)?
+            .with_context(|| format!("{before} was removed while syncing the catalog"))?;
+    }
+    for draft in &diff.added {
+        expansion_admin::insert(&tx, draft, PROFILE)?;
+    }
+    tx.commit()?;
+
+    let changed = diff.updated.len() + diff.added.len();
+    tracing::info!(
+        version = diff.version,
+        added = diff.added.len(),
+        updated = diff.updated.len(),
+        "expansion catalog applied"
+    );
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::test_connection;
+
+    fn stored(conn: &Connection, name: &str) -> Expansion {
+        expansion_admin::list(conn)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_embedded_catalog_seeds_new_databases() {
+        let catalog = parse(EMBEDDED).unwrap();
+        let conn = test_connection();
+
+        let diff = diff(&conn, &catalog).unwrap();
+        assert!(diff.is_empty(), "{diff}");
+        assert_eq!(diff.unchanged, catalog.expansions.len());
+        assert!(diff.local.is_empty());
+        assert_eq!(stored(&conn, "Jungle").abbreviation, "JU");
+        let brilliant_stars = stored(&conn, "Brilliant Stars");
+        assert_eq!(brilliant_stars.printed_number("TG5"), "TG05/TG30");
+        assert_eq!(brilliant_stars.printed_number("49"), "049/172");
+        assert_eq!(
+            stored(&conn, "Scarlet & Violet").name_de.as_deref(),
+            Some("Karmesin & Purpur")
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_catalogs() {
+        let entry = |name: &str, abbreviation: &str| {
+            format!(
+                "[[expansions]]\nname = \"{name}\"\nabbreviation = \"{abbreviation}\"\ncards = 100\n"
+            )
+        };
+        let catalog = |entries: &[String]| format!("version = 1\n{}", entries.join("\n"));
+
+        assert!(parse(&catalog(&[entry("Jungle", "JU"), entry("Fossil", "FO")])).is_ok());
+        assert!(parse(&catalog(&[entry("Jungle", "JU"), entry("jungle", "FO")])).is_err());
+        assert!(parse(&catalog(&[entry("Jungle", "JU"), entry("Fossil", "ju")])).is_err());
+        assert!(parse(&catalog(&[entry("", "JU")])).is_err());
+        assert!(parse(&format!("{}cads = 4\n", catalog(&[entry("Jungle", "JU")]))).is_err());
+    }
+
+    #[test]
+    fn test_apply_is_idempotent_and_keeps_local_sets() {
+        let mut conn = test_connection();
+        let local = expansion_admin::create(
+            &mut conn,
+            &ExpansionDraft {
+                name: "Trainer Kit".to_string(),
+                abbreviation: "TK".to_string(),
+                cards: 30,
+                ..ExpansionDraft::default()
+            },
+            "Ash",
+        )
+        .unwrap();
+        let fossil = stored(&conn, "Fossil");
+        expansion_admin::set_archived(&mut conn, fossil.id, true, "Ash").unwrap();
+
+        let catalog = parse(
+            r#"
+            version = 2
+
+            [[expansions]]
+            name = "Jungle"
+            abbreviation = "JU"
+            cards = 65
+            secret_cards = 0
+
+            [[expansions]]
+            name = "Fossil Expedition"
+            abbreviation = "FO"
+            cards = 62
+            release_date = "1999-10-10"
+
+            [[expansions]]
+            name = "Future Flash"
+            abbreviation = "FFL"
+            cards = 132
+            secret_cards = 56
+            series = "Scarlet & Violet"
+            "#,
+        )
+        .unwrap();
+
+        let changes = diff(&conn, &catalog).unwrap();
+        let mut renamed = changes.updated.clone();
+        renamed.sort_by_key(|(before, _)| before.id);
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(renamed[0].1.cards, 65);
+        assert_eq!(renamed[1].1.name, "Fossil Expedition");
+        // Left-out optional fields and the archived flag keep the stored values
+        assert_eq!(renamed[1].1.series, fossil.series);
+        assert!(renamed[1].1.archived);
+        assert!(changes.local.contains(&local));
+        assert!(changes.to_string().contains("+ Future Flash (FFL)"));
+
+        assert_eq!(apply(&mut conn, &changes).unwrap(), 3);
+        let again = diff(&conn, &catalog).unwrap();
+        assert!(again.is_empty(), "{again}");
+        assert_eq!(apply(&mut conn, &again).unwrap(), 0);
+
+        assert_eq!(stored(&conn, "Trainer Kit"), local);
+        let expedition = stored(&conn, "Fossil Expedition");
+        assert_eq!(expedition.id, fossil.id);
+        assert_eq!(expedition.release_date.as_deref(), Some("1999-10-10"));
+        let recorded = expansion_admin::changes(&conn, 4).unwrap();
+        let profiles: Vec<&str> = recorded.iter().map(|e| e.profile.as_str()).collect();
+        assert_eq!(profiles, [PROFILE, PROFILE, PROFILE, "Ash"]);
+    }
+}
@@ -1,21 +1,41 @@
+mod admin;
 mod book_navigation;
+mod bulk_selection;
+mod card_history;
 mod card_ownership_dialog;
+mod card_tags;
 mod card_view_compact;
 mod collection;
 mod dialog;
+mod expansions;
+mod language_switch;
+mod live_sync;
 mod login;
 mod nav_bar;
+mod offline_sync;
 mod placeholder_card;
 mod protected_route;
+mod query_results;
+mod saved_binder;
 pub mod statistics;
 
+pub use admin::*;
 pub use book_navigation::*;
+pub use bulk_selection::*;
+pub use card_history::*;
 pub use card_ownership_dialog::*;
+pub use card_tags::*;
 pub use card_view_compact::*;
 pub use collection::*;
 pub use dialog::*;
+pub use expansions::*;
+pub use language_switch::*;
+pub use live_sync::*;
 pub use login::*;
 pub use nav_bar::*;
+pub use offline_sync::*;
 pub use placeholder_card::*;
 pub use protected_route::*;
+pub use query_results::*;
+pub use saved_binder::*;
 pub use statistics::*;
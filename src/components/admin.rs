@@ -0,0 +1,422 @@
+use crate::{
+    backend::{
+        check_integrity_db, download_backup, get_backup_status, list_backups, repair_integrity_db,
+        restore_backup,
+    },
+    backup::{BackupEntry, BackupFile, BackupRun, BackupStatus, RestoreReport, Schedule},
+    components::{DialogContent, DialogDescription, DialogRoot, DialogTitle},
+    i18n::{t, tf},
+    integrity::{IntegrityReport, ViolationKind},
+    store,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dioxus::prelude::*;
+
+/// Hands a snapshot to the browser as a file download
+fn save_to_disk(file: BackupFile) {
+    let eval = document::eval(
+        r#"
+        const [name, data] = await dioxus.recv();
+        const bytes = Uint8Array.from(atob(data), (c) => c.charCodeAt(0));
+        const url = URL.createObjectURL(new Blob([bytes], { type: "application/vnd.sqlite3" }));
+        const link = document.createElement("a");
+        link.href = url;
+        link.download = name;
+        link.click();
+        URL.revokeObjectURL(url);
+        "#,
+    );
+    if let Err(e) = eval.send((file.file_name, file.data)) {
+        tracing::error!(error = ?e, "failed to hand snapshot to the browser");
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{b} B"),
+    }
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => t("admin.age_now").to_string(),
+        s if s < 60 * 60 => tf("admin.age_minutes", &[("count", &(s / 60))]),
+        s if s < 24 * 60 * 60 => tf("admin.age_hours", &[("count", &(s / (60 * 60)))]),
+        s => tf("admin.age_days", &[("count", &(s / (24 * 60 * 60)))]),
+    }
+}
+
+fn schedule_name(schedule: &Schedule) -> &'static str {
+    match schedule {
+        Schedule::Hourly => t("admin.schedule_hourly"),
+        Schedule::Daily => t("admin.schedule_daily"),
+    }
+}
+
+#[component]
+fn ScheduleStatus(status: Option<BackupStatus>) -> Element {
+    let Some(status) = status else {
+        return rsx! {
+            p { class: "admin-hint", {t("admin.loading_schedule")} }
+        };
+    };
+
+    let retention = match (status.hourly_keep, status.daily_keep) {
+        (0, 0) => t("admin.schedule_disabled").to_string(),
+        (hourly, daily) => tf("admin.retention", &[("hourly", &hourly), ("daily", &daily)]),
+    };
+
+    rsx! {
+        p { "{retention}" }
+        match status.last_run {
+            None => rsx! {
+                p { class: "admin-hint", {t("admin.no_scheduled_run")} }
+            },
+            Some(BackupRun { schedule, file_name: Some(file_name), pruned, seconds_ago, .. }) => rsx! {
+                div { class: "admin-report",
+                    {
+                        tf(
+                            "admin.last_backup",
+                            &[
+                                ("schedule", &schedule_name(&schedule)),
+                                ("age", &format_age(seconds_ago)),
+                                ("file", &file_name),
+                            ],
+                        )
+                    }
+                    if pruned > 0 {
+                        " "
+                        {tf("admin.pruned", &[("count", &pruned)])}
+                    }
+                }
+            },
+            Some(BackupRun { schedule, error, seconds_ago, .. }) => rsx! {
+                div { class: "error",
+                    {
+                        tf(
+                            "admin.last_backup_failed",
+                            &[
+                                ("schedule", &schedule_name(&schedule)),
+                                ("age", &format_age(seconds_ago)),
+                                ("error", &error.unwrap_or_default()),
+                            ],
+                        )
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[component]
+pub fn Admin() -> Element {
+    let mut password = use_signal(String::new);
+    let mut backups = use_signal(Vec::<BackupEntry>::new);
+    let mut upload = use_signal(|| None::<BackupFile>);
+    let mut report = use_signal(|| None::<RestoreReport>);
+    let mut confirm_open = use_signal(|| false);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(String::new);
+    let mut status = use_signal(|| None::<BackupStatus>);
+    let mut integrity = use_signal(|| None::<IntegrityReport>);
+    let mut repaired = use_signal(|| None::<(ViolationKind, usize)>);
+
+    // Load the scheduler status on mount
+    use_effect(move || {
+        spawn(async move {
+            match get_backup_status().await {
+                Ok(result) => status.set(Some(result)),
+                Err(e) => tracing::error!(error = %e, "failed to load backup status"),
+            }
+        });
+    });
+
+    let refresh = move || {
+        spawn(async move {
+            match list_backups(password.read().clone()).await {
+                Ok(entries) => {
+                    backups.set(entries);
+                    error.set(String::new());
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to list backups");
+                    error.set(tf("admin.list_failed", &[("error", &e)]));
+                }
+            }
+        });
+    };
+
+    let download = move |file_name: Option<String>| {
+        spawn(async move {
+            busy.set(true);
+            match download_backup(password.read().clone(), file_name).await {
+                Ok(file) => {
+                    tracing::info!(file_name = %file.file_name, "backup downloaded");
+                    save_to_disk(file);
+                    error.set(String::new());
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to download backup");
+                    error.set(tf("admin.download_failed", &[("error", &e)]));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let handle_file = move |evt: FormEvent| async move {
+        report.set(None);
+        let Some(file) = evt.files().into_iter().next() else {
+            upload.set(None);
+            return;
+        };
+        match file.read_bytes().await {
+            Ok(bytes) => upload.set(Some(BackupFile {
+                file_name: file.name(),
+                data: STANDARD.encode(&bytes),
+            })),
+            Err(e) => {
+                upload.set(None);
+                error.set(tf(
+                    "admin.read_failed",
+                    &[("file", &file.name()), ("error", &e)],
+                ));
+            }
+        }
+    };
+
+    let handle_restore = move |_| {
+        confirm_open.set(false);
+        let Some(file) = upload.read().clone() else {
+            return;
+        };
+        spawn(async move {
+            busy.set(true);
+            match restore_backup(password.read().clone(), file).await {
+                Ok(result) => {
+                    tracing::info!(cards = result.card_count, "database restored");
+                    report.set(Some(result));
+                    upload.set(None);
+                    error.set(String::new());
+                    refresh();
+                    // Everything cached may be stale after a restore
+                    store::expansions_changed();
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "restore failed");
+                    error.set(tf("admin.restore_failed", &[("error", &e)]));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let check = move || {
+        spawn(async move {
+            match check_integrity_db(password.read().clone()).await {
+                Ok(result) => {
+                    tracing::info!(violations = result.violations.len(), "integrity checked");
+                    integrity.set(Some(result));
+                    error.set(String::new());
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "integrity check failed");
+                    error.set(tf("admin.check_failed", &[("error", &e)]));
+                }
+            }
+        });
+    };
+
+    let repair = move |kind: ViolationKind| {
+        spawn(async move {
+            busy.set(true);
+            match repair_integrity_db(password.read().clone(), kind).await {
+                Ok(changed) => {
+                    tracing::info!(kind = ?kind, changed, "integrity repaired");
+                    repaired.set(Some((kind, changed)));
+                    error.set(String::new());
+                    store::collection_changed();
+                    check();
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, kind = ?kind, "integrity repair failed");
+                    error.set(tf("admin.repair_failed", &[("error", &e)]));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "admin-container",
+            h1 { class: "admin-title", {t("admin.title")} }
+
+            div { class: "form-group",
+                label { r#for: "admin-password", {t("login.password")} }
+                input {
+                    r#type: "password",
+                    id: "admin-password",
+                    value: "{password}",
+                    oninput: move |evt| password.set(evt.value()),
+                    placeholder: t("admin.password_placeholder"),
+                }
+            }
+
+            if !error.read().is_empty() {
+                div { class: "error", "{error}" }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("admin.backup")} }
+                p { {t("admin.backup_hint")} }
+                button {
+                    class: "admin-button",
+                    disabled: busy(),
+                    onclick: move |_| download(None),
+                    {t("admin.download_snapshot")}
+                }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("admin.scheduled_backups")} }
+                ScheduleStatus { status: status() }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("admin.stored_backups")} }
+                button { class: "admin-button", onclick: move |_| refresh(), {t("admin.refresh")} }
+                if backups.read().is_empty() {
+                    p { class: "admin-hint", {t("admin.no_backups")} }
+                } else {
+                    ul { class: "admin-backup-list",
+                        for entry in backups.read().iter().cloned() {
+                            li { key: "{entry.file_name}", class: "admin-backup-list__item",
+                                span { "{entry.file_name}" }
+                                span { class: "admin-hint", {format_size(entry.size_bytes)} }
+                                button {
+                                    class: "admin-button",
+                                    disabled: busy(),
+                                    onclick: move |_| download(Some(entry.file_name.clone())),
+                                    {t("admin.download")}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("admin.restore")} }
+                p {
+                    {t("admin.restore_hint")}
+                }
+                input {
+                    r#type: "file",
+                    accept: ".db,.sqlite,.sqlite3",
+                    onchange: handle_file,
+                }
+                button {
+                    class: "admin-button admin-button--danger",
+                    disabled: busy() || upload.read().is_none(),
+                    onclick: move |_| confirm_open.set(true),
+                    {t("admin.restore")}
+                }
+                if let Some(result) = report.read().as_ref() {
+                    div { class: "admin-report",
+                        {
+                            tf(
+                                "admin.restored",
+                                &[
+                                    ("count", &result.card_count),
+                                    ("version", &result.schema_version),
+                                    ("backup", &result.pre_restore_backup),
+                                ],
+                            )
+                        }
+                    }
+                }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("admin.integrity")} }
+                p { {t("admin.integrity_hint")} }
+                button {
+                    class: "admin-button",
+                    disabled: busy(),
+                    onclick: move |_| {
+                        repaired.set(None);
+                        check();
+                    },
+                    {t("admin.check")}
+                }
+                if let Some((kind, changed)) = repaired() {
+                    div { class: "admin-report",
+                        {tf("admin.repaired", &[("kind", &kind.title()), ("count", &changed)])}
+                    }
+                }
+                match integrity.read().as_ref() {
+                    None => rsx! {},
+                    Some(report) if report.is_clean() => rsx! {
+                        div { class: "admin-report", {t("admin.no_problems")} }
+                    },
+                    Some(report) => rsx! {
+                        for (kind , violations) in report.by_kind() {
+                            div { key: "{kind:?}", class: "integrity-group",
+                                div { class: "integrity-group__header",
+                                    h3 { "{kind.title()} ({violations.len()})" }
+                                    if kind.repairable() {
+                                        button {
+                                            class: "admin-button",
+                                            disabled: busy(),
+                                            onclick: move |_| repair(kind),
+                                            {t("admin.repair")}
+                                        }
+                                    }
+                                }
+                                p { class: "admin-hint", "{kind.description()}" }
+                                ul { class: "integrity-list",
+                                    for violation in violations {
+                                        li { key: "{violation.card_id}-{violation.print_id:?}",
+                                            span { class: "integrity-list__card", "#{violation.card_id}" }
+                                            if let Some(print_id) = violation.print_id {
+                                                span { class: "admin-hint", {tf("admin.print", &[("id", &print_id)])} }
+                                            }
+                                            span { "{violation.detail}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+
+            DialogRoot {
+                open: confirm_open(),
+                on_open_change: move |v| confirm_open.set(v),
+                DialogContent {
+                    DialogTitle { {t("admin.restore_title")} }
+                    DialogDescription {
+                        if let Some(file) = upload.read().as_ref() {
+                            {tf("admin.restore_confirm", &[("file", &file.file_name)])}
+                        }
+                    }
+                    div { class: "admin-dialog-actions",
+                        button {
+                            class: "admin-button",
+                            onclick: move |_| confirm_open.set(false),
+                            {t("common.cancel")}
+                        }
+                        button {
+                            class: "admin-button admin-button--danger",
+                            onclick: handle_restore,
+                            {t("admin.restore")}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,9 @@
-use crate::card::Page;
+use crate::{
+    backend::search_cards_db,
+    card::Page,
+    i18n::{t, tf},
+    search::SearchHit,
+};
 use dioxus::prelude::*;
 
 #[component]
@@ -9,23 +14,106 @@ pub fn BookNavigation(
     loading_card: Signal<bool>,
 ) -> Element {
     let mut search_input = use_signal(String::new);
+    let mut suggestions = use_signal(Vec::<SearchHit>::new);
+    let mut highlighted = use_signal(|| None::<usize>);
+    // Bumped on every keystroke so answers to older queries are dropped
+    let mut generation = use_signal(|| 0usize);
+
+    let mut update_suggestions = move |input: String| {
+        let current = generation() + 1;
+        generation.set(current);
+        highlighted.set(None);
+        if input.trim().is_empty() {
+            suggestions.set(Vec::new());
+            return;
+        }
+        spawn(async move {
+            match search_cards_db(input).await {
+                Ok(hits) if generation() == current => suggestions.set(hits),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "search suggestions failed"),
+            }
+        });
+    };
+
+    let mut close_suggestions = move || {
+        generation.set(generation() + 1);
+        suggestions.set(Vec::new());
+        highlighted.set(None);
+    };
+
+    let mut pick = move |hit: SearchHit| {
+        close_suggestions();
+        search_input.set(String::new());
+        on_search.call(hit.card.index.0.to_string());
+    };
 
     rsx! {
         div { class: "book-nav",
             if loading_card() {
-                div { "Loading ..." }
+                div { {t("common.loading")} }
             }
-            input {
-                r#type: "text",
-                placeholder: "Search ID or name...",
-                class: "book-nav__search",
-                value: "{search_input}",
-                oninput: move |e| search_input.set(e.value().clone()),
-                onkeypress: move |e: Event<KeyboardData>| {
-                    if e.key() == Key::Enter {
-                        on_search.call(search_input().clone());
+            div { class: "book-nav__search-box",
+                input {
+                    r#type: "text",
+                    placeholder: t("book_nav.search_placeholder"),
+                    class: "book-nav__search",
+                    value: "{search_input}",
+                    oninput: move |e| {
+                        search_input.set(e.value().clone());
+                        update_suggestions(e.value());
+                    },
+                    onblur: move |_| close_suggestions(),
+                    onkeydown: move |e: Event<KeyboardData>| {
+                        let count = suggestions.read().len();
+                        match e.key() {
+                            Key::ArrowDown if count > 0 => {
+                                e.prevent_default();
+                                highlighted.set(Some(highlighted().map_or(0, |i| (i + 1) % count)));
+                            }
+                            Key::ArrowUp if count > 0 => {
+                                e.prevent_default();
+                                highlighted
+                                    .set(Some(highlighted().map_or(count - 1, |i| (i + count - 1) % count)));
+                            }
+                            Key::Escape => close_suggestions(),
+                            Key::Enter => {
+                                let hit = highlighted().and_then(|i| suggestions.read().get(i).cloned());
+                                match hit {
+                                    Some(hit) => pick(hit),
+                                    None => {
+                                        close_suggestions();
+                                        on_search.call(search_input().clone());
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    },
+                }
+                if !suggestions.read().is_empty() {
+                    ul { class: "book-nav__suggestions",
+                        for (i , hit) in suggestions().into_iter().enumerate() {
+                            li {
+                                key: "{hit.card.index.0}",
+                                class: "book-nav__suggestion",
+                                class: if highlighted() == Some(i) { "book-nav__suggestion--active" },
+                                onmousedown: move |e| {
+                                    e.prevent_default();
+                                    pick(hit.clone());
+                                },
+                                span { class: "book-nav__suggestion-id", "#{hit.card.index}" }
+                                span { class: "book-nav__suggestion-name",
+                                    "{hit.card.primary_name()}"
+                                    if let Some(secondary) = hit.card.secondary_name() {
+                                        " / {secondary}"
+                                    }
+                                }
+                                span { class: "book-nav__suggestion-context", "{hit.context}" }
+                            }
+                        }
                     }
-                },
+                }
             }
             button {
                 class: "book-nav__button",
@@ -35,7 +123,7 @@ pub fn BookNavigation(
                         current_page.set(current_page() - 1);
                     }
                 },
-                "◀ Previous"
+                {t("book_nav.previous")}
             }
             select {
                 class: "book-nav__dropdown",
@@ -48,7 +136,9 @@ pub fn BookNavigation(
                     }
                 },
                 {(1..=total_pages).map(|p| rsx! {
-                    option { value: "{p}", "Page {p} ({Page::relative_from_absolute(Page(p)).0})" }
+                    option { value: "{p}",
+                        {tf("book_nav.page", &[("page", &p), ("relative", &Page::relative_from_absolute(Page(p)).0)])}
+                    }
                 })}
             }
             button {
@@ -59,7 +149,7 @@ pub fn BookNavigation(
                         current_page.set(current_page() + 1);
                     }
                 },
-                "Next ▶"
+                {t("book_nav.next")}
             }
         }
     }
@@ -0,0 +1,225 @@
+use crate::{
+    backend::bulk_update_ownership_db,
+    bulk::{BulkAction, BulkReport, BulkRequest, BulkStatus, ExpansionTemplate, MAX_BULK_CARDS},
+    card::Rarity,
+    config,
+    expansion::{by_series, Expansion},
+    i18n::{t, tf},
+    store,
+};
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+use strum::IntoEnumIterator;
+
+/// Toolbar of the collection's multi-select mode
+///
+/// Pockets are picked by clicking them in the book, or added a whole double
+/// page or an index range at a time. The chosen action is applied to the
+/// selection in one request and the per-card result is shown below.
+#[component]
+pub fn BulkSelection(
+    selected: Signal<BTreeSet<usize>>,
+    current_page: Signal<usize>,
+    on_applied: EventHandler<BulkReport>,
+) -> Element {
+    let mut all_expansions = use_signal(Vec::<Expansion>::new);
+    let mut expansion_id = use_signal(|| None::<usize>);
+    let mut card_number = use_signal(String::new);
+    let mut rarity = use_signal(|| Rarity::Common);
+    let mut range_start = use_signal(String::new);
+    let mut range_end = use_signal(String::new);
+    let mut report = use_signal(|| None::<BulkReport>);
+    let mut error_message = use_signal(String::new);
+    let mut is_submitting = use_signal(|| false);
+
+    // Load all expansions on mount
+    use_effect(move || {
+        spawn(async move {
+            match store::expansions().await {
+                Ok(expansions) => all_expansions.set(expansions),
+                Err(e) => {
+                    error_message.set(tf("ownership.load_expansions_failed", &[("error", &e)]))
+                }
+            }
+        });
+    });
+
+    let select_double_page = move |_| {
+        let page = config::layout().double_page(current_page());
+        selected.write().extend(page);
+    };
+
+    let select_range = move |_| {
+        let max = config::layout().max_pokemon;
+        match (
+            range_start().trim().parse::<usize>(),
+            range_end().trim().parse::<usize>(),
+        ) {
+            (Ok(start), Ok(end)) if 1 <= start && start <= end && end <= max => {
+                selected.write().extend(start..=end);
+                error_message.set(String::new());
+            }
+            _ => error_message.set(tf("bulk.range_hint", &[("max", &max)])),
+        }
+    };
+
+    let mut submit = move |action: BulkAction| {
+        let request = BulkRequest {
+            indices: selected.read().iter().copied().collect(),
+            action,
+        };
+        if let Some(problem) = request.problem() {
+            error_message.set(problem);
+            return;
+        }
+        is_submitting.set(true);
+        spawn(async move {
+            match bulk_update_ownership_db(request).await {
+                Ok(result) => {
+                    error_message.set(String::new());
+                    report.set(Some(result.clone()));
+                    on_applied.call(result);
+                }
+                Err(e) => error_message.set(tf("bulk.failed", &[("error", &e)])),
+            }
+            is_submitting.set(false);
+        });
+    };
+
+    let mark_owned = move |_| {
+        let Some(expansion_id) = expansion_id() else {
+            error_message.set(t("ownership.select_expansion").to_string());
+            return;
+        };
+        submit(BulkAction::MarkOwned {
+            template: ExpansionTemplate {
+                expansion_id,
+                card_number: card_number(),
+                rarity: rarity(),
+            },
+        });
+    };
+
+    let count = selected.read().len();
+
+    rsx! {
+        div { class: "bulk-panel",
+            div { class: "bulk-panel__row",
+                span { class: "bulk-panel__count",
+                    {tf("bulk.selected", &[("count", &count)])}
+                    if count > MAX_BULK_CARDS {
+                        " "
+                        {tf("bulk.at_most", &[("max", &MAX_BULK_CARDS)])}
+                    }
+                }
+                button {
+                    class: "book-nav__button",
+                    r#type: "button",
+                    onclick: select_double_page,
+                    {t("bulk.select_double_page")}
+                }
+                input {
+                    class: "bulk-panel__index",
+                    r#type: "number",
+                    placeholder: t("bulk.from"),
+                    value: "{range_start}",
+                    oninput: move |evt| range_start.set(evt.value()),
+                }
+                input {
+                    class: "bulk-panel__index",
+                    r#type: "number",
+                    placeholder: t("bulk.to"),
+                    value: "{range_end}",
+                    oninput: move |evt| range_end.set(evt.value()),
+                }
+                button {
+                    class: "book-nav__button",
+                    r#type: "button",
+                    onclick: select_range,
+                    {t("bulk.add_range")}
+                }
+                button {
+                    class: "book-nav__button",
+                    r#type: "button",
+                    disabled: count == 0,
+                    onclick: move |_| {
+                        selected.write().clear();
+                        report.set(None);
+                    },
+                    {t("bulk.clear")}
+                }
+            }
+
+            div { class: "bulk-panel__row",
+                select {
+                    class: "expansion-dropdown",
+                    value: expansion_id().map(|id| id.to_string()).unwrap_or_default(),
+                    onchange: move |evt| expansion_id.set(evt.value().parse::<usize>().ok()),
+                    option { value: "", {t("ownership.expansion_placeholder")} }
+                    for (series , members) in by_series(all_expansions().iter().filter(|e| !e.archived)) {
+                        optgroup { label: "{series}",
+                            for exp in members {
+                                option { value: "{exp.id}", "{exp.abbreviation}: {exp.localized_name()}" }
+                            }
+                        }
+                    }
+                }
+                input {
+                    class: "card-number-input",
+                    r#type: "text",
+                    placeholder: t("bulk.number_placeholder"),
+                    value: "{card_number}",
+                    oninput: move |evt| card_number.set(evt.value()),
+                }
+                select {
+                    class: "rarity-dropdown",
+                    value: rarity().stored(),
+                    onchange: move |evt| rarity.set(Rarity::from(evt.value().as_str())),
+                    for r in Rarity::iter() {
+                        option { value: r.stored(), "{r}" }
+                    }
+                }
+                button {
+                    class: "btn-add",
+                    r#type: "button",
+                    disabled: is_submitting() || count == 0,
+                    onclick: mark_owned,
+                    {t("bulk.mark_owned")}
+                }
+                button {
+                    class: "btn-remove",
+                    r#type: "button",
+                    disabled: is_submitting() || count == 0,
+                    onclick: move |_| submit(BulkAction::MarkUnowned),
+                    {t("bulk.mark_unowned")}
+                }
+            }
+
+            if !error_message().is_empty() {
+                div { class: "expansion-error", "{error_message()}" }
+            }
+
+            if let Some(result) = report() {
+                div { class: "bulk-report",
+                    {
+                        tf(
+                            "bulk.report",
+                            &[
+                                ("changed", &result.changed()),
+                                ("unchanged", &result.unchanged()),
+                                ("failed", &result.failures().count()),
+                            ],
+                        )
+                    }
+                    ul { class: "bulk-report__failures",
+                        for failure in result.failures() {
+                            if let BulkStatus::Failed { reason } = &failure.status {
+                                li { key: "{failure.index}", "#{failure.index}: {reason}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    backend::{get_card_history_db, undo_last_card_change_db},
+    card::Card,
+    expansion::{CardExpansion, Expansion},
+    history::{CardChange, CardEvent},
+    i18n::{t, tf},
+};
+use dioxus::prelude::*;
+
+fn describe_expansion(entry: &CardExpansion, expansions: &[Expansion]) -> String {
+    match expansions.iter().find(|e| e.id == entry.expansion_id) {
+        Some(exp) => format!(
+            "{} #{} ({})",
+            exp.abbreviation,
+            entry.display_card_number(exp),
+            entry.rarity
+        ),
+        None => tf(
+            "history.unknown_expansion",
+            &[
+                ("id", &entry.expansion_id),
+                ("number", &entry.card_number),
+                ("rarity", &entry.rarity),
+            ],
+        ),
+    }
+}
+
+fn describe(change: &CardChange, expansions: &[Expansion]) -> String {
+    match change {
+        CardChange::Ownership { after: true, .. } => t("history.owned").to_string(),
+        CardChange::Ownership { after: false, .. } => t("history.not_owned").to_string(),
+        CardChange::ExpansionAdded { expansion } => tf(
+            "history.print_added",
+            &[("print", &describe_expansion(expansion, expansions))],
+        ),
+        CardChange::ExpansionUpdated { before, after } => tf(
+            "history.print_changed",
+            &[
+                ("before", &describe_expansion(before, expansions)),
+                ("after", &describe_expansion(after, expansions)),
+            ],
+        ),
+        CardChange::ExpansionRemoved { expansion } => tf(
+            "history.print_removed",
+            &[("print", &describe_expansion(expansion, expansions))],
+        ),
+    }
+}
+
+/// Timeline of recorded changes for one card with an undo action
+#[component]
+pub fn CardHistory(
+    card_id: usize,
+    expansions: Vec<Expansion>,
+    on_undo: EventHandler<Card>,
+) -> Element {
+    let mut events = use_signal(Vec::<CardEvent>::new);
+    let mut loading = use_signal(|| true);
+    let mut undoing = use_signal(|| false);
+    let mut error_message = use_signal(String::new);
+
+    let reload = move || {
+        spawn(async move {
+            loading.set(true);
+            match get_card_history_db(card_id).await {
+                Ok(history) => events.set(history),
+                Err(e) => error_message.set(tf("history.load_failed", &[("error", &e)])),
+            }
+            loading.set(false);
+        });
+    };
+
+    // Load history on mount
+    use_effect(move || reload());
+
+    let handle_undo = move |_| {
+        undoing.set(true);
+        spawn(async move {
+            match undo_last_card_change_db(card_id).await {
+                Ok(Some(card)) => {
+                    error_message.set(String::new());
+                    on_undo.call(card);
+                }
+                Ok(None) => error_message.set(t("history.nothing_to_undo").to_string()),
+                Err(e) => error_message.set(tf("history.undo_failed", &[("error", &e)])),
+            }
+            undoing.set(false);
+            reload();
+        });
+    };
+
+    rsx! {
+        div { class: "card-history",
+            if loading() && events.read().is_empty() {
+                div { class: "dialog-loading", {t("history.loading")} }
+            } else if events.read().is_empty() {
+                div { class: "card-history__empty", {t("history.empty")} }
+            } else {
+                ul { class: "card-history__list",
+                    for event in events() {
+                        li {
+                            key: "{event.id}",
+                            class: if event.reverts.is_some() { "card-history__event card-history__event--undo" } else { "card-history__event" },
+                            span { class: "card-history__time", "{event.created_at}" }
+                            span { class: "card-history__change",
+                                if event.reverts.is_some() {
+                                    {t("history.undo_prefix")}
+                                    " "
+                                }
+                                {describe(&event.change, &expansions)}
+                            }
+                            span { class: "card-history__profile", "{event.profile}" }
+                        }
+                    }
+                }
+                button {
+                    class: "btn-undo",
+                    r#type: "button",
+                    disabled: undoing(),
+                    onclick: handle_undo,
+                    if undoing() {
+                        {t("history.undoing")}
+                    } else {
+                        {t("history.undo")}
+                    }
+                }
+            }
+            if !error_message().is_empty() {
+                div { class: "expansion-error", "{error_message()}" }
+            }
+        }
+    }
+}
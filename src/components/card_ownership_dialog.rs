@@ -1,12 +1,12 @@
 use crate::{
-    backend::{
-        delete_all_card_expansions_db, get_all_expansions_db, get_card_expansions_db, save_card_db,
-        save_card_expansion_db, update_card_db,
-    },
+    backend::save_card_ownership_db,
     card::{Bool, Card, Rarity},
-    components::{DialogContent, DialogRoot, DialogTitle},
-    expansion::{CardExpansion, Expansion},
-    utils::get_highest_rarity,
+    components::{CardHistory, CardTags, DialogContent, DialogRoot, DialogTitle},
+    expansion::{by_series, CardExpansion, Expansion},
+    i18n::{t, tf},
+    offline,
+    ownership::{EditConflict, EditProblem, OwnershipEdit, SavedOwnership},
+    store,
 };
 use dioxus::prelude::*;
 use strum::IntoEnumIterator;
@@ -25,6 +25,17 @@ pub struct ExpansionEntry {
     pub rarity: Rarity,
 }
 
+impl From<CardExpansion> for ExpansionEntry {
+    fn from(expansion: CardExpansion) -> Self {
+        ExpansionEntry {
+            id: expansion.id,
+            expansion_id: expansion.expansion_id,
+            card_number: expansion.card_number,
+            rarity: expansion.rarity,
+        }
+    }
+}
+
 #[component]
 pub fn CardOwnershipDialog(
     card: Signal<Card>,
@@ -40,6 +51,14 @@ pub fn CardOwnershipDialog(
     let mut card_expansions = use_signal(Vec::<ExpansionEntry>::new);
     let mut highest_rarity = use_signal(Rarity::default);
     let mut expansion_form_open = use_signal(|| false);
+    let mut history_open = use_signal(|| false);
+    // The card and prints the edit started from, needed to merge conflicts
+    let mut base = use_signal(|| SavedOwnership {
+        card: Card::default(),
+        expansions: Vec::new(),
+    });
+    // A save refused because the card changed elsewhere, with the refused edit
+    let mut conflict = use_signal(|| None::<(EditConflict, OwnershipEdit)>);
 
     // New expansion form state
     let mut new_expansion_id = use_signal(|| None::<usize>);
@@ -57,64 +76,92 @@ pub fn CardOwnershipDialog(
         });
     });
 
-    // Load all expansions on mount
+    // Load all expansions on mount, the store fetches them once
     use_effect(move || {
         spawn(async move {
             loading_expansions.set(true);
-            match get_all_expansions_db().await {
+            match store::expansions().await {
                 Ok(expansions) => {
                     all_expansions.set(expansions);
                 }
                 Err(e) => {
-                    error_message.set(format!("Failed to load expansions: {}", e));
+                    error_message.set(tf("ownership.load_expansions_failed", &[("error", &e)]));
                 }
             }
             loading_expansions.set(false);
         });
     });
 
-    // Load card expansions when dialog opens
+    let mut show_prints = move |expansions: Vec<CardExpansion>| {
+        let rarity = expansions.iter().map(|ce| ce.rarity.clone()).max();
+        highest_rarity.set(rarity.unwrap_or_default());
+        card_expansions.set(expansions.into_iter().map(ExpansionEntry::from).collect());
+    };
+
+    // Load card expansions when dialog opens, a new version alone keeps the entries
+    let shown = use_memo(move || (dialog_open(), card.read().index.0, card.read().owned.0));
     use_effect(move || {
-        if card.cloned().owned.0 {
+        let (_, index, owned) = shown();
+        base.set(SavedOwnership {
+            card: card.peek().clone(),
+            expansions: Vec::new(),
+        });
+        if owned {
             spawn(async move {
-                let index = card.peek().index.0;
-                match get_card_expansions_db(index).await {
+                match store::card_prints(index).await {
                     Ok(expansions) => {
-                        let entries: Vec<ExpansionEntry> = expansions
-                            .into_iter()
-                            .map(|ce| ExpansionEntry {
-                                id: ce.id,
-                                expansion_id: ce.expansion_id,
-                                card_number: ce.card_number,
-                                rarity: ce.rarity,
-                            })
-                            .collect();
-                        card_expansions.set(entries);
-                        // set highest rarity
-                        let rarity = get_highest_rarity(index).await;
-                        highest_rarity.set(rarity);
+                        base.write().expansions = expansions.clone();
+                        show_prints(expansions);
                     }
                     Err(e) => {
-                        error_message.set(format!("Failed to load card expansions: {}", e));
+                        error_message.set(tf("ownership.load_prints_failed", &[("error", &e)]));
                     }
                 }
             });
         }
     });
 
+    let selected_expansion = use_memo(move || {
+        let id = new_expansion_id()?;
+        all_expansions.read().iter().find(|e| e.id == id).cloned()
+    });
+
+    // Why the typed number does not fit the selected expansion, checked while typing
+    let number_problem = use_memo(move || {
+        let expansion = selected_expansion()?;
+        let typed = new_card_number();
+        if typed.trim().is_empty() {
+            return None;
+        }
+        expansion.check_number(&typed).err()
+    });
+
     // Add expansion to list
     let add_expansion = move |_| {
         if let Some(exp_id) = new_expansion_id() {
             if !new_card_number().trim().is_empty() {
+                let checked = match selected_expansion() {
+                    Some(expansion) => expansion.check_number(&new_card_number()),
+                    None => Err(t("ownership.select_expansion").to_string()),
+                };
+                let card_number = match checked {
+                    Ok(number) => number.to_string(),
+                    Err(reason) => {
+                        error_message.set(reason);
+                        return;
+                    }
+                };
                 let mut expansions = card_expansions.read().clone();
 
                 // Check for duplicates and swap it (only when card number is NOT the same)
-                if let Some((index, _)) = expansions.iter().enumerate().find(|(_, ex)| {
-                    ex.expansion_id == exp_id && ex.card_number == new_card_number()
-                }) {
+                if let Some((index, _)) = expansions
+                    .iter()
+                    .enumerate()
+                    .find(|(_, ex)| ex.expansion_id == exp_id && ex.card_number == card_number)
+                {
                     let ex = expansions.swap_remove(index);
                     let entry = ExpansionEntry {
-                        card_number: new_card_number(),
+                        card_number,
                         rarity: new_rarity(),
                         ..ex
                     };
@@ -124,7 +171,7 @@ pub fn CardOwnershipDialog(
                     expansions.push(ExpansionEntry {
                         id: None,
                         expansion_id: exp_id,
-                        card_number: new_card_number(),
+                        card_number,
                         rarity: new_rarity(),
                     });
                 }
@@ -136,10 +183,10 @@ pub fn CardOwnershipDialog(
                 new_card_number.set(String::new());
                 error_message.set(String::new());
             } else {
-                error_message.set("Card number is required".to_string());
+                error_message.set(t("ownership.number_required").to_string());
             }
         } else {
-            error_message.set("Please select an expansion".to_string());
+            error_message.set(t("ownership.select_expansion").to_string());
         }
     };
 
@@ -149,7 +196,7 @@ pub fn CardOwnershipDialog(
 
         // Prevent removing the last expansion
         if expansions.len() <= 1 {
-            error_message.set("At least one expansion is required".to_string());
+            error_message.set(t("ownership.expansion_required").to_string());
             return;
         }
 
@@ -160,91 +207,123 @@ pub fn CardOwnershipDialog(
         }
     };
 
-    // Handle add to collection
-    let handle_add_to_collection = move |_| {
-        if card_expansions().is_empty() {
-            error_message.set("At least one expansion is required".to_string());
-            return;
-        }
-
+    // Applies the dialog state in a single request, the server saves it atomically
+    let mut submit = move |owned: bool| {
         is_submitting.set(true);
-        card.write().owned = Bool(true);
-        let card_clone = card.read().clone();
-
-        let expansions_clone = card_expansions();
 
-        spawn(async move {
-            // Save or update card
-            let save_result = save_card_db(card_clone.clone()).await;
-
-            if let Err(e) = save_result {
-                error_message.set(format!("Failed to save card: {}", e));
-                is_submitting.set(false);
-                return;
-            }
-
-            // Delete all existing expansions for this card
-            if let Err(e) = delete_all_card_expansions_db(card.cloned().index.0).await {
-                error_message.set(format!("Failed to delete expansions: {}", e));
-                is_submitting.set(false);
-                return;
-            }
-
-            // Insert all expansions from the current list
-            for entry in expansions_clone.iter() {
-                let card_expansion = CardExpansion {
-                    id: None, // Always insert as new (id will be auto-generated)
-                    card_id: card.cloned().index.0,
+        let mut edited = card.read().clone();
+        edited.owned = Bool(owned);
+        let card_id = edited.index.0;
+        let expansions = if owned {
+            card_expansions()
+                .into_iter()
+                .map(|entry| CardExpansion {
+                    id: entry.id,
+                    card_id,
                     expansion_id: entry.expansion_id,
-                    card_number: entry.card_number.clone(),
-                    rarity: entry.rarity.clone(),
-                };
+                    card_number: entry.card_number,
+                    rarity: entry.rarity,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-                if let Err(e) = save_card_expansion_db(card_expansion).await {
-                    error_message.set(format!("Failed to save expansion: {}", e));
+        spawn(async move {
+            let edit = OwnershipEdit {
+                card: edited,
+                expansions,
+            };
+            match save_card_ownership_db(edit.clone()).await {
+                Ok(saved) => {
+                    is_submitting.set(false);
+                    dialog_open.set(false);
+                    expansion_form_open.set(false);
+                    on_change.call(saved.card);
+                }
+                Err(e) => {
+                    let problems = EditProblem::from_error(&e);
+                    if offline::is_unreachable(&e) {
+                        // Sent later, once the server can be reached again
+                        store::queue_edit(edit, base.peek().clone());
+                        dialog_open.set(false);
+                        expansion_form_open.set(false);
+                    } else if let Some(stale) = EditConflict::from_error(&e) {
+                        error_message.set(String::new());
+                        conflict.set(Some((stale, edit)));
+                    } else if problems.is_empty() {
+                        error_message.set(tf("collection.save_failed", &[("error", &e)]));
+                    } else {
+                        error_message.set(
+                            problems
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                        );
+                    }
                     is_submitting.set(false);
-                    return;
                 }
             }
-
-            // Success
-            is_submitting.set(false);
-            dialog_open.set(false);
-            expansion_form_open.set(false);
-            on_change.call(card.cloned());
         });
     };
 
-    // Handle remove from collection
-    let handle_remove_from_collection = move |_| {
-        is_submitting.set(true);
-        card.write().owned = Bool(false);
-
-        spawn(async move {
-            // Delete all expansion associations
-            let delete_result = delete_all_card_expansions_db(card.cloned().index.0).await;
+    // Handle add to collection
+    let handle_add_to_collection = move |_| {
+        if card_expansions().is_empty() {
+            error_message.set(t("ownership.expansion_required").to_string());
+            return;
+        }
+        submit(true);
+    };
 
-            if let Err(e) = delete_result {
-                error_message.set(format!("Failed to delete expansions: {}", e));
-                is_submitting.set(false);
-                return;
-            }
+    // Handle remove from collection
+    let handle_remove_from_collection = move |_| submit(false);
 
-            // Update card to owned=false
-            let update_result = update_card_db(card.cloned()).await;
+    // Conflict: drop the edit and show what is stored now
+    let reload_theirs = move |_| {
+        let Some((stale, _)) = conflict.take() else {
+            return;
+        };
+        let current = stale.current;
+        base.set(current.clone());
+        card.set(current.card.clone());
+        show_prints(current.expansions);
+        error_message.set(String::new());
+        on_change.call(current.card);
+    };
 
-            if let Err(e) = update_result {
-                error_message.set(format!("Failed to update card: {}", e));
-                is_submitting.set(false);
-                return;
-            }
+    // Conflict: replay the edit on the stored state and let the user review it
+    let merge_mine = move |_| {
+        let Some((stale, mine)) = conflict.take() else {
+            return;
+        };
+        let merged = stale.merge(&base.peek(), &mine);
+        base.set(stale.current.clone());
+        card.set(stale.current.card);
+        show_prints(merged.expansions);
+        error_message.set(t("ownership.merged").to_string());
+    };
 
-            // Success
-            is_submitting.set(false);
-            dialog_open.set(false);
-            expansion_form_open.set(false);
-            on_change.call(card.cloned());
-        });
+    // Conflict: save the edit again on top of the stored version
+    let overwrite_theirs = move |_| {
+        let Some((stale, mine)) = conflict.take() else {
+            return;
+        };
+        // Entries deleted elsewhere are added again
+        let entries = card_expansions()
+            .into_iter()
+            .map(|entry| match entry.id {
+                Some(id) if !stale.current.expansions.iter().any(|e| e.id == Some(id)) => {
+                    ExpansionEntry { id: None, ..entry }
+                }
+                _ => entry,
+            })
+            .collect();
+        card_expansions.set(entries);
+        base.set(stale.current.clone());
+        card.write().version = stale.current.card.version;
+        submit(mine.card.owned.0);
     };
 
     rsx! {
@@ -257,40 +336,44 @@ pub fn CardOwnershipDialog(
                 new_card_number.set(String::new());
                 error_message.set(String::new());
                 expansion_form_open.set(false);
+                history_open.set(false);
+                conflict.set(None);
             },
             DialogContent {
                 button {
                     class: "dialog-close",
                     r#type: "button",
-                    aria_label: "Close",
+                    aria_label: t("common.close"),
                     onclick: move |_| dialog_open.set(false),
                     "×"
                 }
 
-                DialogTitle { "Card Details" }
+                DialogTitle { {t("ownership.title")} }
 
                 div { class: "card-dialog-content",
                     // Card Image
                     div { class: "card-dialog-image",
                         img {
                             src: "{card.cloned().img_url}",
-                            alt: "{card.cloned().name_en}",
+                            alt: "{card.cloned().primary_name()}",
                         }
                     }
 
                     // Card Names
-                    div { class: "card-dialog-name-de", "{card.cloned().name_de}" }
-                    div { class: "card-dialog-name-en", "{card.cloned().name_en}" }
+                    div { class: "card-dialog-name-primary", "{card.cloned().primary_name()}" }
+                    if let Some(secondary) = card.cloned().secondary_name() {
+                        div { class: "card-dialog-name-secondary", "{secondary}" }
+                    }
 
                     // Card Info
                     div { class: "card-dialog-info",
-                        div { "ID: #{card.cloned().index}" }
+                        div { {tf("ownership.id", &[("id", &card.cloned().index)])} }
                         if card.cloned().owned.0 {
-                            div { "Rarity: {highest_rarity.cloned()}" }
+                            div { {tf("ownership.rarity", &[("rarity", &highest_rarity.cloned())])} }
                         }
                     }
                     div { class: "expansion-manager",
-                        h3 { class: "expansion-manager-title", "Expansions" }
+                        h3 { class: "expansion-manager-title", {t("ownership.expansions")} }
                         if card_expansions().is_empty() {
                             div { class: "expansion-list",
                                 div { class: "expansion-item", key: "0",
@@ -308,11 +391,18 @@ pub fn CardOwnershipDialog(
                                         key: "{index}",
                                         div { class: "expansion-item-info",
                                             if let Some(exp) = all_expansions().iter().find(|e| e.id == entry.expansion_id) {
+                                                if let Some(symbol) = &exp.symbol_url {
+                                                    img {
+                                                        class: "expansion-symbol",
+                                                        src: "{symbol}",
+                                                        alt: "{exp.abbreviation}",
+                                                    }
+                                                }
                                                 span { class: "expansion-name",
-                                                    "{exp.abbreviation}: {exp.name}"
+                                                    "{exp.abbreviation}: {exp.localized_name()}"
                                                 }
                                                 span { class: "expansion-card-num",
-                                                    "#{entry.card_number}"
+                                                    "#{exp.printed_number(&entry.card_number)}"
                                                 }
                                                 span { class: "expansion-card-num", "{entry.rarity}" }
                                             }
@@ -321,7 +411,7 @@ pub fn CardOwnershipDialog(
                                             class: "btn-delete-expansion",
                                             r#type: "button",
                                             onclick: move |_| remove_expansion(index),
-                                            "Remove"
+                                            {t("common.remove")}
                                         }
                                     }
                                 }
@@ -342,31 +432,39 @@ pub fn CardOwnershipDialog(
                                         new_expansion_id.set(None);
                                     }
                                 },
-                                option { value: "", "Select expansion..." }
-                                for exp in all_expansions().iter() {
-                                    option { value: "{exp.id}", "{exp.abbreviation}: {exp.name}" }
+                                option { value: "", {t("ownership.expansion_placeholder")} }
+                                for (series , members) in by_series(all_expansions().iter().filter(|e| !e.archived)) {
+                                    optgroup { label: "{series}",
+                                        for exp in members {
+                                            option { value: "{exp.id}", "{exp.abbreviation}: {exp.localized_name()}" }
+                                        }
+                                    }
                                 }
                             }
 
                             input {
-                                class: "card-number-input",
+                                class: if number_problem().is_some() { "card-number-input card-number-input--invalid" } else { "card-number-input" },
                                 r#type: "text",
-                                placeholder: "Card number",
+                                placeholder: match selected_expansion() {
+                                    Some(expansion) => tf("ownership.number_hint", &[("examples", &expansion.numbers.hint(expansion.cards))]),
+                                    None => t("ownership.number_placeholder").to_string(),
+                                },
+                                title: number_problem().unwrap_or_default(),
                                 value: "{new_card_number()}",
                                 oninput: move |evt| new_card_number.set(evt.value()),
                             }
 
                             select {
                                 class: "rarity-dropdown",
-                                value: new_rarity().to_string(),
+                                value: new_rarity().stored(),
                                 onchange: move |evt| {
                                     let Ok(rarity_str) = evt.value().parse::<String>();
                                     new_rarity.set(Rarity::from(rarity_str.as_str()));
 
                                 },
-                                option { value: "", "Select rarity..." }
+                                option { value: "", {t("ownership.rarity_placeholder")} }
                                 for rarity in all_rarities().iter() {
-                                    option { value: "{rarity}", "{rarity}" }
+                                    option { value: rarity.stored(), "{rarity}" }
                                 }
                             }
                             div { class: "card-expansion-actions",
@@ -374,13 +472,13 @@ pub fn CardOwnershipDialog(
                                     class: "btn-add-expansion",
                                     r#type: "button",
                                     onclick: add_expansion,
-                                    "+ Add Expansion"
+                                    {t("ownership.add_print")}
                                 }
                                 button {
                                     class: "btn-close-expansion-form",
                                     r#type: "button",
                                     onclick: move |_| expansion_form_open.set(false),
-                                    "Close"
+                                    {t("common.close")}
                                 }
                             }
                         }
@@ -389,7 +487,72 @@ pub fn CardOwnershipDialog(
                             class: "btn-open-expansion-form",
                             r#type: "button",
                             onclick: move |_| expansion_form_open.set(true),
-                            "Add Expansion"
+                            {t("ownership.open_print_form")}
+                        }
+                    }
+
+                    // Tags of cards in the collection
+                    if matches!(mode, DialogMode::Edit) {
+                        CardTags { card_id: card.cloned().index.0 }
+                    }
+
+                    // Change history
+                    div { class: "card-history-section",
+                        button {
+                            class: "btn-open-history",
+                            r#type: "button",
+                            onclick: move |_| history_open.toggle(),
+                            if history_open() {
+                                {t("ownership.hide_history")}
+                            } else {
+                                {t("ownership.show_history")}
+                            }
+                        }
+                        if history_open() {
+                            CardHistory {
+                                card_id: card.cloned().index.0,
+                                expansions: all_expansions(),
+                                on_undo: move |restored: Card| {
+                                    history_open.set(false);
+                                    dialog_open.set(false);
+                                    on_change.call(restored);
+                                },
+                            }
+                        }
+                    }
+
+                    // Stale edit, let the user decide how to go on
+                    if let Some((stale, _)) = conflict() {
+                        div { class: "edit-conflict", role: "alert",
+                            p { class: "edit-conflict__message", "{stale}" }
+                            p { class: "edit-conflict__theirs",
+                                if stale.current.card.owned.0 {
+                                    {tf("ownership.now_owned", &[("count", &stale.current.expansions.len())])}
+                                } else {
+                                    {t("ownership.now_not_owned")}
+                                }
+                            }
+                            div { class: "edit-conflict__actions",
+                                button {
+                                    class: "edit-conflict__button",
+                                    r#type: "button",
+                                    onclick: reload_theirs,
+                                    {t("ownership.reload_theirs")}
+                                }
+                                button {
+                                    class: "edit-conflict__button",
+                                    r#type: "button",
+                                    onclick: merge_mine,
+                                    {t("ownership.merge_mine")}
+                                }
+                                button {
+                                    class: "edit-conflict__button edit-conflict__button--danger",
+                                    r#type: "button",
+                                    disabled: is_submitting(),
+                                    onclick: overwrite_theirs,
+                                    {t("ownership.overwrite")}
+                                }
+                            }
                         }
                     }
 
@@ -407,9 +570,9 @@ pub fn CardOwnershipDialog(
                                 disabled: is_submitting(),
                                 onclick: handle_add_to_collection,
                                 if is_submitting() {
-                                    "Adding..."
+                                    {t("ownership.adding")}
                                 } else {
-                                    "Add to Collection"
+                                    {t("ownership.add")}
                                 }
                             }
                         }
@@ -421,9 +584,9 @@ pub fn CardOwnershipDialog(
                                 disabled: is_submitting() || card_expansions().is_empty(),
                                 onclick: handle_add_to_collection,
                                 if is_submitting() {
-                                    "Saving..."
+                                    {t("common.saving")}
                                 } else {
-                                    "Save Changes"
+                                    {t("ownership.save")}
                                 }
                             }
                             button {
@@ -431,9 +594,9 @@ pub fn CardOwnershipDialog(
                                 disabled: is_submitting(),
                                 onclick: handle_remove_from_collection,
                                 if is_submitting() {
-                                    "Removing..."
+                                    {t("ownership.removing")}
                                 } else {
-                                    "Remove from Collection"
+                                    {t("ownership.remove")}
                                 }
                             }
                         }
@@ -0,0 +1,87 @@
+use crate::{
+    backend::{get_card_tags_db, set_card_tags_db},
+    i18n::{t, tf},
+    store,
+};
+use dioxus::prelude::*;
+
+/// Tags of a stored card with a field to add more, see [`crate::tags`]
+#[component]
+pub fn CardTags(card_id: usize) -> Element {
+    let mut tags = use_signal(Vec::<String>::new);
+    let mut new_tag = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+    let mut error_message = use_signal(String::new);
+
+    // Load tags on mount
+    use_effect(move || {
+        spawn(async move {
+            match get_card_tags_db(card_id).await {
+                Ok(stored) => tags.set(stored),
+                Err(e) => error_message.set(tf("tags.load_failed", &[("error", &e)])),
+            }
+        });
+    });
+
+    // Saved searches and counts may filter by tag, reload them after a change
+    let mut save = move |next: Vec<String>| {
+        saving.set(true);
+        spawn(async move {
+            match set_card_tags_db(card_id, next).await {
+                Ok(stored) => {
+                    tags.set(stored);
+                    new_tag.set(String::new());
+                    error_message.set(String::new());
+                    store::collection_changed();
+                }
+                Err(e) => error_message.set(tf("tags.save_failed", &[("error", &e)])),
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "card-tags",
+            span { class: "card-tags__label", {t("tags.title")} }
+            ul { class: "card-tags__list",
+                for tag in tags() {
+                    li { key: "{tag}", class: "card-tags__tag",
+                        "{tag}"
+                        button {
+                            class: "card-tags__remove",
+                            r#type: "button",
+                            aria_label: tf("tags.remove", &[("tag", &tag)]),
+                            disabled: saving(),
+                            onclick: move |_| save(tags().into_iter().filter(|t| *t != tag).collect()),
+                            "×"
+                        }
+                    }
+                }
+            }
+            form {
+                class: "card-tags__form",
+                onsubmit: move |e| {
+                    e.prevent_default();
+                    let mut next = tags();
+                    next.push(new_tag());
+                    save(next);
+                },
+                input {
+                    class: "card-tags__input",
+                    placeholder: t("tags.placeholder"),
+                    value: new_tag(),
+                    oninput: move |evt| new_tag.set(evt.value()),
+                }
+                button {
+                    class: "btn-add-tag",
+                    r#type: "submit",
+                    disabled: saving() || new_tag().trim().is_empty(),
+                    {t("tags.add")}
+                }
+            }
+            if !error_message().is_empty() {
+                div { class: "expansion-error", "{error_message()}" }
+            }
+        }
+    }
+}
@@ -1,42 +1,52 @@
-use crate::{
-    card::{Card, Rarity},
-    utils::get_highest_rarity,
-};
+use crate::card::{Card, Rarity};
 use dioxus::prelude::*;
 
+/// An owned card in a binder pocket
+///
+/// `highest_rarity` and `prints` come with the page summary, see
+/// [`crate::page_view`]. Cards without prints are styled as common.
 #[component]
-pub fn CardViewCompact(card: Card, onclick: EventHandler<usize>) -> Element {
-    let mut rarity_class = use_signal(String::new);
-
-    use_effect(move || {
-        spawn(async move {
-            let class = match get_highest_rarity(card.index.0).await {
-                Rarity::Common => "card-compact--rarity-common",
-                Rarity::Uncommon => "card-compact--rarity-uncommon",
-                Rarity::Rare => "card-compact--rarity-rare",
-                Rarity::HoloRare => "card-compact--rarity-holo-rare",
-                Rarity::ReverseHoloRare => "card-compact--rarity-reverse-holo-rare",
-                Rarity::DoubleRare => "card-compact--rarity-double-rare",
-                Rarity::UltraRare => "card-compact--rarity-ultra-rare",
-                Rarity::SecretRare => "card-compact--rarity-secret-rare",
-                Rarity::Promo => "card-compact--rarity-promo",
-            };
-            rarity_class.set(class.to_string());
-        });
-    });
+pub fn CardViewCompact(
+    card: Card,
+    onclick: EventHandler<usize>,
+    #[props(default)] selected: bool,
+    #[props(default)] highest_rarity: Option<Rarity>,
+    #[props(default)] prints: usize,
+) -> Element {
+    let rarity_class = match highest_rarity.unwrap_or_default() {
+        Rarity::Common => "card-compact--rarity-common",
+        Rarity::Uncommon => "card-compact--rarity-uncommon",
+        Rarity::Rare => "card-compact--rarity-rare",
+        Rarity::HoloRare => "card-compact--rarity-holo-rare",
+        Rarity::ReverseHoloRare => "card-compact--rarity-reverse-holo-rare",
+        Rarity::DoubleRare => "card-compact--rarity-double-rare",
+        Rarity::UltraRare => "card-compact--rarity-ultra-rare",
+        Rarity::SecretRare => "card-compact--rarity-secret-rare",
+        Rarity::Promo => "card-compact--rarity-promo",
+    };
 
     rsx! {
         div {
             class: "card-compact card-compact--owned {rarity_class}",
+            class: if selected { "card-compact--selected" },
             onclick: move |_| onclick.call(card.index.0),
             div { class: "card-compact__meta",
                 span { "#{card.index}" }
+                if prints > 1 {
+                    span {
+                        class: "card-compact__prints",
+                        title: "{prints} prints",
+                        "×{prints}"
+                    }
+                }
             }
             div { class: "card-compact__sprite",
-                img { src: "{card.img_url}", alt: "{card.name_en}" }
+                img { src: "{card.img_url}", alt: "{card.primary_name()}" }
+            }
+            div { class: "card-compact__name--primary", "{card.primary_name()}" }
+            if let Some(secondary) = card.secondary_name() {
+                div { class: "card-compact__name--secondary", "{secondary}" }
             }
-            div { class: "card-compact__name--de", "{card.name_de}" }
-            div { class: "card-compact__name--en", "{card.name_en}" }
         }
     }
 }
@@ -1,27 +1,38 @@
 use crate::{
     backend::{
-        get_all_owned_cards_db, get_card_by_id_db, get_card_by_id_remote, get_card_by_name_db,
-        get_card_by_name_remote, save_card_db,
+        get_card_by_id_db, get_card_by_id_remote, get_card_by_name_db, get_card_by_name_remote,
+        query_cards_db,
     },
+    bulk::{BulkReport, BulkStatus},
     card::{Card, Page},
     components::{
-        BookNavigation, CardOwnershipDialog, CardViewCompact, DialogContent, DialogDescription,
-        DialogMode, DialogRoot, DialogTitle, PlaceholderCard,
+        BookNavigation, BulkSelection, CardOwnershipDialog, CardViewCompact, DialogContent,
+        DialogDescription, DialogMode, DialogRoot, DialogTitle, PlaceholderCard, QueryErrorMessage,
+        QueryResults, SaveQueryForm,
     },
-    CARDS_PER_DOUBLE_PAGE, MAX_POKEMON, TOTAL_PAGES,
+    config,
+    i18n::{t, tf},
+    names::NameNotFound,
+    page_view::PocketSummary,
+    query::{self, QueryError, QueryResult},
+    store, COLLECTION_REVISION,
 };
 use dioxus::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 
 #[component]
 pub fn Collection() -> Element {
     let mut current_page = use_signal(|| 1usize);
-    let mut owned_cards = use_signal(HashMap::<usize, Card>::new);
     let mut dialog_open = use_signal(|| false);
     let mut selected_index = use_signal(|| None::<usize>);
     let mut error_message = use_signal(String::new);
     let mut loading_card = use_signal(|| false);
     let mut temp_card = use_signal(Card::default);
+    let mut select_mode = use_signal(|| false);
+    let mut selected = use_signal(BTreeSet::<usize>::new);
+    let mut name_suggestions = use_signal(Vec::<String>::new);
+    let mut active_query = use_signal(|| None::<(String, QueryResult)>);
+    let mut query_error = use_signal(|| None::<(String, QueryError)>);
 
     // Check if mobile viewport
     let is_mobile = use_signal(|| {
@@ -40,28 +51,45 @@ pub fn Collection() -> Element {
         }
     });
 
-    // Load owned cards on mount
-    use_effect(move || {
+    // Fetch the pocket summaries of one double page unless the store has them
+    let load_page = move |page: usize| {
         spawn(async move {
-            match get_all_owned_cards_db().await {
-                Ok(cards) => {
-                    owned_cards.set(cards);
-                }
-                Err(e) => {
-                    error_message.set(format!("Failed to load collection: {}", e));
-                }
+            if let Err(e) = store::load_page(page).await {
+                error_message.set(tf("collection.load_failed", &[("error", &e)]));
             }
         });
+    };
+
+    // Load the visible double page first, then prefetch its neighbours
+    let load_around = move |page: usize| {
+        load_page(page);
+        load_page(page + 1);
+        load_page(page.saturating_sub(1));
+    };
+
+    // Invalidated pages are fetched again when the revision changes
+    use_effect(move || {
+        let _revision = COLLECTION_REVISION();
+        load_around(current_page());
     });
 
     // Handle card click
     let handle_card_click = move |index: usize| {
+        // In select mode a click only toggles the pocket
+        if select_mode() {
+            let mut selection = selected.write();
+            if !selection.remove(&index) {
+                selection.insert(index);
+            }
+            return;
+        }
+
         selected_index.set(Some(index));
         dialog_open.set(true);
 
-        // Check if card is owned
-        if let Some(card) = owned_cards.read().get(&index) {
-            temp_card.set(card.clone());
+        // Use the stored card if its page is cached
+        if let Some(card) = store::stored_card(index) {
+            temp_card.set(card);
         } else {
             loading_card.set(true);
             spawn(async move {
@@ -78,14 +106,19 @@ pub fn Collection() -> Element {
                             Ok(card) => {
                                 temp_card.set(card.clone());
                                 loading_card.set(false);
-                                if let Err(e) = save_card_db(card.clone()).await {
-                                    error_message.set(format!("Failed to save card: {}", e));
-                                    loading_card.set(false);
-                                    dialog_open.set(false);
+                                match store::save_fetched_card(card).await {
+                                    Ok(stored) => temp_card.set(stored),
+                                    Err(e) => {
+                                        error_message
+                                            .set(tf("collection.save_failed", &[("error", &e)]));
+                                        loading_card.set(false);
+                                        dialog_open.set(false);
+                                    }
                                 }
                             }
                             Err(e) => {
-                                error_message.set(format!("Failed to fetch card: {}", e));
+                                name_suggestions.set(NameNotFound::suggestions(&e));
+                                error_message.set(tf("collection.fetch_failed", &[("error", &e)]));
                                 loading_card.set(false);
                                 dialog_open.set(false);
                             }
@@ -96,24 +129,63 @@ pub fn Collection() -> Element {
         }
     };
 
-    // Handle ownership change (from dialog)
-    let handle_ownership_change = move |card: Card| {
-        let index = card.index.0;
+    let mut close_error = move || {
+        error_message.set(String::new());
+        name_suggestions.set(Vec::new());
+    };
 
-        if card.owned.0 {
-            // Add to collection
-            owned_cards.write().insert(index, card.clone());
-        } else {
-            // Remove from collection
-            owned_cards.write().remove(&index);
+    // Run a filter query, parse errors are shown below the search box
+    let mut run_query = move |input: String| {
+        loading_card.set(true);
+        spawn(async move {
+            match query_cards_db(input.clone()).await {
+                Ok(result) => {
+                    query_error.set(None);
+                    active_query.set(Some((input, result)));
+                }
+                Err(e) => match QueryError::from_error(&e) {
+                    Some(error) => query_error.set(Some((input, error))),
+                    None => error_message.set(tf("collection.filter_failed", &[("error", &e)])),
+                },
+            }
+            loading_card.set(false);
+        });
+    };
+
+    // Keep a filtered grid in step with changes from here or other devices
+    use_effect(move || {
+        let _revision = COLLECTION_REVISION();
+        let active = active_query.peek().clone();
+        if let Some((input, _)) = active {
+            run_query(input);
         }
+    });
+
+    // Handle ownership change (from dialog)
+    let handle_ownership_change = move |card: Card| store::card_changed(&card);
+
+    // Handle bulk update, failed cards stay selected for another try
+    let handle_bulk_applied = move |report: BulkReport| {
+        selected.write().retain(|index| {
+            report
+                .results
+                .iter()
+                .any(|r| r.index == *index && matches!(r.status, BulkStatus::Failed { .. }))
+        });
+        store::collection_changed();
     };
 
     // Handle search
-    let handle_search = move |input: String| {
+    let mut handle_search = move |input: String| {
         if input.is_empty() {
             return;
         }
+        query_error.set(None);
+
+        if query::is_query(&input) {
+            run_query(input);
+            return;
+        }
 
         // Try parsing as ID first
         if let Ok(index) = input.parse::<usize>() {
@@ -138,14 +210,18 @@ pub fn Collection() -> Element {
                                 temp_card.set(card.clone());
                                 loading_card.set(false);
                                 dialog_open.set(true);
-                                if let Err(e) = save_card_db(card.clone()).await {
-                                    error_message.set(format!("Failed to save card: {}", e));
-                                    loading_card.set(false);
-                                    dialog_open.set(false);
+                                match store::save_fetched_card(card).await {
+                                    Ok(stored) => temp_card.set(stored),
+                                    Err(e) => {
+                                        error_message
+                                            .set(tf("collection.save_failed", &[("error", &e)]));
+                                        loading_card.set(false);
+                                        dialog_open.set(false);
+                                    }
                                 }
                             }
                             Err(e) => {
-                                error_message.set(format!("Failed to fetch card: {}", e));
+                                error_message.set(tf("collection.fetch_failed", &[("error", &e)]));
                                 loading_card.set(false);
                                 dialog_open.set(false);
                             }
@@ -176,14 +252,19 @@ pub fn Collection() -> Element {
                                 loading_card.set(false);
                                 selected_index.set(Some(card.index.0));
                                 dialog_open.set(true);
-                                if let Err(e) = save_card_db(card.clone()).await {
-                                    error_message.set(format!("Failed to save card: {}", e));
-                                    loading_card.set(false);
-                                    dialog_open.set(false);
+                                match store::save_fetched_card(card).await {
+                                    Ok(stored) => temp_card.set(stored),
+                                    Err(e) => {
+                                        error_message
+                                            .set(tf("collection.save_failed", &[("error", &e)]));
+                                        loading_card.set(false);
+                                        dialog_open.set(false);
+                                    }
                                 }
                             }
                             Err(e) => {
-                                error_message.set(format!("Failed to fetch card: {}", e));
+                                name_suggestions.set(NameNotFound::suggestions(&e));
+                                error_message.set(tf("collection.fetch_failed", &[("error", &e)]));
                                 loading_card.set(false);
                                 dialog_open.set(false);
                             }
@@ -196,13 +277,12 @@ pub fn Collection() -> Element {
 
     // Calculate cards for current page
     let cards_for_page = move || {
-        let page = current_page();
-        let start = (page - 1) * CARDS_PER_DOUBLE_PAGE + 1;
-        let end = (start + CARDS_PER_DOUBLE_PAGE - 1).min(MAX_POKEMON);
-        (start..=end).collect::<Vec<_>>()
+        config::layout()
+            .double_page(current_page())
+            .collect::<Vec<_>>()
     };
 
-    // Render page side (12 cards)
+    // Render page side (half a double page)
     let render_page_side = move |indices: Vec<usize>| {
         rsx! {
             div { class: "book-page",
@@ -210,14 +290,30 @@ pub fn Collection() -> Element {
                     indices
                         .into_iter()
                         .map(|idx| {
-                            let owned = owned_cards.read();
-                            if let Some(card) = owned.get(&idx) {
+                            let is_selected = selected.read().contains(&idx);
+                            let owned = store::pocket(idx).filter(|pocket| pocket.owned());
+                            if let Some(
+                                PocketSummary { card: Some(card), highest_rarity, prints, .. },
+                            ) = owned
+                            {
                                 rsx! {
-                                    CardViewCompact { key: "{idx}", card: card.clone(), onclick: handle_card_click }
+                                    CardViewCompact {
+                                        key: "{idx}",
+                                        card,
+                                        onclick: handle_card_click,
+                                        selected: is_selected,
+                                        highest_rarity,
+                                        prints,
+                                    }
                                 }
                             } else {
                                 rsx! {
-                                    PlaceholderCard { key: "{idx}", index: idx, onclick: handle_card_click }
+                                    PlaceholderCard {
+                                        key: "{idx}",
+                                        index: idx,
+                                        onclick: handle_card_click,
+                                        selected: is_selected,
+                                    }
                                 }
                             }
                         })
@@ -232,28 +328,64 @@ pub fn Collection() -> Element {
         div { class: "collection-container",
             BookNavigation {
                 current_page,
-                total_pages: TOTAL_PAGES,
+                total_pages: config::layout().total_pages(),
                 on_search: handle_search,
                 loading_card,
             }
 
-            div { class: if is_mobile() { "book-view-mobile" } else { "book-view-desktop" },
-                div { class: "book-spread",
-                    // Left page (first 12 cards)
-                    {
-                        let cards = cards_for_page();
-                        let left_cards = cards[..12.min(cards.len())].to_vec();
-                        render_page_side(left_cards)
+            if let Some((input, error)) = query_error() {
+                QueryErrorMessage { query: input, error }
+            }
+
+            div { class: "bulk-toggle",
+                button {
+                    class: "book-nav__button",
+                    r#type: "button",
+                    onclick: move |_| {
+                        select_mode.toggle();
+                        selected.write().clear();
+                    },
+                    if select_mode() {
+                        {t("collection.done_selecting")}
+                    } else {
+                        {t("collection.select_multiple")}
                     }
-                    // Right page (last 12 cards)
-                    {
-                        let cards = cards_for_page();
-                        if cards.len() > 12 {
-                            let right_cards = cards[12..].to_vec();
-                            render_page_side(right_cards)
-                        } else {
-                            rsx! {
-                                div { class: "book-page book-page--empty" }
+                }
+            }
+
+            if select_mode() {
+                BulkSelection { selected, current_page, on_applied: handle_bulk_applied }
+            }
+
+            if let Some((input, result)) = active_query() {
+                QueryResults {
+                    query: input.clone(),
+                    result,
+                    onclick: handle_card_click,
+                    on_clear: move |_| active_query.set(None),
+                    SaveQueryForm { query: input }
+                }
+            } else {
+                div { class: if is_mobile() { "book-view-mobile" } else { "book-view-desktop" },
+                    div { class: "book-spread",
+                        // Left page (first half of the cards)
+                        {
+                            let cards = cards_for_page();
+                            let per_page = config::layout().cards_per_page();
+                            let left_cards = cards[..per_page.min(cards.len())].to_vec();
+                            render_page_side(left_cards)
+                        }
+                        // Right page (second half of the cards)
+                        {
+                            let cards = cards_for_page();
+                            let per_page = config::layout().cards_per_page();
+                            if cards.len() > per_page {
+                                let right_cards = cards[per_page..].to_vec();
+                                render_page_side(right_cards)
+                            } else {
+                                rsx! {
+                                    div { class: "book-page book-page--empty" }
+                                }
                             }
                         }
                     }
@@ -274,19 +406,38 @@ pub fn Collection() -> Element {
                     open: true,
                     on_open_change: move |v: bool| {
                         if !v {
-                            error_message.set(String::new());
+                            close_error();
                         }
                     },
                     DialogContent {
                         button {
                             class: "dialog-close",
                             r#type: "button",
-                            aria_label: "Close",
-                            onclick: move |_| error_message.set(String::new()),
+                            aria_label: t("common.close"),
+                            onclick: move |_| close_error(),
                             "×"
                         }
-                        DialogTitle { "Notice" }
+                        DialogTitle { {t("collection.notice")} }
                         DialogDescription { {error_message.read().clone()} }
+                        if !name_suggestions.read().is_empty() {
+                            div { class: "name-suggestions",
+                                {t("collection.did_you_mean")}
+                                " "
+                                for suggestion in name_suggestions() {
+                                    button {
+                                        key: "{suggestion}",
+                                        class: "name-suggestions__option",
+                                        r#type: "button",
+                                        onclick: move |_| {
+                                            close_error();
+                                            handle_search(suggestion.clone());
+                                        },
+                                        "{suggestion}"
+                                    }
+                                }
+                                "?"
+                            }
+                        }
                     }
                 }
             }
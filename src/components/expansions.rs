@@ -0,0 +1,491 @@
+use crate::{
+    backend::{
+        create_expansion_db, get_all_expansions_db, get_expansion_changes_db,
+        set_expansion_archived_db, update_expansion_db,
+    },
+    card_number::{NumberSubset, MAX_DIGITS, MAX_PREFIX_LEN},
+    expansion::{Expansion, Region},
+    expansion_admin::{
+        ExpansionDraft, ExpansionEvent, MAX_ABBREVIATION_LEN, MAX_NAME_LEN, MAX_SERIES_LEN,
+    },
+    i18n::{t, tf},
+    store,
+};
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+use strum::IntoEnumIterator;
+
+/// `None` for an empty input
+fn optional(value: String) -> Option<String> {
+    (!value.trim().is_empty()).then_some(value)
+}
+
+#[component]
+fn ExpansionChanges(changes: Vec<ExpansionEvent>) -> Element {
+    if changes.is_empty() {
+        return rsx! {
+            p { class: "admin-hint", {t("expansions.no_changes")} }
+        };
+    }
+
+    rsx! {
+        ul { class: "expansion-changes",
+            for event in changes {
+                li { key: "{event.id}",
+                    span { class: "admin-hint", "{event.created_at} · {event.profile}" }
+                    span { {event.change.describe()} }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Expansions() -> Element {
+    let mut password = use_signal(String::new);
+    let mut expansions = use_signal(Vec::<Expansion>::new);
+    let mut changes = use_signal(Vec::<ExpansionEvent>::new);
+    let mut filter = use_signal(String::new);
+    let mut show_archived = use_signal(|| false);
+    // `None` while adding a new expansion
+    let mut editing = use_signal(|| None::<usize>);
+    let mut draft = use_signal(ExpansionDraft::default);
+    let mut busy = use_signal(|| false);
+    let mut error = use_signal(String::new);
+
+    let reload = move || {
+        spawn(async move {
+            match get_all_expansions_db().await {
+                Ok(result) => expansions.set(result),
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to load expansions");
+                    error.set(tf("ownership.load_expansions_failed", &[("error", &e)]));
+                }
+            }
+            match get_expansion_changes_db().await {
+                Ok(result) => changes.set(result),
+                Err(e) => tracing::error!(error = %e, "failed to load expansion changes"),
+            }
+        });
+    };
+
+    use_effect(move || reload());
+
+    let mut reset_form = move || {
+        editing.set(None);
+        draft.set(ExpansionDraft::default());
+    };
+
+    let handle_save = move |evt: FormEvent| {
+        evt.prevent_default();
+        let current = draft.read().clone();
+        if let Err(message) = current.check() {
+            error.set(message);
+            return;
+        }
+        spawn(async move {
+            busy.set(true);
+            let result = match editing() {
+                Some(id) => update_expansion_db(password.read().clone(), id, current).await,
+                None => create_expansion_db(password.read().clone(), current).await,
+            };
+            match result {
+                Ok(saved) => {
+                    tracing::info!(id = saved.id, name = %saved.name, "expansion saved");
+                    error.set(String::new());
+                    reset_form();
+                    reload();
+                    store::expansions_changed();
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to save expansion");
+                    error.set(tf("expansions.save_failed", &[("error", &e)]));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let toggle_archived = move |expansion: Expansion| {
+        spawn(async move {
+            busy.set(true);
+            match set_expansion_archived_db(
+                password.read().clone(),
+                expansion.id,
+                !expansion.archived,
+            )
+            .await
+            {
+                Ok(saved) => {
+                    tracing::info!(
+                        id = saved.id,
+                        archived = saved.archived,
+                        "expansion archived"
+                    );
+                    error.set(String::new());
+                    reload();
+                    store::expansions_changed();
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to archive expansion");
+                    error.set(tf("expansions.archive_failed", &[("error", &e)]));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let shown = use_memo(move || {
+        let needle = filter().trim().to_lowercase();
+        expansions()
+            .into_iter()
+            .filter(|e| show_archived() || !e.archived)
+            .filter(|e| {
+                needle.is_empty()
+                    || e.name.to_lowercase().contains(&needle)
+                    || e.name_de
+                        .as_ref()
+                        .is_some_and(|name_de| name_de.to_lowercase().contains(&needle))
+                    || e.abbreviation.to_lowercase().contains(&needle)
+                    || e.series.to_lowercase().contains(&needle)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let known_series = use_memo(move || {
+        expansions()
+            .into_iter()
+            .map(|e| e.series)
+            .filter(|series| !series.is_empty())
+            .collect::<BTreeSet<_>>()
+    });
+
+    rsx! {
+        div { class: "admin-container",
+            h1 { class: "admin-title", {t("nav.expansions")} }
+
+            div { class: "form-group",
+                label { r#for: "expansions-password", {t("login.password")} }
+                input {
+                    r#type: "password",
+                    id: "expansions-password",
+                    value: "{password}",
+                    oninput: move |evt| password.set(evt.value()),
+                    placeholder: t("expansions.password_placeholder"),
+                }
+            }
+
+            if !error.read().is_empty() {
+                div { class: "error", "{error}" }
+            }
+
+            section { class: "admin-section",
+                h2 {
+                    if editing().is_some() {
+                        {t("expansions.edit_title")}
+                    } else {
+                        {t("expansions.new_title")}
+                    }
+                }
+                form { class: "expansion-form", onsubmit: handle_save,
+                    input {
+                        r#type: "text",
+                        placeholder: t("expansions.name"),
+                        maxlength: "{MAX_NAME_LEN}",
+                        value: "{draft.read().name}",
+                        oninput: move |evt| draft.write().name = evt.value(),
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: t("expansions.name_de"),
+                        maxlength: "{MAX_NAME_LEN}",
+                        value: draft.read().name_de.clone().unwrap_or_default(),
+                        oninput: move |evt| draft.write().name_de = optional(evt.value()),
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: t("expansions.abbreviation"),
+                        maxlength: "{MAX_ABBREVIATION_LEN}",
+                        value: "{draft.read().abbreviation}",
+                        oninput: move |evt| draft.write().abbreviation = evt.value(),
+                    }
+                    label {
+                        {t("expansions.cards")}
+                        input {
+                            r#type: "number",
+                            min: "1",
+                            value: "{draft.read().cards}",
+                            oninput: move |evt| draft.write().cards = evt.value().parse().unwrap_or_default(),
+                        }
+                    }
+                    label {
+                        {t("expansions.secret_cards")}
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            value: "{draft.read().secret_cards}",
+                            oninput: move |evt| {
+                                draft.write().secret_cards = evt.value().parse().unwrap_or_default()
+                            },
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: t("expansions.series"),
+                        list: "expansion-series",
+                        maxlength: "{MAX_SERIES_LEN}",
+                        value: "{draft.read().series}",
+                        oninput: move |evt| draft.write().series = evt.value(),
+                    }
+                    datalist { id: "expansion-series",
+                        for series in known_series() {
+                            option { value: "{series}" }
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        placeholder: t("expansions.set_code"),
+                        maxlength: "{MAX_SERIES_LEN}",
+                        value: draft.read().set_code.clone().unwrap_or_default(),
+                        oninput: move |evt| draft.write().set_code = optional(evt.value()),
+                    }
+                    label {
+                        {t("expansions.released")}
+                        input {
+                            r#type: "date",
+                            value: draft.read().release_date.clone().unwrap_or_default(),
+                            oninput: move |evt| draft.write().release_date = optional(evt.value()),
+                        }
+                    }
+                    label {
+                        {t("expansions.region")}
+                        select {
+                            value: "{draft.read().region}",
+                            onchange: move |evt| draft.write().region = Region::from(evt.value().as_str()),
+                            for region in Region::iter() {
+                                option {
+                                    value: "{region}",
+                                    selected: draft.read().region == region,
+                                    "{region}"
+                                }
+                            }
+                        }
+                    }
+                    input {
+                        class: "expansion-form__wide",
+                        r#type: "url",
+                        placeholder: t("expansions.symbol_url"),
+                        value: draft.read().symbol_url.clone().unwrap_or_default(),
+                        oninput: move |evt| draft.write().symbol_url = optional(evt.value()),
+                    }
+                    fieldset { class: "expansion-form__numbers expansion-form__wide",
+                        legend { {t("expansions.numbers")} }
+                        label {
+                            {t("expansions.padding")}
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                max: "{MAX_DIGITS}",
+                                value: "{draft.read().numbers.digits}",
+                                oninput: move |evt| {
+                                    draft.write().numbers.digits = evt.value().parse().unwrap_or_default()
+                                },
+                            }
+                        }
+                        label { class: "expansion-form__check",
+                            input {
+                                r#type: "checkbox",
+                                checked: draft.read().numbers.prefixed_only,
+                                onchange: move |evt| draft.write().numbers.prefixed_only = evt.checked(),
+                            }
+                            {t("expansions.prefixed_only")}
+                        }
+                        for (index , subset) in draft.read().numbers.subsets.clone().into_iter().enumerate() {
+                            div { key: "{index}", class: "expansion-form__subset",
+                                input {
+                                    r#type: "text",
+                                    placeholder: t("expansions.prefix"),
+                                    maxlength: "{MAX_PREFIX_LEN}",
+                                    value: "{subset.prefix}",
+                                    oninput: move |evt| {
+                                        if let Some(subset) = draft.write().numbers.subsets.get_mut(index) {
+                                            subset.prefix = evt.value();
+                                        }
+                                    },
+                                }
+                                input {
+                                    r#type: "text",
+                                    placeholder: t("expansions.subset_name"),
+                                    value: "{subset.name}",
+                                    oninput: move |evt| {
+                                        if let Some(subset) = draft.write().numbers.subsets.get_mut(index) {
+                                            subset.name = evt.value();
+                                        }
+                                    },
+                                }
+                                input {
+                                    r#type: "number",
+                                    min: "1",
+                                    placeholder: t("expansions.cards"),
+                                    value: subset.cards.map(|cards| cards.to_string()).unwrap_or_default(),
+                                    oninput: move |evt| {
+                                        if let Some(subset) = draft.write().numbers.subsets.get_mut(index) {
+                                            subset.cards = evt.value().parse().ok();
+                                        }
+                                    },
+                                }
+                                input {
+                                    r#type: "number",
+                                    min: "0",
+                                    max: "{MAX_DIGITS}",
+                                    placeholder: t("expansions.padding"),
+                                    value: "{subset.digits}",
+                                    oninput: move |evt| {
+                                        if let Some(subset) = draft.write().numbers.subsets.get_mut(index) {
+                                            subset.digits = evt.value().parse().unwrap_or_default();
+                                        }
+                                    },
+                                }
+                                button {
+                                    class: "admin-button",
+                                    r#type: "button",
+                                    onclick: move |_| {
+                                        draft.write().numbers.subsets.remove(index);
+                                    },
+                                    {t("common.remove")}
+                                }
+                            }
+                        }
+                        button {
+                            class: "admin-button",
+                            r#type: "button",
+                            onclick: move |_| draft.write().numbers.subsets.push(NumberSubset::default()),
+                            {t("expansions.add_subset")}
+                        }
+                        span { class: "admin-hint",
+                            {tf("expansions.printed_as", &[("examples", &draft.read().numbers.hint(draft.read().cards))])}
+                        }
+                    }
+                    div { class: "expansion-form__actions",
+                        button {
+                            class: "admin-button",
+                            r#type: "submit",
+                            disabled: busy(),
+                            if editing().is_some() {
+                                {t("common.save")}
+                            } else {
+                                {t("expansions.add")}
+                            }
+                        }
+                        if editing().is_some() {
+                            button {
+                                class: "admin-button",
+                                r#type: "button",
+                                onclick: move |_| reset_form(),
+                                {t("common.cancel")}
+                            }
+                        }
+                    }
+                }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("expansions.all")} }
+                div { class: "expansion-filter",
+                    input {
+                        r#type: "search",
+                        placeholder: t("expansions.filter_placeholder"),
+                        value: "{filter}",
+                        oninput: move |evt| filter.set(evt.value()),
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: show_archived(),
+                            onchange: move |evt| show_archived.set(evt.checked()),
+                        }
+                        {t("expansions.show_archived")}
+                    }
+                }
+                table { class: "expansion-table",
+                    thead {
+                        tr {
+                            th {}
+                            th { {t("expansions.column_abbreviation")} }
+                            th { {t("expansions.name")} }
+                            th { {t("expansions.series")} }
+                            th { {t("expansions.released")} }
+                            th { {t("expansions.region")} }
+                            th { {t("expansions.column_numbers")} }
+                            th { {t("expansions.cards")} }
+                            th { {t("expansions.column_secret")} }
+                            th {}
+                        }
+                    }
+                    tbody {
+                        for expansion in shown() {
+                            tr {
+                                key: "{expansion.id}",
+                                class: if expansion.archived { "expansion-table__archived" },
+                                td {
+                                    if let Some(symbol) = &expansion.symbol_url {
+                                        img {
+                                            class: "expansion-symbol",
+                                            src: "{symbol}",
+                                            alt: "{expansion.abbreviation}",
+                                        }
+                                    }
+                                }
+                                td { "{expansion.abbreviation}" }
+                                td {
+                                    "{expansion.name}"
+                                    if let Some(name_de) = &expansion.name_de {
+                                        span { class: "admin-hint", " · {name_de}" }
+                                    }
+                                }
+                                td { "{expansion.series}" }
+                                td { {expansion.release_date.clone().unwrap_or_default()} }
+                                td { "{expansion.region}" }
+                                td { {expansion.numbers.hint(expansion.cards)} }
+                                td { "{expansion.cards}" }
+                                td { "{expansion.secret_cards}" }
+                                td { class: "expansion-table__actions",
+                                    button {
+                                        class: "admin-button",
+                                        disabled: busy(),
+                                        onclick: {
+                                            let expansion = expansion.clone();
+                                            move |_| {
+                                                editing.set(Some(expansion.id));
+                                                draft.set(ExpansionDraft::from(&expansion));
+                                            }
+                                        },
+                                        {t("expansions.edit")}
+                                    }
+                                    button {
+                                        class: "admin-button",
+                                        disabled: busy(),
+                                        onclick: {
+                                            let expansion = expansion.clone();
+                                            move |_| toggle_archived(expansion.clone())
+                                        },
+                                        if expansion.archived {
+                                            {t("expansions.restore")}
+                                        } else {
+                                            {t("expansions.archive")}
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            section { class: "admin-section",
+                h2 { {t("expansions.recent_changes")} }
+                ExpansionChanges { changes: changes() }
+            }
+        }
+    }
+}
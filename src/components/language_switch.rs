@@ -0,0 +1,42 @@
+use crate::{
+    backend::set_language_db,
+    i18n::{self, t, Language},
+    IS_AUTHENTICATED,
+};
+use dioxus::prelude::*;
+use strum::IntoEnumIterator;
+
+/// Picks the UI language, remembered for the profile once logged in
+#[component]
+pub fn LanguageSwitch() -> Element {
+    let current = i18n::LANGUAGE();
+
+    rsx! {
+        select {
+            class: "language-switch",
+            aria_label: t("language.label"),
+            value: current.code(),
+            onchange: move |evt| {
+                let Some(language) = Language::from_code(&evt.value()) else {
+                    return;
+                };
+                if *IS_AUTHENTICATED.peek() {
+                    // Spawned outside this view, the switch remounts it
+                    spawn_forever(async move {
+                        if let Err(e) = set_language_db(language).await {
+                            tracing::warn!(error = %e, "failed to store language");
+                        }
+                    });
+                }
+                i18n::set_language(language);
+            },
+            for language in Language::iter() {
+                option {
+                    value: language.code(),
+                    selected: language == current,
+                    "{language}"
+                }
+            }
+        }
+    }
+}
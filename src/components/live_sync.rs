@@ -0,0 +1,83 @@
+use crate::{backend::live_sync, history::PROFILE, i18n::t, store};
+use dioxus::prelude::*;
+
+/// How long a toast stays up
+const TOAST_MS: u32 = 6000;
+
+/// Pause before reconnecting a dropped stream
+const RECONNECT_MS: u32 = 3000;
+
+/// Most toasts shown at once, older ones are dropped
+const MAX_TOASTS: usize = 3;
+
+pub(super) async fn sleep(ms: u32) {
+    let _ = document::eval(&format!(
+        "await new Promise((resolve) => setTimeout(resolve, {ms})); return null;"
+    ))
+    .await;
+}
+
+/// Keeps the store in step with changes made on other devices
+///
+/// Listens on the live sync stream while mounted and shows a toast for
+/// every change made under another profile.
+#[component]
+pub fn LiveSync() -> Element {
+    let mut toasts = use_signal(Vec::<(usize, String)>::new);
+    let mut next_toast = use_signal(|| 0usize);
+
+    let mut show_toast = move |message: String| {
+        let id = next_toast();
+        next_toast.set(id + 1);
+        {
+            let mut toasts = toasts.write();
+            toasts.push((id, message));
+            let overflow = toasts.len().saturating_sub(MAX_TOASTS);
+            toasts.drain(..overflow);
+        }
+        spawn(async move {
+            sleep(TOAST_MS).await;
+            toasts.write().retain(|(toast, _)| *toast != id);
+        });
+    };
+
+    use_future(move || async move {
+        loop {
+            match live_sync().await {
+                Ok(mut events) => {
+                    while let Some(Ok(event)) = events.recv().await {
+                        match event.card_ids() {
+                            Some(card_ids) => store::cards_changed(card_ids),
+                            None => store::expansions_changed(),
+                        }
+                        if event.profile() != Some(PROFILE.peek().as_str()) {
+                            show_toast(event.describe());
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "failed to open live sync stream"),
+            }
+
+            // Changes made while disconnected were missed, reload everything
+            sleep(RECONNECT_MS).await;
+            store::collection_changed();
+        }
+    });
+
+    rsx! {
+        div { class: "sync-toasts", role: "status", aria_live: "polite",
+            for (id, message) in toasts() {
+                div { key: "{id}", class: "sync-toast",
+                    span { class: "sync-toast__message", "{message}" }
+                    button {
+                        class: "sync-toast__close",
+                        r#type: "button",
+                        aria_label: t("common.dismiss"),
+                        onclick: move |_| toasts.write().retain(|(toast, _)| *toast != id),
+                        "×"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{backend::validate_password, Route, IS_AUTHENTICATED};
+use super::LanguageSwitch;
+use crate::{
+    backend::{get_language_db, set_language_db, validate_password},
+    history,
+    i18n::{self, t},
+    Route, IS_AUTHENTICATED,
+};
 use dioxus::prelude::*;
 
 #[component]
@@ -13,6 +19,7 @@ pub fn Login() -> Element {
         });
     }
     let mut password = use_signal(String::new);
+    let mut profile = use_signal(String::new);
     let mut error = use_signal(String::new);
 
     // If already authenticated, redirect to home
@@ -34,16 +41,36 @@ pub fn Login() -> Element {
             Ok(is_valid) => {
                 if is_valid {
                     tracing::info!("user authenticated successfully");
+                    // Recorded as the acting profile in the change history
+                    history::set_profile(&profile.read());
+                    // The profile's language wins, a new profile keeps the one picked here
+                    let stored = match get_language_db().await {
+                        Ok(Some(language)) => Some(language),
+                        Ok(None) => {
+                            if let Err(e) = set_language_db(i18n::language()).await {
+                                tracing::warn!(error = %e, "failed to store language");
+                            }
+                            None
+                        }
+                        Err(e) => {
+                            tracing::warn!(error = %e, "failed to load language");
+                            None
+                        }
+                    };
                     *IS_AUTHENTICATED.write() = true;
                     nav.push(Route::Collection);
+                    // Last, switching remounts this page and ends the handler
+                    if let Some(language) = stored {
+                        i18n::set_language(language);
+                    }
                 } else {
                     tracing::warn!("authentication failed - incorrect credentials");
-                    error.set("Incorrect password".to_string());
+                    error.set(t("login.incorrect_password").to_string());
                 }
             }
             Err(e) => {
                 tracing::error!(error = %e, "authentication error");
-                error.set(format!("Error: {}", e));
+                error.set(i18n::tf("common.error", &[("error", &e)]));
             }
         }
     };
@@ -51,23 +78,34 @@ pub fn Login() -> Element {
     rsx! {
         div { class: "login-container",
             div { class: "login-box",
-                h1 { "Login" }
+                h1 { {t("login.title")} }
                 form { onsubmit: handle_submit,
                     div { class: "form-group",
-                        label { r#for: "password", "Password:" }
+                        label { r#for: "password", {t("login.password")} }
                         input {
                             r#type: "password",
                             id: "password",
                             value: "{password}",
                             oninput: move |evt| password.set(evt.value()),
-                            placeholder: "Enter password",
+                            placeholder: t("login.password_placeholder"),
                             autofocus: true,
                         }
                     }
+                    div { class: "form-group",
+                        label { r#for: "profile", {t("login.profile")} }
+                        input {
+                            r#type: "text",
+                            id: "profile",
+                            value: "{profile}",
+                            oninput: move |evt| profile.set(evt.value()),
+                            placeholder: t("login.profile_placeholder"),
+                        }
+                    }
                     if !error.read().is_empty() {
                         div { class: "error", "{error}" }
                     }
-                    button { r#type: "submit", "Login" }
+                    button { r#type: "submit", {t("login.submit")} }
+                    LanguageSwitch {}
                 }
             }
         }
@@ -1,14 +1,38 @@
-use crate::Route;
+use super::LanguageSwitch;
+use crate::{backend::get_saved_query_counts_db, i18n::t, Route, COLLECTION_REVISION};
 use dioxus::prelude::*;
 
 #[component]
 pub fn NavBar() -> Element {
+    // Saved searches are virtual binders, their counts follow ownership changes
+    let saved = use_resource(move || {
+        let _revision = COLLECTION_REVISION();
+        get_saved_query_counts_db()
+    });
+
     rsx! {
         div { class: "nav-bar",
             Link { to: Route::Collection,
-                h1 { "My Collection" }
+                h1 { {t("nav.collection")} }
+            }
+            if let Some(Ok(saved)) = &*saved.read() {
+                for count in saved.iter() {
+                    Link {
+                        key: "{count.saved.id}",
+                        to: Route::SavedBinderView {
+                            id: count.saved.id,
+                        },
+                        class: "nav-bar__link nav-bar__binder",
+                        title: "{count.saved.query}",
+                        "{count.saved.name}"
+                        span { class: "nav-bar__binder-count", "{count.total}" }
+                    }
+                }
             }
-            Link { to: Route::Statistics, class: "nav-bar__link", "Statistics" }
+            Link { to: Route::Statistics, class: "nav-bar__link", {t("nav.statistics")} }
+            Link { to: Route::Expansions, class: "nav-bar__link", {t("nav.expansions")} }
+            Link { to: Route::Admin, class: "nav-bar__link", {t("nav.admin")} }
+            LanguageSwitch {}
         }
         Outlet::<Route> {}
     }
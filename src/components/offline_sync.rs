@@ -0,0 +1,78 @@
+use super::live_sync::sleep;
+use crate::{
+    i18n::{t, tf},
+    offline::Replayed,
+    store, COLLECTION_REVISION,
+};
+use dioxus::prelude::*;
+
+/// Pause between attempts to reach the server
+const RETRY_MS: u32 = 10_000;
+
+/// Sends edits queued offline and keeps the offline copy up to date
+///
+/// Shows a status line while offline or while edits wait to be sent, and a
+/// notice for every queued edit the server refused.
+#[component]
+pub fn OfflineSync() -> Element {
+    let mut notices = use_signal(Vec::<String>::new);
+
+    use_future(move || async move {
+        store::restore_queue().await;
+        let mut snapshot_revision = None;
+        loop {
+            let mut sent = true;
+            for replayed in store::replay_queue().await {
+                match replayed {
+                    Replayed::Dropped { card, reason } => notices.write().push(tf(
+                        "offline.dropped",
+                        &[("card", &card), ("reason", &reason)],
+                    )),
+                    Replayed::Unreachable => sent = false,
+                    _ => {}
+                }
+            }
+
+            // Refresh the copy once the queue is sent and the collection changed
+            let revision = *COLLECTION_REVISION.peek();
+            if sent && snapshot_revision != Some(revision) && store::refresh_snapshot().await {
+                snapshot_revision = Some(revision);
+            }
+            sleep(RETRY_MS).await;
+        }
+    });
+
+    let queued = store::queued_edits();
+    rsx! {
+        if store::offline() || queued > 0 {
+            div { class: "offline-status", role: "status",
+                if store::offline() {
+                    {t("offline.offline")}
+                } else {
+                    {t("offline.sending")}
+                }
+                if queued == 1 {
+                    " · "
+                    {t("offline.one_waiting")}
+                } else if queued > 1 {
+                    " · "
+                    {tf("offline.waiting", &[("count", &queued)])}
+                }
+            }
+        }
+        for (index, notice) in notices().into_iter().enumerate() {
+            div { key: "{index}", class: "offline-notice", role: "alert",
+                span { "{notice}" }
+                button {
+                    class: "sync-toast__close",
+                    r#type: "button",
+                    aria_label: t("common.dismiss"),
+                    onclick: move |_| {
+                        notices.write().remove(index);
+                    },
+                    "×"
+                }
+            }
+        }
+    }
+}
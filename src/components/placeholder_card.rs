@@ -1,14 +1,20 @@
+use crate::i18n::t;
 use dioxus::prelude::*;
 
 #[component]
-pub fn PlaceholderCard(index: usize, onclick: EventHandler<usize>) -> Element {
+pub fn PlaceholderCard(
+    index: usize,
+    onclick: EventHandler<usize>,
+    #[props(default)] selected: bool,
+) -> Element {
     rsx! {
         div {
             class: "card-compact card-compact--placeholder",
+            class: if selected { "card-compact--selected" },
             onclick: move |_| onclick.call(index),
             div { class: "card-compact__placeholder-icon", "?" }
             div { class: "card-compact__id", "#{index}" }
-            div { class: "card-compact__placeholder-text", "Not Owned" }
+            div { class: "card-compact__placeholder-text", {t("card.not_owned")} }
         }
     }
 }
@@ -1,5 +1,5 @@
-use super::NavBar;
-use crate::IS_AUTHENTICATED;
+use super::{LiveSync, NavBar, OfflineSync};
+use crate::{i18n::t, IS_AUTHENTICATED};
 use dioxus::prelude::*;
 
 #[component]
@@ -27,10 +27,12 @@ pub fn ProtectedRoute() -> Element {
     if *IS_AUTHENTICATED.read() {
         rsx! {
             NavBar {}
+            LiveSync {}
+            OfflineSync {}
         }
     } else {
         rsx! {
-            div { "Redirecting to login..." }
+            div { {t("login.redirecting")} }
         }
     }
 }
@@ -0,0 +1,87 @@
+use crate::{
+    components::{CardViewCompact, PlaceholderCard},
+    i18n::{t, tf},
+    query::{QueryError, QueryResult},
+};
+use dioxus::prelude::*;
+
+/// Cards matching a filter query, in binder order
+///
+/// `children` are extra actions shown next to the summary.
+#[component]
+pub fn QueryResults(
+    query: String,
+    result: QueryResult,
+    onclick: EventHandler<usize>,
+    on_clear: Option<EventHandler<()>>,
+    children: Element,
+) -> Element {
+    let shown = result.matches.len();
+
+    rsx! {
+        div { class: "query-results",
+            div { class: "query-results__header",
+                span { class: "query-results__summary",
+                    if shown < result.total {
+                        {tf("query.showing_some", &[("shown", &shown), ("total", &result.total)])}
+                    } else {
+                        {tf("query.showing_all", &[("total", &result.total)])}
+                    }
+                    " "
+                    code { "{query}" }
+                }
+                div { class: "query-results__actions",
+                    {children}
+                    if let Some(on_clear) = on_clear {
+                        button {
+                            class: "book-nav__button",
+                            r#type: "button",
+                            onclick: move |_| on_clear.call(()),
+                            {t("query.clear")}
+                        }
+                    }
+                }
+            }
+            div { class: "query-results__grid",
+                for m in result.matches {
+                    if let Some(card) = m.card.filter(|card| card.owned.0) {
+                        CardViewCompact {
+                            key: "{m.index}",
+                            card,
+                            onclick,
+                            highest_rarity: m.highest_rarity,
+                        }
+                    } else {
+                        PlaceholderCard { key: "{m.index}", index: m.index, onclick }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The query with the token that failed to parse highlighted
+#[component]
+pub fn QueryErrorMessage(query: String, error: QueryError) -> Element {
+    let start = error.span.start.min(query.len());
+    let end = error.span.end.clamp(start, query.len());
+    let (before, rest) = query.split_at(start);
+    let (token, after) = rest.split_at(end - start);
+
+    rsx! {
+        div { class: "query-error", role: "alert",
+            div { class: "query-error__message", "{error.message}" }
+            code { class: "query-error__query",
+                "{before}"
+                mark { class: "query-error__token",
+                    if token.is_empty() {
+                        " "
+                    } else {
+                        "{token}"
+                    }
+                }
+                "{after}"
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    backend::{
+        delete_saved_query_db, get_card_by_id_db, get_card_by_id_remote, get_saved_binder_db,
+        save_query_db,
+    },
+    card::Card,
+    components::{CardOwnershipDialog, DialogMode, QueryResults},
+    i18n::{t, tf},
+    query::QueryError,
+    saved_queries::MAX_NAME_LEN,
+    store, Route, COLLECTION_REVISION,
+};
+use dioxus::prelude::*;
+
+/// Saves the current filter query under a name
+#[component]
+pub fn SaveQueryForm(query: String) -> Element {
+    let mut open = use_signal(|| false);
+    let mut name = use_signal(String::new);
+    let mut message = use_signal(String::new);
+    let mut saving = use_signal(|| false);
+
+    let mut submit = move |query: String| {
+        saving.set(true);
+        spawn(async move {
+            match save_query_db(name(), query).await {
+                Ok(saved) => {
+                    message.set(tf("saved.saved_as", &[("name", &saved.name)]));
+                    name.set(String::new());
+                    open.set(false);
+                    *COLLECTION_REVISION.write() += 1;
+                }
+                Err(e) => match QueryError::from_error(&e) {
+                    Some(error) => message.set(error.message),
+                    None => message.set(tf("saved.save_failed", &[("error", &e)])),
+                },
+            }
+            saving.set(false);
+        });
+    };
+
+    rsx! {
+        div { class: "save-query",
+            if open() {
+                form {
+                    class: "save-query__form",
+                    onsubmit: move |evt| {
+                        evt.prevent_default();
+                        submit(query.clone());
+                    },
+                    input {
+                        class: "book-nav__search",
+                        r#type: "text",
+                        placeholder: t("saved.name_placeholder"),
+                        maxlength: "{MAX_NAME_LEN}",
+                        value: "{name}",
+                        oninput: move |evt| name.set(evt.value()),
+                    }
+                    button {
+                        class: "book-nav__button",
+                        r#type: "submit",
+                        disabled: saving() || name.read().trim().is_empty(),
+                        {t("common.save")}
+                    }
+                    button {
+                        class: "book-nav__button",
+                        r#type: "button",
+                        onclick: move |_| open.set(false),
+                        {t("common.cancel")}
+                    }
+                }
+            } else {
+                button {
+                    class: "book-nav__button",
+                    r#type: "button",
+                    onclick: move |_| {
+                        message.set(String::new());
+                        open.set(true);
+                    },
+                    {t("saved.save_search")}
+                }
+            }
+            if !message.read().is_empty() {
+                span { class: "save-query__message", "{message}" }
+            }
+        }
+    }
+}
+
+/// A saved search shown as a virtual binder
+#[component]
+pub fn SavedBinderView(id: usize) -> Element {
+    let nav = use_navigator();
+    let mut dialog_open = use_signal(|| false);
+    let mut temp_card = use_signal(Card::default);
+    let mut error_message = use_signal(String::new);
+
+    // Reload when switching binders and whenever ownership changes
+    let binder = use_resource(use_reactive!(|id| {
+        let _revision = COLLECTION_REVISION();
+        async move { get_saved_binder_db(id).await }
+    }));
+
+    let handle_card_click = move |index: usize| {
+        if let Some(card) = store::stored_card(index) {
+            temp_card.set(card);
+            dialog_open.set(true);
+            return;
+        }
+        spawn(async move {
+            let card = match get_card_by_id_db(index).await {
+                Ok(card) => Ok(card),
+                Err(_) => match get_card_by_id_remote(index).await {
+                    Ok(card) => store::save_fetched_card(card).await,
+                    Err(e) => Err(e),
+                },
+            };
+            match card {
+                Ok(card) => {
+                    temp_card.set(card);
+                    dialog_open.set(true);
+                }
+                Err(e) => error_message.set(tf("collection.fetch_failed", &[("error", &e)])),
+            }
+        });
+    };
+
+    let handle_delete = move |_| {
+        spawn(async move {
+            match delete_saved_query_db(id).await {
+                Ok(()) => {
+                    *COLLECTION_REVISION.write() += 1;
+                    nav.push(Route::Collection);
+                }
+                Err(e) => error_message.set(tf("saved.delete_failed", &[("error", &e)])),
+            }
+        });
+    };
+
+    let is_owned = move || temp_card.read().owned.0;
+
+    rsx! {
+        div { class: "collection-container",
+            match &*binder.read() {
+                Some(Ok(binder)) => rsx! {
+                    h2 { class: "saved-binder__title", "{binder.saved.name}" }
+                    QueryResults {
+                        query: binder.saved.query.clone(),
+                        result: binder.result.clone(),
+                        onclick: handle_card_click,
+                        button {
+                            class: "book-nav__button",
+                            r#type: "button",
+                            onclick: handle_delete,
+                            {t("saved.delete")}
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "query-error", {tf("saved.load_failed", &[("error", e)])} }
+                },
+                None => rsx! {
+                    div { class: "widget-loading", {t("common.loading")} }
+                },
+            }
+
+            if !error_message.read().is_empty() {
+                div { class: "query-error", "{error_message}" }
+            }
+
+            CardOwnershipDialog {
+                card: temp_card,
+                dialog_open,
+                mode: if is_owned() { DialogMode::Edit } else { DialogMode::Add },
+                on_change: move |card: Card| store::card_changed(&card),
+            }
+        }
+    }
+}
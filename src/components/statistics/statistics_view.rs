@@ -1,5 +1,11 @@
+use crate::backend::list_saved_queries_db;
 use crate::components::statistics::{BarChart, ScalarDisplay};
-use crate::statistics::{ExpansionCompletionWidget, StatWidget, TotalOwnedWidget};
+use crate::i18n::{t, tf};
+use crate::saved_queries::SavedQuery;
+use crate::statistics::{
+    ExpansionCompletionWidget, SavedQueryWidget, StatWidget, StatisticData, TotalOwnedWidget,
+};
+use crate::COLLECTION_REVISION;
 use dioxus::prelude::*;
 
 #[component]
@@ -9,8 +15,9 @@ pub fn Statistics() -> Element {
     let mut loading = use_signal(|| true);
     let mut error = use_signal(String::new);
 
-    // Load all widget data on mount
+    // Load all widget data on mount and again once the store was invalidated
     use_effect(move || {
+        let _revision = COLLECTION_REVISION();
         spawn(async move {
             // Calculate expansion completion
             let expansion_widget = ExpansionCompletionWidget;
@@ -19,7 +26,7 @@ pub fn Statistics() -> Element {
                 Err(e) => {
                     #[cfg(feature = "server")]
                     tracing::error!(error = %e, "failed to calculate expansion completion");
-                    error.set(format!("Failed to load expansion statistics: {}", e));
+                    error.set(tf("stats.completion_failed", &[("error", &e)]));
                 }
             }
 
@@ -30,7 +37,7 @@ pub fn Statistics() -> Element {
                 Err(e) => {
                     #[cfg(feature = "server")]
                     tracing::error!(error = %e, "failed to calculate total owned");
-                    error.set(format!("Failed to load total owned: {}", e));
+                    error.set(tf("stats.total_owned_failed", &[("error", &e)]));
                 }
             }
 
@@ -43,12 +50,12 @@ pub fn Statistics() -> Element {
 
     rsx! {
         div { class: "statistics-container",
-            h1 { class: "statistics-title", "Collection Statistics" }
+            h1 { class: "statistics-title", {t("stats.title")} }
 
             if loading() {
-                div { class: "statistics-loading", "Loading statistics..." }
+                div { class: "statistics-loading", {t("stats.loading")} }
             } else if !error.read().is_empty() {
-                div { class: "statistics-error", "Error: {error}" }
+                div { class: "statistics-error", {tf("common.error", &[("error", &error)])} }
             } else {
                 div { class: "statistics-dashboard",
                     div { class: "dashboard-grid",
@@ -68,12 +75,12 @@ pub fn Statistics() -> Element {
                                                 ScalarDisplay { value: value.clone() }
                                             },
                                             _ => rsx! {
-                                                div { "Invalid data type" }
+                                                div { {t("stats.invalid_data")} }
                                             },
                                         }
                                     } else {
                                         rsx! {
-                                            div { class: "widget-loading", "Loading..." }
+                                            div { class: "widget-loading", {t("common.loading")} }
                                         }
                                     }
                                 }
@@ -98,20 +105,81 @@ pub fn Statistics() -> Element {
                                                 }
                                             }
                                             _ => rsx! {
-                                                div { "Invalid data type" }
+                                                div { {t("stats.invalid_data")} }
                                             },
                                         }
                                     } else {
                                         rsx! {
-                                            div { class: "widget-loading", "Loading..." }
+                                            div { class: "widget-loading", {t("common.loading")} }
                                         }
                                     }
                                 }
                             }
                         }
+
+                        // One widget per saved search
+                        SavedQueryWidgets {}
                     }
                 }
             }
         }
     }
 }
+
+/// Counts of the saved searches, reloaded whenever ownership changes
+#[component]
+fn SavedQueryWidgets() -> Element {
+    let saved = use_resource(move || {
+        let _revision = COLLECTION_REVISION();
+        list_saved_queries_db()
+    });
+
+    match &*saved.read() {
+        Some(Ok(saved)) => rsx! {
+            for saved in saved.iter() {
+                SavedQueryWidgetView { key: "{saved.id}", saved: saved.clone() }
+            }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "statistics-error", {tf("stats.saved_failed", &[("error", e)])} }
+        },
+        None => rsx! {},
+    }
+}
+
+#[component]
+fn SavedQueryWidgetView(saved: SavedQuery) -> Element {
+    let data = use_resource(use_reactive!(|saved| {
+        let _revision = COLLECTION_REVISION();
+        async move {
+            let widget = SavedQueryWidget { saved };
+            widget.calculate().await
+        }
+    }));
+    let widget = SavedQueryWidget { saved };
+
+    rsx! {
+        div { class: "widget-container",
+            div { class: "widget-header",
+                h3 { class: "widget-title", "{widget.title()}" }
+                p { class: "widget-description", "{widget.metadata().description}" }
+            }
+            div { class: "widget-content",
+                match &*data.read() {
+                    Some(Ok(StatisticData::Scalar(value))) => rsx! {
+                        ScalarDisplay { value: value.clone() }
+                    },
+                    Some(Ok(_)) => rsx! {
+                        div { {t("stats.invalid_data")} }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "statistics-error", {tf("common.error", &[("error", e)])} }
+                    },
+                    None => rsx! {
+                        div { class: "widget-loading", {t("common.loading")} }
+                    },
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::components::statistics::ChartRenderer;
+use crate::i18n::t;
 use crate::statistics::{FilterCriteria, SortOrder, StatisticData};
 use dioxus::prelude::*;
 
@@ -35,7 +36,7 @@ pub fn WidgetContainer(
                 div { class: "widget-controls",
                     if !sort_orders.is_empty() {
                         div { class: "widget-controls__sort",
-                            label { "Sort by: " }
+                            label { {t("stats.sort_by")} " " }
                             select {
                                 class: "widget-controls__select",
                                 onchange: move |evt| {
@@ -49,15 +50,15 @@ pub fn WidgetContainer(
                                     active_sort.set(sort_order);
                                     // Note: Actual sorting would need to be implemented via callback
                                 },
-                                option { value: "", "Default" }
+                                option { value: "", {t("stats.sort_default")} }
                                 if sort_orders.contains(&SortOrder::CompletionDesc) {
-                                    option { value: "completion_desc", "Completion % (High to Low)" }
+                                    option { value: "completion_desc", {t("stats.sort_completion_desc")} }
                                 }
                                 if sort_orders.contains(&SortOrder::CompletionAsc) {
-                                    option { value: "completion_asc", "Completion % (Low to High)" }
+                                    option { value: "completion_asc", {t("stats.sort_completion_asc")} }
                                 }
                                 if sort_orders.contains(&SortOrder::Alphabetical) {
-                                    option { value: "alphabetical", "Alphabetical" }
+                                    option { value: "alphabetical", {t("stats.sort_alphabetical")} }
                                 }
                             }
                         }
@@ -65,7 +66,7 @@ pub fn WidgetContainer(
 
                     if !filter_criteria.is_empty() {
                         div { class: "widget-controls__filter",
-                            label { "Filter: " }
+                            label { {t("stats.filter")} " " }
                             select {
                                 class: "widget-controls__select",
                                 onchange: move |evt| {
@@ -79,15 +80,15 @@ pub fn WidgetContainer(
                                     active_filter.set(filter);
                                     // Note: Actual filtering would need to be implemented via callback
                                 },
-                                option { value: "", "All Expansions" }
+                                option { value: "", {t("stats.filter_all")} }
                                 if filter_criteria.iter().any(|f| matches!(f, FilterCriteria::MinCompletion(25.0))) {
-                                    option { value: "min_25", "≥ 25% Complete" }
+                                    option { value: "min_25", {t("stats.filter_min_25")} }
                                 }
                                 if filter_criteria.iter().any(|f| matches!(f, FilterCriteria::MinCompletion(50.0))) {
-                                    option { value: "min_50", "≥ 50% Complete" }
+                                    option { value: "min_50", {t("stats.filter_min_50")} }
                                 }
                                 if filter_criteria.iter().any(|f| matches!(f, FilterCriteria::MinCompletion(75.0))) {
-                                    option { value: "min_75", "≥ 75% Complete" }
+                                    option { value: "min_75", {t("stats.filter_min_75")} }
                                 }
                             }
                         }
@@ -102,7 +103,7 @@ pub fn WidgetContainer(
                         ChartRenderer { data: d, metadata: metadata.clone() }
                     },
                     None => rsx! {
-                        div { class: "widget-loading", "Loading..." }
+                        div { class: "widget-loading", {t("common.loading")} }
                     },
                 }
             }
@@ -0,0 +1,619 @@
+//! Runtime configuration
+//!
+//! The server loads its configuration once at startup from a TOML file
+//! (`config.toml`, or the path in `FS_CHAOT_CONFIG`) and then applies
+//! environment overrides on top. Invalid values are reported together before
+//! the server starts instead of failing on first use.
+//!
+//! The client never reads the file. It receives the binder layout from the
+//! server and otherwise works with the defaults.
+
+use crate::card::NameLanguage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Server URL used by the web client, overridable at compile time
+#[cfg(not(feature = "server"))]
+pub const SERVER_URL: &str = match option_env!("FS_CHAOT_SERVER_URL") {
+    Some(url) => url,
+    None => "https://fs-chaot-production.up.railway.app",
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub backup: BackupConfig,
+    pub auth: AuthConfig,
+    pub pokeapi: PokeApiConfig,
+    pub binder: BinderLayout,
+    pub names: NamesConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub path: PathBuf,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: if cfg!(feature = "dev") {
+                PathBuf::from("db_dev/dev.db")
+            } else {
+                PathBuf::from("db/production.db")
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    /// Where snapshots, including automatic pre-restore copies, are written
+    pub dir: PathBuf,
+    /// Hourly snapshots to keep, 0 disables them
+    pub hourly_keep: usize,
+    /// Daily snapshots to keep, 0 disables them
+    pub daily_keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            dir: if cfg!(feature = "dev") {
+                PathBuf::from("db_dev/backups")
+            } else {
+                PathBuf::from("db/backups")
+            },
+            hourly_keep: 24,
+            daily_keep: 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Shared login password, usually provided through `APP_PASSWORD`
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PokeApiConfig {
+    pub base_url: String,
+    pub language_url: String,
+    pub types_url: String,
+    pub sprite_url: String,
+}
+
+impl Default for PokeApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://pokeapi.co/api/v2/pokemon/".to_string(),
+            language_url: "https://raw.githubusercontent.com/PokeAPI/pokeapi/refs/heads/master/data/v2/csv/pokemon_species_names.csv".to_string(),
+            types_url: "https://raw.githubusercontent.com/PokeAPI/pokeapi/refs/heads/master/data/v2/csv/pokemon_types.csv".to_string(),
+            sprite_url: "https://raw.githubusercontent.com/PokeAPI/sprites/master/sprites/pokemon/"
+                .to_string(),
+        }
+    }
+}
+
+/// Physical layout of the binders the collection is sorted into
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BinderLayout {
+    pub books: usize,
+    pub cards_per_book: usize,
+    pub cards_per_double_page: usize,
+    pub max_pokemon: usize,
+}
+
+impl Default for BinderLayout {
+    fn default() -> Self {
+        Self {
+            books: 2,
+            cards_per_book: 576,
+            cards_per_double_page: 24,
+            max_pokemon: 1025,
+        }
+    }
+}
+
+impl BinderLayout {
+    /// Number of double pages needed to hold every Pokemon
+    pub fn total_pages(&self) -> usize {
+        self.max_pokemon.div_ceil(self.cards_per_double_page)
+    }
+
+    /// Cards on one side of a double page
+    pub fn cards_per_page(&self) -> usize {
+        self.cards_per_double_page / 2
+    }
+
+    /// Indices of the pockets on double page `page`, counted from 1
+    pub fn double_page(&self, page: usize) -> std::ops::RangeInclusive<usize> {
+        let start = (page.max(1) - 1) * self.cards_per_double_page + 1;
+        let end = (start + self.cards_per_double_page - 1).min(self.max_pokemon);
+        start..=end
+    }
+
+    #[cfg(feature = "server")]
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.cards_per_double_page == 0 || !self.cards_per_double_page.is_multiple_of(2) {
+            problems.push(format!(
+                "binder.cards_per_double_page must be a positive even number, got {}",
+                self.cards_per_double_page
+            ));
+        } else if self.cards_per_book == 0
+            || !self
+                .cards_per_book
+                .is_multiple_of(self.cards_per_double_page)
+        {
+            problems.push(format!(
+                "binder.cards_per_book ({}) must be a positive multiple of binder.cards_per_double_page ({})",
+                self.cards_per_book, self.cards_per_double_page
+            ));
+        }
+        if self.max_pokemon == 0 {
+            problems.push("binder.max_pokemon must be at least 1".to_string());
+        }
+        if self.books * self.cards_per_book < self.max_pokemon {
+            problems.push(format!(
+                "binder holds {} cards ({} books x {}), fewer than binder.max_pokemon ({})",
+                self.books * self.cards_per_book,
+                self.books,
+                self.cards_per_book,
+                self.max_pokemon
+            ));
+        }
+        problems
+    }
+}
+
+/// Languages Pokemon names are shown in, every language is stored and searchable
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamesConfig {
+    /// Shown large on cards and in the card dialog
+    pub primary: NameLanguage,
+    /// Shown small below the primary name, `None` (`""` in the file) hides it
+    #[serde(deserialize_with = "optional_language")]
+    pub secondary: Option<NameLanguage>,
+}
+
+/// Reads a language code, empty text is `None`
+fn optional_language<'de, D>(deserializer: D) -> Result<Option<NameLanguage>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(code) if !code.trim().is_empty() => NameLanguage::from_code(&code)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown name language `{code}`"))),
+        _ => Ok(None),
+    }
+}
+
+impl Default for NamesConfig {
+    fn default() -> Self {
+        Self {
+            primary: NameLanguage::De,
+            secondary: Some(NameLanguage::En),
+        }
+    }
+}
+
+impl NamesConfig {
+    /// The primary language, then the secondary one
+    pub fn shown(&self) -> Vec<NameLanguage> {
+        std::iter::once(self.primary)
+            .chain(
+                self.secondary
+                    .filter(|secondary| *secondary != self.primary),
+            )
+            .collect()
+    }
+}
+
+/// The part of the configuration the client needs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClientConfig {
+    pub binder: BinderLayout,
+    pub names: NamesConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `tracing` filter directive, `RUST_LOG` still takes precedence
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: if cfg!(feature = "dev") {
+                // Dev: Show debug and above, exclude noisy crates
+                "debug,hyper=info,tower=info,tokio=info,dioxus_core=info".to_string()
+            } else {
+                // Prod: Show info and above by default
+                "info".to_string()
+            },
+        }
+    }
+}
+
+/// The active configuration
+///
+/// Falls back to the defaults when nothing was loaded, which is the case on
+/// the client and in unit tests.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// The active binder layout
+pub fn layout() -> BinderLayout {
+    get().binder
+}
+
+/// What [`init_client`] receives from the server
+pub fn client() -> ClientConfig {
+    ClientConfig {
+        binder: get().binder,
+        names: get().names,
+    }
+}
+
+/// Installs the configuration received from the server on the client
+///
+/// Has to run before the first [`get`], which settles on the defaults.
+pub fn init_client(client: ClientConfig) {
+    let installed = CONFIG.set(Config {
+        binder: client.binder,
+        names: client.names,
+        ..Default::default()
+    });
+    if installed.is_err() && client != self::client() {
+        tracing::error!(
+            "client config arrived after the config was first read, keeping the earlier one"
+        );
+    }
+}
+
+#[cfg(feature = "server")]
+pub use loader::*;
+
+#[cfg(feature = "server")]
+mod loader {
+    use super::*;
+    use std::path::Path;
+    use thiserror::Error;
+
+    /// Environment variable pointing at the config file
+    pub const CONFIG_PATH_VAR: &str = "FS_CHAOT_CONFIG";
+    const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+    #[derive(Debug, Error)]
+    pub enum ConfigError {
+        #[error("could not read config file {}: {source}", .path.display())]
+        Read {
+            path: PathBuf,
+            source: std::io::Error,
+        },
+        #[error("could not parse config file {}: {source}", .path.display())]
+        Parse {
+            path: PathBuf,
+            source: toml::de::Error,
+        },
+        #[error("environment variable {var}={value:?} is invalid: {reason}")]
+        Env {
+            var: String,
+            value: String,
+            reason: String,
+        },
+        #[error("invalid configuration:\n{}", .0.iter().map(|p| format!("  - {p}")).collect::<Vec<_>>().join("\n"))]
+        Invalid(Vec<String>),
+    }
+
+    impl Config {
+        /// Loads the config file, applies environment overrides and validates the result
+        pub fn load() -> Result<Self, ConfigError> {
+            let (path, explicit) = match std::env::var(CONFIG_PATH_VAR) {
+                Ok(path) => (PathBuf::from(path), true),
+                Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+            };
+
+            let mut config = if explicit || path.exists() {
+                Self::from_file(&path)?
+            } else {
+                Self::default()
+            };
+
+            config.apply_env(|var| std::env::var(var).ok())?;
+            config.validate()?;
+            Ok(config)
+        }
+
+        pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+            let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            toml::from_str(&content).map_err(|source| ConfigError::Parse {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+
+        /// Applies `FS_CHAOT_*` and `APP_PASSWORD` overrides
+        pub fn apply_env(
+            &mut self,
+            env: impl Fn(&str) -> Option<String>,
+        ) -> Result<(), ConfigError> {
+            if let Some(path) = env("FS_CHAOT_DATABASE_PATH") {
+                self.database.path = PathBuf::from(path);
+            }
+            if let Some(dir) = env("FS_CHAOT_BACKUP_DIR") {
+                self.backup.dir = PathBuf::from(dir);
+            }
+            if let Some(password) = env("APP_PASSWORD") {
+                self.auth.password = Some(password);
+            }
+            if let Some(url) = env("FS_CHAOT_POKEAPI_BASE_URL") {
+                self.pokeapi.base_url = url;
+            }
+            if let Some(url) = env("FS_CHAOT_POKEAPI_LANGUAGE_URL") {
+                self.pokeapi.language_url = url;
+            }
+            if let Some(url) = env("FS_CHAOT_POKEAPI_TYPES_URL") {
+                self.pokeapi.types_url = url;
+            }
+            if let Some(url) = env("FS_CHAOT_POKEAPI_SPRITE_URL") {
+                self.pokeapi.sprite_url = url;
+            }
+            if let Some(level) = env("FS_CHAOT_LOG_LEVEL") {
+                self.log.level = level;
+            }
+
+            let language = |var: &str, value: &str| {
+                NameLanguage::from_code(value).ok_or_else(|| ConfigError::Env {
+                    var: var.to_string(),
+                    value: value.to_string(),
+                    reason: "unknown name language".to_string(),
+                })
+            };
+            if let Some(value) = env("FS_CHAOT_NAMES_PRIMARY") {
+                self.names.primary = language("FS_CHAOT_NAMES_PRIMARY", &value)?;
+            }
+            if let Some(value) = env("FS_CHAOT_NAMES_SECONDARY") {
+                // Empty hides the secondary name
+                self.names.secondary = match value.trim() {
+                    "" => None,
+                    code => Some(language("FS_CHAOT_NAMES_SECONDARY", code)?),
+                };
+            }
+
+            let numeric = |var: &str, target: &mut usize| -> Result<(), ConfigError> {
+                if let Some(value) = env(var) {
+                    *target = value.trim().parse().map_err(|e| ConfigError::Env {
+                        var: var.to_string(),
+                        value: value.clone(),
+                        reason: format!("{e}"),
+                    })?;
+                }
+                Ok(())
+            };
+            numeric("FS_CHAOT_BINDER_BOOKS", &mut self.binder.books)?;
+            numeric(
+                "FS_CHAOT_BINDER_CARDS_PER_BOOK",
+                &mut self.binder.cards_per_book,
+            )?;
+            numeric(
+                "FS_CHAOT_BINDER_CARDS_PER_DOUBLE_PAGE",
+                &mut self.binder.cards_per_double_page,
+            )?;
+            numeric("FS_CHAOT_BINDER_MAX_POKEMON", &mut self.binder.max_pokemon)?;
+            numeric("FS_CHAOT_BACKUP_HOURLY_KEEP", &mut self.backup.hourly_keep)?;
+            numeric("FS_CHAOT_BACKUP_DAILY_KEEP", &mut self.backup.daily_keep)?;
+
+            Ok(())
+        }
+
+        /// Collects every problem instead of stopping at the first one
+        pub fn validate(&self) -> Result<(), ConfigError> {
+            let mut problems = Vec::new();
+
+            if self.database.path.as_os_str().is_empty() {
+                problems.push("database.path must not be empty".to_string());
+            }
+            if self.backup.dir.as_os_str().is_empty() {
+                problems.push("backup.dir must not be empty".to_string());
+            }
+
+            match &self.auth.password {
+                Some(password) if password.is_empty() => {
+                    problems.push("auth.password / APP_PASSWORD must not be empty".to_string())
+                }
+                None if !cfg!(feature = "dev") => problems.push(
+                    "no login password configured, set APP_PASSWORD or auth.password".to_string(),
+                ),
+                _ => {}
+            }
+
+            for (key, url) in [
+                ("pokeapi.base_url", &self.pokeapi.base_url),
+                ("pokeapi.language_url", &self.pokeapi.language_url),
+                ("pokeapi.types_url", &self.pokeapi.types_url),
+                ("pokeapi.sprite_url", &self.pokeapi.sprite_url),
+            ] {
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    problems.push(format!("{key} must be an http(s) URL, got {url:?}"));
+                }
+            }
+
+            problems.extend(self.binder.problems());
+
+            if self.names.secondary == Some(self.names.primary) {
+                problems.push(format!(
+                    "names.secondary must differ from names.primary ({})",
+                    self.names.primary
+                ));
+            }
+
+            if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log.level) {
+                problems.push(format!("log.level {:?} is invalid: {e}", self.log.level));
+            }
+
+            if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(ConfigError::Invalid(problems))
+            }
+        }
+    }
+
+    /// Loads the configuration and makes it available through [`get`]
+    pub fn init() -> Result<&'static Config, ConfigError> {
+        let config = Config::load()?;
+        Ok(CONFIG.get_or_init(|| config))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashMap;
+
+        fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            move |key| vars.get(key).cloned()
+        }
+
+        fn valid() -> Config {
+            let mut config = Config::default();
+            config.auth.password = Some("secret".to_string());
+            config
+        }
+
+        #[test]
+        fn test_default_config_is_valid_with_password() {
+            assert!(valid().validate().is_ok());
+        }
+
+        #[test]
+        fn test_parse_partial_toml() {
+            let config: Config = toml::from_str(
+                r#"
+                [database]
+                path = "/data/collection.db"
+
+                [binder]
+                books = 3
+                "#,
+            )
+            .unwrap();
+
+            assert_eq!(config.database.path, PathBuf::from("/data/collection.db"));
+            assert_eq!(config.binder.books, 3);
+            assert_eq!(config.binder.cards_per_double_page, 24);
+            assert_eq!(config.pokeapi, PokeApiConfig::default());
+        }
+
+        #[test]
+        fn test_unknown_keys_are_rejected() {
+            let result = toml::from_str::<Config>("[database]\npaht = \"typo.db\"\n");
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_env_overrides_file_values() {
+            let mut config = valid();
+            config
+                .apply_env(env(&[
+                    ("APP_PASSWORD", "from-env"),
+                    ("FS_CHAOT_DATABASE_PATH", "other.db"),
+                    ("FS_CHAOT_BINDER_BOOKS", "4"),
+                ]))
+                .unwrap();
+
+            assert_eq!(config.auth.password.as_deref(), Some("from-env"));
+            assert_eq!(config.database.path, PathBuf::from("other.db"));
+            assert_eq!(config.binder.books, 4);
+        }
+
+        #[test]
+        fn test_env_override_with_invalid_number() {
+            let mut config = valid();
+            let err = config
+                .apply_env(env(&[("FS_CHAOT_BINDER_BOOKS", "two")]))
+                .unwrap_err();
+            assert!(err.to_string().contains("FS_CHAOT_BINDER_BOOKS"));
+        }
+
+        #[test]
+        fn test_validate_reports_all_problems() {
+            let mut config = valid();
+            config.pokeapi.base_url = "pokeapi.co".to_string();
+            config.binder.cards_per_double_page = 23;
+            config.binder.books = 1;
+
+            match config.validate() {
+                Err(ConfigError::Invalid(problems)) => assert_eq!(problems.len(), 3),
+                other => panic!("expected invalid config, got {other:?}"),
+            }
+        }
+
+        #[test]
+        fn test_name_languages() {
+            let config: Config =
+                toml::from_str("[names]\nprimary = \"ja\"\nsecondary = \"en\"\n").unwrap();
+            assert_eq!(config.names.primary, NameLanguage::Ja);
+            assert_eq!(
+                config.names.shown(),
+                vec![NameLanguage::Ja, NameLanguage::En]
+            );
+            assert!(toml::from_str::<Config>("[names]\nprimary = \"xx\"\n").is_err());
+            let hidden: Config = toml::from_str("[names]\nsecondary = \"\"\n").unwrap();
+            assert_eq!(hidden.names.shown(), vec![NameLanguage::De]);
+
+            let mut config = valid();
+            config
+                .apply_env(env(&[
+                    ("FS_CHAOT_NAMES_PRIMARY", "fr"),
+                    ("FS_CHAOT_NAMES_SECONDARY", ""),
+                ]))
+                .unwrap();
+            assert_eq!(config.names.shown(), vec![NameLanguage::Fr]);
+            assert!(config
+                .apply_env(env(&[("FS_CHAOT_NAMES_PRIMARY", "klingon")]))
+                .is_err());
+
+            config.names.secondary = Some(NameLanguage::Fr);
+            assert!(config.validate().is_err());
+        }
+
+        #[test]
+        fn test_double_page_is_clamped_to_max_pokemon() {
+            let layout = BinderLayout::default();
+            assert_eq!(layout.double_page(1), 1..=24);
+            assert_eq!(layout.double_page(layout.total_pages()), 1009..=1025);
+        }
+
+        #[test]
+        fn test_empty_password_is_rejected() {
+            let mut config = valid();
+            config.auth.password = Some(String::new());
+            assert!(config.validate().is_err());
+        }
+    }
+}
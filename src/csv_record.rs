@@ -6,3 +6,11 @@ pub(crate) struct Record {
     #[allow(dead_code)]
     pub(crate) genus: String,
 }
+
+/// A row of the PokeAPI `pokemon_types.csv`
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct TypeRecord {
+    pub(crate) pokemon_id: u32,
+    pub(crate) type_id: usize,
+    pub(crate) slot: u8,
+}
@@ -0,0 +1,147 @@
+//! Shared SQLite connection pool for the server
+//!
+//! All database access goes through a single `r2d2` pool. Every pooled
+//! connection is configured for WAL mode with a busy timeout, and queries are
+//! executed on tokio's blocking thread pool so they never stall the async
+//! executor. Schema creation, migrations and seeding run once in [`init`],
+//! before the server starts accepting requests.
+
+use crate::catalog::{self, Catalog, CatalogDiff};
+use crate::migrations::{self, Migration};
+use anyhow::{anyhow, Context, Result};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How long a connection waits on a locked database before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound for concurrently checked out connections
+const MAX_CONNECTIONS: u32 = 8;
+
+pub type DbPool = Pool<SqliteConnectionManager>;
+pub type DbConnection = PooledConnection<SqliteConnectionManager>;
+
+static POOL: OnceLock<DbPool> = OnceLock::new();
+
+/// Opens the connection pool, creates the schema, runs migrations and seeds
+/// the expansions table.
+///
+/// Must be called once at startup before the server is launched. Calling it
+/// again is a no-op.
+pub fn init() -> Result<()> {
+    if POOL.get().is_some() {
+        return Ok(());
+    }
+
+    let path = &crate::config::get().database.path;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create database directory {}", dir.display()))?;
+    }
+
+    let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.busy_timeout(BUSY_TIMEOUT)
+    });
+
+    let pool = Pool::builder()
+        .max_size(MAX_CONNECTIONS)
+        .build(manager)
+        .with_context(|| format!("Failed to open database pool at {}", path.display()))?;
+
+    {
+        let mut conn = pool.get().context("Failed to get connection for setup")?;
+        crate::backend::setup_database(&mut conn)?;
+        // Only changes made from now on are sent to live sync clients
+        crate::sync::init(&conn)?;
+    }
+
+    tracing::info!(
+        path = %path.display(),
+        max_connections = MAX_CONNECTIONS,
+        "database pool initialized"
+    );
+
+    // A concurrent init would have run the same idempotent setup, keep the first pool
+    let _ = POOL.set(pool);
+    Ok(())
+}
+
+/// Lists the migrations [`init`] would apply, without modifying the database
+pub fn pending_migrations() -> Result<Vec<Migration>> {
+    let path = &crate::config::get().database.path;
+    if !path.exists() {
+        return migrations::embedded();
+    }
+
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {} read-only", path.display()))?;
+    migrations::pending(&conn)
+}
+
+/// Compares `catalog` with the stored expansions, without modifying the database
+///
+/// Without a database file the diff is against the sets [`init`] would seed.
+pub fn catalog_diff(catalog: &Catalog) -> Result<CatalogDiff> {
+    let path = &crate::config::get().database.path;
+    if !path.exists() {
+        let mut conn = rusqlite::Connection::open_in_memory()?;
+        crate::backend::setup_database(&mut conn)?;
+        return catalog::diff(&conn, catalog);
+    }
+
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open {} read-only", path.display()))?;
+    if !migrations::pending(&conn)?.is_empty() {
+        return Err(anyhow!(
+            "{} has pending migrations, start the server once before comparing the catalog",
+            path.display()
+        ));
+    }
+    catalog::diff(&conn, catalog)
+}
+
+fn pool() -> Result<&'static DbPool> {
+    POOL.get()
+        .ok_or_else(|| anyhow!("Database pool used before db::init() was called"))
+}
+
+/// Checks out a connection for callers that already run off the executor,
+/// such as the backup scheduler thread
+pub fn get() -> Result<DbConnection> {
+    pool()?
+        .get()
+        .context("Failed to get database connection from pool")
+}
+
+/// Runs `f` with a pooled connection on the blocking thread pool
+///
+/// # Examples
+///
+/// ```ignore
+/// let count = db::run(|conn| {
+///     Ok(conn.query_row("SELECT COUNT(*) FROM cards", [], |row| row.get::<_, i64>(0))?)
+/// })
+/// .await?;
+/// ```
+pub async fn run<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce(&mut DbConnection) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = pool()?;
+    tokio::task::spawn_blocking(move || {
+        let mut conn = pool
+            .get()
+            .context("Failed to get database connection from pool")?;
+        f(&mut conn)
+    })
+    .await
+    .context("Database task panicked")?
+}
@@ -4,22 +4,156 @@ use rusqlite::{
     ToSql,
 };
 use std::fmt::Display;
+use strum::EnumIter;
 
 use crate::card::Rarity;
+use crate::card_number::{CardNumber, NumberScheme};
+use crate::i18n::Language;
+
+/// Market an expansion was released in
+#[derive(
+    Default, Debug, Clone, Copy, serde::Deserialize, serde::Serialize, PartialEq, Eq, EnumIter,
+)]
+pub enum Region {
+    #[default]
+    EN,
+    DE,
+    JP,
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EN => f.write_str("EN"),
+            Self::DE => f.write_str("DE"),
+            Self::JP => f.write_str("JP"),
+        }
+    }
+}
+
+impl From<&str> for Region {
+    fn from(value: &str) -> Self {
+        match value {
+            "DE" => Self::DE,
+            "JP" => Self::JP,
+            _ => Self::EN,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl ToSql for Region {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(
+            self.to_string(),
+        )))
+    }
+}
+
+#[cfg(feature = "server")]
+impl FromSql for Region {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        FromSqlResult::Ok(Region::from(value.as_str()?))
+    }
+}
 
 #[derive(Default, Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
 pub struct Expansion {
     pub id: usize,
     pub name: String,
+    /// German name, `None` if it is unknown or the same as `name`
+    #[serde(default)]
+    pub name_de: Option<String>,
     pub abbreviation: String,
     pub cards: usize,
     pub secret_cards: usize,
+    /// Hidden from the pickers, prints already stored keep it
+    #[serde(default)]
+    pub archived: bool,
+    /// e.g. "Sword & Shield", empty if unknown
+    #[serde(default)]
+    pub series: String,
+    /// ISO date, `YYYY-MM-DD`
+    #[serde(default)]
+    pub release_date: Option<String>,
+    #[serde(default)]
+    pub region: Region,
+    /// Official set code, e.g. "SV01"
+    #[serde(default)]
+    pub set_code: Option<String>,
+    /// Image of the set symbol
+    #[serde(default)]
+    pub symbol_url: Option<String>,
+    /// Padding and prefixed subsets of the card numbers
+    #[serde(default)]
+    pub numbers: NumberScheme,
 }
 
+/// The name in the current UI language with the abbreviation
 impl Display for Expansion {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} ({})", self.name, self.abbreviation)
+        write!(f, "{} ({})", self.localized_name(), self.abbreviation)
+    }
+}
+
+impl Expansion {
+    /// The name in the current UI language, English where there is no translation
+    pub fn localized_name(&self) -> &str {
+        self.name_in(crate::i18n::language())
+    }
+
+    /// The name in `language`, English where there is no translation
+    pub fn name_in(&self, language: Language) -> &str {
+        match (language, &self.name_de) {
+            (Language::De, Some(name_de)) => name_de,
+            _ => &self.name,
+        }
+    }
+
+    /// Checks a card number typed for this expansion
+    pub fn check_number(&self, input: &str) -> Result<CardNumber, String> {
+        self.numbers.check(input, self.cards, self.secret_cards)
+    }
+
+    /// A stored card number as printed on the card, numbers the scheme does
+    /// not understand are shown as stored
+    pub fn printed_number(&self, card_number: &str) -> String {
+        match self.numbers.parse(card_number) {
+            Ok(number) => self.numbers.printed(&number, self.cards),
+            Err(_) => card_number.to_string(),
+        }
+    }
+}
+
+/// Series label of expansions without one
+pub const UNKNOWN_SERIES: &str = "Other";
+
+/// Expansions grouped by series, oldest series first
+///
+/// Expansions without a release date count as older than dated ones of the
+/// same series and keep the order they were added in, which is release order
+/// for the seeded sets. Expansions without a series come last.
+pub fn by_series<'a>(
+    expansions: impl IntoIterator<Item = &'a Expansion>,
+) -> Vec<(&'a str, Vec<&'a Expansion>)> {
+    let key = |e: &Expansion| (e.release_date.clone().unwrap_or_default(), e.id);
+    let mut sorted: Vec<&Expansion> = expansions.into_iter().collect();
+    sorted.sort_by_key(|e| key(e));
+
+    let mut groups: Vec<(&str, Vec<&Expansion>)> = Vec::new();
+    for expansion in sorted {
+        let series = match expansion.series.as_str() {
+            "" => UNKNOWN_SERIES,
+            series => series,
+        };
+        match groups.iter_mut().find(|(name, _)| *name == series) {
+            Some((_, members)) => members.push(expansion),
+            None => groups.push((series, vec![expansion])),
+        }
     }
+    // Groups are in order of their oldest expansion already
+    groups.sort_by_key(|(series, _)| *series == UNKNOWN_SERIES);
+    groups
 }
 
 #[derive(Default, Debug, Clone, serde::Deserialize, serde::Serialize, PartialEq)]
@@ -32,20 +166,20 @@ pub struct CardExpansion {
 }
 
 impl CardExpansion {
-    /// Calculates if a card is secret based on card_number and expansion.cards
-    /// Returns true if card_number (as integer) > expansion.cards
+    /// Whether the card is numbered beyond the expansion's regular cards
+    ///
+    /// Subset numbers such as `TG05` are never secret.
     #[allow(dead_code)]
     pub fn is_secret(&self, expansion: &Expansion) -> bool {
-        self.card_number
-            .parse::<usize>()
-            .map(|num| num > expansion.cards)
-            .unwrap_or(false) // Non-numeric card numbers (promos) default to false
+        matches!(
+            expansion.numbers.parse(&self.card_number),
+            Ok(CardNumber::Main(number)) if number > expansion.cards
+        )
     }
 
-    /// Formats card number for display: "24/165" format
-    #[allow(dead_code)]
+    /// The card number as printed, e.g. "24/165", "049/198" or "TG05/TG30"
     pub fn display_card_number(&self, expansion: &Expansion) -> String {
-        format!("{}/{}", self.card_number, expansion.cards)
+        expansion.printed_number(&self.card_number)
     }
 }
 
@@ -86,3 +220,43 @@ impl FromSql for CardExpansion {
         Err(rusqlite::types::FromSqlError::InvalidType)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expansion(id: usize, series: &str, release_date: Option<&str>) -> Expansion {
+        Expansion {
+            id,
+            name: format!("Expansion {id}"),
+            series: series.to_string(),
+            release_date: release_date.map(str::to_string),
+            ..Expansion::default()
+        }
+    }
+
+    #[test]
+    fn test_by_series() {
+        let expansions = [
+            expansion(1, "Scarlet & Violet", Some("2023-03-31")),
+            expansion(2, "", None),
+            expansion(3, "Sword & Shield", None),
+            expansion(4, "Scarlet & Violet", Some("2023-05-26")),
+            expansion(5, "Sword & Shield", Some("2020-02-07")),
+            expansion(6, "Scarlet & Violet", None),
+        ];
+
+        let groups: Vec<(&str, Vec<usize>)> = by_series(&expansions)
+            .into_iter()
+            .map(|(series, members)| (series, members.iter().map(|e| e.id).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("Sword & Shield", vec![3, 5]),
+                ("Scarlet & Violet", vec![6, 1, 4]),
+                (UNKNOWN_SERIES, vec![2]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,698 @@
+//! Creating, editing and archiving expansions
+//!
+//! New sets used to need a seed or migration change. Expansions are now
+//! managed from the app: names and abbreviations are unique ignoring case,
+//! and archiving hides an expansion from the pickers without touching the
+//! prints already stored for it. Every change is written to the append-only
+//! `expansion_events` table together with the acting profile.
+
+use crate::card_number::NumberScheme;
+use crate::expansion::{Expansion, Region};
+use crate::i18n::{t, tf};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Longest accepted name, in characters
+pub const MAX_NAME_LEN: usize = 80;
+
+/// Longest accepted abbreviation, in characters
+pub const MAX_ABBREVIATION_LEN: usize = 10;
+
+/// Longest accepted series name or set code, in characters
+pub const MAX_SERIES_LEN: usize = 60;
+
+/// The editable fields of an expansion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExpansionDraft {
+    pub name: String,
+    pub name_de: Option<String>,
+    pub abbreviation: String,
+    pub cards: usize,
+    pub secret_cards: usize,
+    pub series: String,
+    pub release_date: Option<String>,
+    pub region: Region,
+    pub set_code: Option<String>,
+    pub symbol_url: Option<String>,
+    pub numbers: NumberScheme,
+}
+
+impl From<&Expansion> for ExpansionDraft {
+    fn from(expansion: &Expansion) -> Self {
+        Self {
+            name: expansion.name.clone(),
+            name_de: expansion.name_de.clone(),
+            abbreviation: expansion.abbreviation.clone(),
+            cards: expansion.cards,
+            secret_cards: expansion.secret_cards,
+            series: expansion.series.clone(),
+            release_date: expansion.release_date.clone(),
+            region: expansion.region,
+            set_code: expansion.set_code.clone(),
+            symbol_url: expansion.symbol_url.clone(),
+            numbers: expansion.numbers.clone(),
+        }
+    }
+}
+
+/// Trimmed `value`, `None` if nothing is left
+fn optional(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// True for a plausible `YYYY-MM-DD` date, the format of `<input type="date">`
+fn is_iso_date(date: &str) -> bool {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return false;
+    };
+    let number = |part: &str, len: usize| {
+        (part.len() == len && part.chars().all(|c| c.is_ascii_digit()))
+            .then(|| part.parse::<u32>().ok())
+            .flatten()
+    };
+    matches!(
+        (number(year, 4), number(month, 2), number(day, 2)),
+        (Some(_), Some(1..=12), Some(1..=31))
+    )
+}
+
+impl ExpansionDraft {
+    /// The trimmed draft, or why it can't be stored
+    pub fn check(&self) -> Result<Self, String> {
+        let name = self.name.trim();
+        let abbreviation = self.abbreviation.trim();
+        if name.is_empty() {
+            return Err("An expansion needs a name".to_string());
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            return Err(format!("Names are limited to {MAX_NAME_LEN} characters"));
+        }
+        let name_de = optional(&self.name_de).filter(|name_de| name_de != name);
+        if name_de
+            .as_ref()
+            .is_some_and(|name_de| name_de.chars().count() > MAX_NAME_LEN)
+        {
+            return Err(format!("Names are limited to {MAX_NAME_LEN} characters"));
+        }
+        if abbreviation.is_empty() {
+            return Err("An expansion needs an abbreviation".to_string());
+        }
+        if abbreviation.chars().count() > MAX_ABBREVIATION_LEN {
+            return Err(format!(
+                "Abbreviations are limited to {MAX_ABBREVIATION_LEN} characters"
+            ));
+        }
+        if self.cards == 0 {
+            return Err("An expansion has at least one card".to_string());
+        }
+        let series = self.series.trim();
+        if series.chars().count() > MAX_SERIES_LEN {
+            return Err(format!("Series are limited to {MAX_SERIES_LEN} characters"));
+        }
+        let release_date = optional(&self.release_date);
+        if let Some(date) = release_date.as_deref().filter(|date| !is_iso_date(date)) {
+            return Err(format!("`{date}` is not a date like 2025-09-26"));
+        }
+        let set_code = optional(&self.set_code);
+        if set_code
+            .as_ref()
+            .is_some_and(|code| code.chars().count() > MAX_SERIES_LEN)
+        {
+            return Err(format!(
+                "Set codes are limited to {MAX_SERIES_LEN} characters"
+            ));
+        }
+        let symbol_url = optional(&self.symbol_url);
+        if let Some(url) = symbol_url.as_deref().filter(|url| {
+            !url.starts_with("https://") && !url.starts_with("http://") && !url.starts_with('/')
+        }) {
+            return Err(format!(
+                "The symbol `{url}` must be an http(s) URL or an asset path"
+            ));
+        }
+        let mut numbers = self.numbers.clone();
+        for subset in &mut numbers.subsets {
+            subset.prefix = subset.prefix.trim().to_string();
+            subset.name = subset.name.trim().to_string();
+        }
+        if let Some(problem) = numbers.problem() {
+            return Err(problem);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            name_de,
+            abbreviation: abbreviation.to_string(),
+            cards: self.cards,
+            secret_cards: self.secret_cards,
+            series: series.to_string(),
+            release_date,
+            region: self.region,
+            set_code,
+            symbol_url,
+            numbers,
+        })
+    }
+}
+
+/// A single recorded change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExpansionChange {
+    Created {
+        expansion: Expansion,
+    },
+    Updated {
+        before: Box<Expansion>,
+        after: Box<Expansion>,
+    },
+    Archived {
+        expansion: Expansion,
+    },
+    Restored {
+        expansion: Expansion,
+    },
+}
+
+impl ExpansionChange {
+    /// Value of the `kind` column
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExpansionChange::Created { .. } => "created",
+            ExpansionChange::Updated { .. } => "updated",
+            ExpansionChange::Archived { .. } => "archived",
+            ExpansionChange::Restored { .. } => "restored",
+        }
+    }
+
+    /// One line for the change list, e.g. "Archived Base Set (BS)"
+    pub fn describe(&self) -> String {
+        match self {
+            ExpansionChange::Created { expansion } => {
+                tf("expansion_change.created", &[("expansion", expansion)])
+            }
+            ExpansionChange::Updated { before, after } => {
+                let mut fields = Vec::new();
+                let shown =
+                    |value: &Option<String>| value.clone().unwrap_or_else(|| "–".to_string());
+                let mut changed = |field: &str, before: &dyn Display, after: &dyn Display| {
+                    fields.push(tf(
+                        "expansion_change.field",
+                        &[("field", &t(field)), ("before", before), ("after", after)],
+                    ))
+                };
+                if before.name != after.name {
+                    changed("expansion_field.name", &before.name, &after.name);
+                }
+                if before.name_de != after.name_de {
+                    changed(
+                        "expansion_field.name_de",
+                        &shown(&before.name_de),
+                        &shown(&after.name_de),
+                    );
+                }
+                if before.abbreviation != after.abbreviation {
+                    changed(
+                        "expansion_field.abbreviation",
+                        &before.abbreviation,
+                        &after.abbreviation,
+                    );
+                }
+                if before.cards != after.cards {
+                    changed("expansion_field.cards", &before.cards, &after.cards);
+                }
+                if before.secret_cards != after.secret_cards {
+                    changed(
+                        "expansion_field.secret_cards",
+                        &before.secret_cards,
+                        &after.secret_cards,
+                    );
+                }
+                if before.series != after.series {
+                    changed("expansion_field.series", &before.series, &after.series);
+                }
+                if before.release_date != after.release_date {
+                    changed(
+                        "expansion_field.release_date",
+                        &shown(&before.release_date),
+                        &shown(&after.release_date),
+                    );
+                }
+                if before.region != after.region {
+                    changed("expansion_field.region", &before.region, &after.region);
+                }
+                if before.set_code != after.set_code {
+                    changed(
+                        "expansion_field.set_code",
+                        &shown(&before.set_code),
+                        &shown(&after.set_code),
+                    );
+                }
+                if before.symbol_url != after.symbol_url {
+                    fields.push(t("expansion_field.symbol").to_string());
+                }
+                if before.numbers != after.numbers {
+                    fields.push(t("expansion_field.numbers").to_string());
+                }
+                tf(
+                    "expansion_change.updated",
+                    &[("expansion", after), ("fields", &fields.join(", "))],
+                )
+            }
+            ExpansionChange::Archived { expansion } => {
+                tf("expansion_change.archived", &[("expansion", expansion)])
+            }
+            ExpansionChange::Restored { expansion } => {
+                tf("expansion_change.restored", &[("expansion", expansion)])
+            }
+        }
+    }
+}
+
+/// A row of `expansion_events`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpansionEvent {
+    pub id: usize,
+    pub expansion_id: usize,
+    pub change: ExpansionChange,
+    pub profile: String,
+    pub created_at: String,
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use anyhow::Result;
+    use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+
+    /// Another expansion already uses the name or abbreviation, both ignore case
+    #[derive(Debug, thiserror::Error)]
+    #[error("An expansion with the {field} `{value}` already exists")]
+    pub struct DuplicateExpansion {
+        pub field: &'static str,
+        pub value: String,
+    }
+
+    /// Columns [`read_expansion`] expects, in order
+    pub(crate) const COLUMNS: &str = "id, name, abbreviation, cards, secret_cards, archived, \
+         series, release_date, region, set_code, symbol_url, number_scheme, name_de";
+
+    pub(crate) fn read_expansion(row: &Row<'_>) -> rusqlite::Result<Expansion> {
+        Ok(Expansion {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            abbreviation: row.get(2)?,
+            cards: row.get(3)?,
+            secret_cards: row.get(4)?,
+            archived: row.get(5)?,
+            series: row.get(6)?,
+            release_date: row.get(7)?,
+            region: row.get(8)?,
+            set_code: row.get(9)?,
+            symbol_url: row.get(10)?,
+            numbers: row.get(11)?,
+            name_de: row.get(12)?,
+        })
+    }
+
+    /// All expansions including archived ones, by name
+    pub fn list(conn: &Connection) -> Result<Vec<Expansion>> {
+        Ok(conn
+            .prepare(&format!("SELECT {COLUMNS} FROM expansions ORDER BY name"))?
+            .query_map([], read_expansion)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?)
+    }
+
+    pub fn get(conn: &Connection, id: usize) -> Result<Option<Expansion>> {
+        Ok(conn
+            .query_row(
+                &format!("SELECT {COLUMNS} FROM expansions WHERE id = ?1"),
+                [id],
+                read_expansion,
+            )
+            .optional()?)
+    }
+
+    /// Fails if an expansion other than `id` uses the draft's name or abbreviation
+    fn check_unique(conn: &Connection, id: Option<usize>, draft: &ExpansionDraft) -> Result<()> {
+        let taken = |column: &str, value: &str| -> rusqlite::Result<bool> {
+            conn.query_row(
+                &format!(
+                    "SELECT EXISTS(SELECT 1 FROM expansions
+                     WHERE {column} = ?1 COLLATE NOCASE AND id IS NOT ?2)"
+                ),
+                params![value, id],
+                |row| row.get(0),
+            )
+        };
+        if taken("name", &draft.name)? {
+            return Err(DuplicateExpansion {
+                field: "name",
+                value: draft.name.clone(),
+            }
+            .into());
+        }
+        if taken("abbreviation", &draft.abbreviation)? {
+            return Err(DuplicateExpansion {
+                field: "abbreviation",
+                value: draft.abbreviation.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// `expansion` with the editable fields taken from `draft`
+    pub(crate) fn apply(expansion: Expansion, draft: &ExpansionDraft) -> Expansion {
+        let draft = draft.clone();
+        Expansion {
+            name: draft.name,
+            name_de: draft.name_de,
+            abbreviation: draft.abbreviation,
+            cards: draft.cards,
+            secret_cards: draft.secret_cards,
+            series: draft.series,
+            release_date: draft.release_date,
+            region: draft.region,
+            set_code: draft.set_code,
+            symbol_url: draft.symbol_url,
+            numbers: draft.numbers,
+            ..expansion
+        }
+    }
+
+    fn record(
+        conn: &Connection,
+        expansion_id: usize,
+        change: &ExpansionChange,
+        profile: &str,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT INTO expansion_events (expansion_id, kind, payload, profile) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                expansion_id,
+                change.kind(),
+                serde_json::to_string(change)?,
+                profile
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stores a new expansion, `draft` must have passed [`ExpansionDraft::check`]
+    pub fn create(
+        conn: &mut Connection,
+        draft: &ExpansionDraft,
+        profile: &str,
+    ) -> Result<Expansion> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let expansion = insert(&tx, draft, profile)?;
+        tx.commit()?;
+        Ok(expansion)
+    }
+
+    /// [`create`] inside a transaction the caller commits
+    pub(crate) fn insert(
+        conn: &Connection,
+        draft: &ExpansionDraft,
+        profile: &str,
+    ) -> Result<Expansion> {
+        check_unique(conn, None, draft)?;
+        conn.execute(
+            "INSERT INTO expansions (name, abbreviation, cards, secret_cards, series, release_date, region, set_code, symbol_url, number_scheme, name_de)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                draft.name,
+                draft.abbreviation,
+                draft.cards,
+                draft.secret_cards,
+                draft.series,
+                draft.release_date,
+                draft.region,
+                draft.set_code,
+                draft.symbol_url,
+                draft.numbers,
+                draft.name_de
+            ],
+        )?;
+        let expansion = apply(
+            Expansion {
+                id: conn.last_insert_rowid() as usize,
+                ..Expansion::default()
+            },
+            draft,
+        );
+        record(
+            conn,
+            expansion.id,
+            &ExpansionChange::Created {
+                expansion: expansion.clone(),
+            },
+            profile,
+        )?;
+        Ok(expansion)
+    }
+
+    /// Replaces the editable fields, `None` if there is no expansion with the ID
+    ///
+    /// Saving unchanged fields records nothing.
+    pub fn update(
+        conn: &mut Connection,
+        id: usize,
+        draft: &ExpansionDraft,
+        profile: &str,
+    ) -> Result<Option<Expansion>> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let expansion = replace(&tx, id, draft, profile)?;
+        tx.commit()?;
+        Ok(expansion)
+    }
+
+    /// [`update`] inside a transaction the caller commits
+    pub(crate) fn replace(
+        conn: &Connection,
+        id: usize,
+        draft: &ExpansionDraft,
+        profile: &str,
+    ) -> Result<Option<Expansion>> {
+        let Some(before) = get(conn, id)? else {
+            return Ok(None);
+        };
+        check_unique(conn, Some(id), draft)?;
+        let after = apply(before.clone(), draft);
+        if after != before {
+            conn.execute(
+                "UPDATE expansions SET name = ?1, abbreviation = ?2, cards = ?3, secret_cards = ?4,
+                 series = ?5, release_date = ?6, region = ?7, set_code = ?8, symbol_url = ?9,
+                 number_scheme = ?10, name_de = ?11
+                 WHERE id = ?12",
+                params![
+                    after.name,
+                    after.abbreviation,
+                    after.cards,
+                    after.secret_cards,
+                    after.series,
+                    after.release_date,
+                    after.region,
+                    after.set_code,
+                    after.symbol_url,
+                    after.numbers,
+                    after.name_de,
+                    id
+                ],
+            )?;
+            record(
+                conn,
+                id,
+                &ExpansionChange::Updated {
+                    before: Box::new(before),
+                    after: Box::new(after.clone()),
+                },
+                profile,
+            )?;
+        }
+        Ok(Some(after))
+    }
+
+    /// Archives or restores an expansion, `None` if there is none with the ID
+    pub fn set_archived(
+        conn: &mut Connection,
+        id: usize,
+        archived: bool,
+        profile: &str,
+    ) -> Result<Option<Expansion>> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let Some(mut expansion) = get(&tx, id)? else {
+            return Ok(None);
+        };
+        if expansion.archived != archived {
+            expansion.archived = archived;
+            tx.execute(
+                "UPDATE expansions SET archived = ?1 WHERE id = ?2",
+                params![archived, id],
+            )?;
+            let change = if archived {
+                ExpansionChange::Archived {
+                    expansion: expansion.clone(),
+                }
+            } else {
+                ExpansionChange::Restored {
+                    expansion: expansion.clone(),
+                }
+            };
+            record(&tx, id, &change, profile)?;
+        }
+        tx.commit()?;
+        Ok(Some(expansion))
+    }
+
+    /// The latest changes to any expansion, newest first
+    pub fn changes(conn: &Connection, limit: usize) -> Result<Vec<ExpansionEvent>> {
+        let rows = conn
+            .prepare(
+                "SELECT id, expansion_id, payload, profile, created_at FROM expansion_events ORDER BY id DESC LIMIT ?1",
+            )?
+            .query_map([limit], |row| {
+                Ok((
+                    row.get::<_, usize>(0)?,
+                    row.get::<_, usize>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        rows.into_iter()
+            .map(|(id, expansion_id, payload, profile, created_at)| {
+                Ok(ExpansionEvent {
+                    id,
+                    expansion_id,
+                    change: serde_json::from_str(&payload)?,
+                    profile,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn draft(name: &str, abbreviation: &str) -> ExpansionDraft {
+            ExpansionDraft {
+                name: name.to_string(),
+                abbreviation: abbreviation.to_string(),
+                cards: 120,
+                secret_cards: 10,
+                ..ExpansionDraft::default()
+            }
+            .check()
+            .unwrap()
+        }
+
+        #[test]
+        fn test_check_trims_and_rejects_incomplete_drafts() {
+            let trimmed = ExpansionDraft {
+                name: "  Mega Evolution ".to_string(),
+                name_de: Some(" Mega-Entwicklung".to_string()),
+                abbreviation: " MEG".to_string(),
+                cards: 132,
+                secret_cards: 56,
+                series: " Mega Evolution".to_string(),
+                release_date: Some("2025-09-26".to_string()),
+                set_code: Some("  ".to_string()),
+                ..ExpansionDraft::default()
+            }
+            .check()
+            .unwrap();
+            assert_eq!(trimmed.name, "Mega Evolution");
+            assert_eq!(trimmed.name_de.as_deref(), Some("Mega-Entwicklung"));
+            assert_eq!(trimmed.abbreviation, "MEG");
+            assert_eq!(trimmed.series, "Mega Evolution");
+            assert_eq!(trimmed.set_code, None);
+            let untranslated = ExpansionDraft {
+                name_de: Some("Mega Evolution".to_string()),
+                ..trimmed.clone()
+            };
+            assert_eq!(untranslated.check().unwrap().name_de, None);
+
+            for release_date in ["26.09.2025", "2025-13-01", "2025-9-26"] {
+                assert!(ExpansionDraft {
+                    release_date: Some(release_date.to_string()),
+                    ..trimmed.clone()
+                }
+                .check()
+                .is_err());
+            }
+            assert!(ExpansionDraft {
+                symbol_url: Some("javascript:alert(1)".to_string()),
+                ..trimmed.clone()
+            }
+            .check()
+            .is_err());
+
+            assert!(ExpansionDraft::default().check().is_err());
+            assert!(ExpansionDraft {
+                cards: 0,
+                ..draft("Mega Evolution", "MEG")
+            }
+            .check()
+            .is_err());
+        }
+
+        #[test]
+        fn test_names_and_abbreviations_are_unique_ignoring_case() {
+            let mut conn = test_connection();
+            let err = create(&mut conn, &draft("base set", "NEW"), "misty").unwrap_err();
+            let duplicate = err.downcast_ref::<DuplicateExpansion>().unwrap();
+            assert_eq!(duplicate.field, "name");
+
+            let err = create(&mut conn, &draft("Brand New", "bs"), "misty").unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<DuplicateExpansion>().unwrap().field,
+                "abbreviation"
+            );
+
+            // Keeping its own name is not a duplicate
+            let created = create(&mut conn, &draft("Brand New", "BNW"), "misty").unwrap();
+            let renamed = update(&mut conn, created.id, &draft("Brand New", "BN"), "misty")
+                .unwrap()
+                .unwrap();
+            assert_eq!(renamed.abbreviation, "BN");
+            assert!(update(&mut conn, 999_999, &draft("Other", "OT"), "misty")
+                .unwrap()
+                .is_none());
+        }
+
+        #[test]
+        fn test_changes_are_recorded() {
+            let mut conn = test_connection();
+            let created = create(&mut conn, &draft("Brand New", "BNW"), "misty").unwrap();
+            update(&mut conn, created.id, &draft("Brand New", "BNW"), "brock").unwrap();
+            update(&mut conn, created.id, &draft("Brand Newer", "BNW"), "brock").unwrap();
+            set_archived(&mut conn, created.id, true, "misty").unwrap();
+            set_archived(&mut conn, created.id, true, "misty").unwrap();
+
+            assert!(get(&conn, created.id).unwrap().unwrap().archived);
+            // The sets seeded from the catalog were recorded before
+            let changes = changes(&conn, 3).unwrap();
+            let kinds: Vec<&str> = changes.iter().map(|e| e.change.kind()).collect();
+            assert_eq!(kinds, vec!["archived", "updated", "created"]);
+            assert_eq!(changes[1].profile, "brock");
+            assert_eq!(
+                changes[1].change.describe(),
+                "Edited Brand Newer (BNW): name Brand New → Brand Newer"
+            );
+        }
+    }
+}
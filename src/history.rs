@@ -0,0 +1,531 @@
+//! Append-only history of collection changes
+//!
+//! Every ownership change and every `card_expansions` insert, update and
+//! delete is recorded in `card_events` inside the same transaction as the
+//! change itself. Events written by one server call share a `batch`, which is
+//! the unit "undo last change" reverts. Undo never deletes history, it applies
+//! the inverse operations and records them as a new batch pointing at the
+//! reverted one.
+//!
+//! The acting profile is sent by the client in the [`PROFILE_HEADER`] header.
+
+use crate::expansion::CardExpansion;
+use serde::{Deserialize, Serialize};
+
+/// Request header carrying the acting profile, base64 encoded UTF-8
+pub const PROFILE_HEADER: &str = "x-fs-chaot-profile";
+
+/// Profile recorded when the client did not send one
+pub const ANONYMOUS_PROFILE: &str = "anonymous";
+
+/// A single recorded change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CardChange {
+    Ownership {
+        before: bool,
+        after: bool,
+    },
+    ExpansionAdded {
+        expansion: CardExpansion,
+    },
+    ExpansionUpdated {
+        before: CardExpansion,
+        after: CardExpansion,
+    },
+    ExpansionRemoved {
+        expansion: CardExpansion,
+    },
+}
+
+impl CardChange {
+    /// Value of the `kind` column
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CardChange::Ownership { .. } => "ownership",
+            CardChange::ExpansionAdded { .. } => "expansion_added",
+            CardChange::ExpansionUpdated { .. } => "expansion_updated",
+            CardChange::ExpansionRemoved { .. } => "expansion_removed",
+        }
+    }
+
+    /// The change that reverts this one
+    pub fn inverse(&self) -> CardChange {
+        match self.clone() {
+            CardChange::Ownership { before, after } => CardChange::Ownership {
+                before: after,
+                after: before,
+            },
+            CardChange::ExpansionAdded { expansion } => CardChange::ExpansionRemoved { expansion },
+            CardChange::ExpansionUpdated { before, after } => CardChange::ExpansionUpdated {
+                before: after,
+                after: before,
+            },
+            CardChange::ExpansionRemoved { expansion } => CardChange::ExpansionAdded { expansion },
+        }
+    }
+}
+
+/// A row of `card_events`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardEvent {
+    pub id: usize,
+    pub batch: usize,
+    pub card_id: usize,
+    pub change: CardChange,
+    pub profile: String,
+    /// Batch this event was written to undo
+    pub reverts: Option<usize>,
+    pub created_at: String,
+}
+
+/// Profile this client acts as, as it is recorded by the server
+pub static PROFILE: dioxus::prelude::GlobalSignal<String> =
+    dioxus::prelude::Signal::global(|| ANONYMOUS_PROFILE.to_string());
+
+/// Sends `profile` with every following server function call
+pub fn set_profile(profile: &str) {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use dioxus::fullstack::{HeaderMap, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+    let profile = profile.trim();
+    *PROFILE.write() = if profile.is_empty() {
+        ANONYMOUS_PROFILE.to_string()
+    } else {
+        profile.to_string()
+    };
+    if !profile.is_empty() {
+        // Header values must be ASCII, names like "Jürgen" are not
+        if let Ok(value) = HeaderValue::from_str(&STANDARD.encode(profile)) {
+            headers.insert(PROFILE_HEADER, value);
+        }
+    }
+    dioxus::fullstack::set_request_headers(headers);
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use anyhow::{anyhow, bail, Result};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use dioxus::fullstack::{FullstackContext, HeaderMap};
+    use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+    /// Undo found the database in a different state than the event recorded
+    #[derive(Debug, thiserror::Error)]
+    #[error("{0}")]
+    pub struct UndoConflict(pub String);
+
+    /// Profile of the client making the current request
+    pub async fn current_profile() -> String {
+        let headers = FullstackContext::extract::<HeaderMap, _>()
+            .await
+            .unwrap_or_default();
+        headers
+            .get(PROFILE_HEADER)
+            .and_then(|value| STANDARD.decode(value.as_bytes()).ok())
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .map(|profile| profile.trim().to_string())
+            .filter(|profile| !profile.is_empty())
+            .unwrap_or_else(|| ANONYMOUS_PROFILE.to_string())
+    }
+
+    /// Writes the events of one batch
+    pub struct Recorder {
+        batch: i64,
+        profile: String,
+        reverts: Option<i64>,
+    }
+
+    impl Recorder {
+        /// Starts a new batch, call inside the transaction making the change
+        pub fn new(conn: &Connection, profile: &str) -> Result<Self> {
+            let batch: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(batch), 0) + 1 FROM card_events",
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(Self {
+                batch,
+                profile: profile.to_string(),
+                reverts: None,
+            })
+        }
+
+        pub fn record(&self, conn: &Connection, card_id: usize, change: &CardChange) -> Result<()> {
+            conn.execute(
+                "INSERT INTO card_events (batch, card_id, kind, payload, profile, reverts) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    self.batch,
+                    card_id,
+                    change.kind(),
+                    serde_json::to_string(change)?,
+                    self.profile,
+                    self.reverts
+                ],
+            )?;
+            Ok(())
+        }
+    }
+
+    /// Events of a card, newest first
+    pub fn timeline(conn: &Connection, card_id: usize) -> Result<Vec<CardEvent>> {
+        let rows = conn
+            .prepare(
+                "SELECT id, batch, card_id, payload, profile, reverts, created_at FROM card_events WHERE card_id = ?1 ORDER BY id DESC",
+            )?
+            .query_map([card_id], |row| {
+                Ok((
+                    row.get::<_, usize>(0)?,
+                    row.get::<_, usize>(1)?,
+                    row.get::<_, usize>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<usize>>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        rows.into_iter()
+            .map(
+                |(id, batch, card_id, payload, profile, reverts, created_at)| {
+                    Ok(CardEvent {
+                        id,
+                        batch,
+                        card_id,
+                        change: serde_json::from_str(&payload)?,
+                        profile,
+                        reverts,
+                        created_at,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Reverts the most recent batch of `card_id` that is neither an undo
+    /// nor undone already
+    ///
+    /// Returns the reverted batch, or `None` when there is nothing to undo.
+    pub fn undo_last(
+        conn: &mut Connection,
+        card_id: usize,
+        profile: &str,
+    ) -> Result<Option<usize>> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let batch: Option<i64> = tx
+            .query_row(
+                "SELECT e.batch FROM card_events e
+                 WHERE e.card_id = ?1 AND e.reverts IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM card_events u WHERE u.reverts = e.batch)
+                 ORDER BY e.id DESC LIMIT 1",
+                [card_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(batch) = batch else {
+            return Ok(None);
+        };
+
+        let events: Vec<(usize, String)> = tx
+            .prepare("SELECT card_id, payload FROM card_events WHERE batch = ?1 ORDER BY id DESC")?
+            .query_map([batch], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+
+        let mut recorder = Recorder::new(&tx, profile)?;
+        recorder.reverts = Some(batch);
+
+        let inverses = events
+            .into_iter()
+            .map(|(event_card_id, payload)| {
+                Ok((
+                    event_card_id,
+                    serde_json::from_str::<CardChange>(&payload)?.inverse(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Like saving, removals go first and prints changing expansion or
+        // number move to a placeholder number, so undoing swapped numbers or a
+        // print taking over a number can't trip the UNIQUE constraint
+        let (removals, others): (Vec<_>, Vec<_>) = inverses
+            .into_iter()
+            .partition(|(_, inverse)| matches!(inverse, CardChange::ExpansionRemoved { .. }));
+        for (_, inverse) in &others {
+            if let CardChange::ExpansionUpdated { before, after } = inverse {
+                if before.expansion_id != after.expansion_id
+                    || before.card_number != after.card_number
+                {
+                    tx.execute(
+                        "UPDATE card_expansions SET card_number = 'moving:' || id
+                         WHERE id = ?1 AND expansion_id = ?2 AND card_number = ?3 AND rarity = ?4",
+                        params![
+                            before.id,
+                            before.expansion_id,
+                            before.card_number,
+                            before.rarity
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        for (event_card_id, inverse) in removals.iter().chain(&others) {
+            apply(&tx, *event_card_id, inverse)?;
+            recorder.record(&tx, *event_card_id, inverse)?;
+        }
+
+        tx.commit()?;
+        tracing::info!(card_id, batch, profile, "card change undone");
+        Ok(Some(batch as usize))
+    }
+
+    /// Applies `change`, failing if the rows are not in its `before` state
+    fn apply(tx: &Transaction<'_>, card_id: usize, change: &CardChange) -> Result<()> {
+        let conflict = |what: &str| {
+            anyhow!(UndoConflict(format!(
+                "Card #{card_id} changed since this event was recorded ({what}), undo it manually"
+            )))
+        };
+
+        match change {
+            CardChange::Ownership { before, after } => {
+                let updated = tx.execute(
+                    "UPDATE cards SET owned = ?1 WHERE id = ?2 AND owned = ?3",
+                    params![after, card_id, before],
+                )?;
+                if updated == 0 {
+                    return Err(conflict("ownership"));
+                }
+            }
+            CardChange::ExpansionAdded { expansion } => {
+                let Some(id) = expansion.id else {
+                    bail!("Recorded expansion has no id");
+                };
+                let taken = tx
+                    .query_row("SELECT 1 FROM card_expansions WHERE id = ?1", [id], |_| {
+                        Ok(())
+                    })
+                    .optional()?
+                    .is_some();
+                if taken {
+                    return Err(conflict("expansion entry already exists"));
+                }
+                tx.execute(
+                    "INSERT INTO card_expansions (id, card_id, expansion_id, card_number, rarity) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        id,
+                        expansion.card_id,
+                        expansion.expansion_id,
+                        expansion.card_number,
+                        expansion.rarity
+                    ],
+                )?;
+            }
+            CardChange::ExpansionUpdated { before, after } => {
+                let updated = tx.execute(
+                    "UPDATE card_expansions SET expansion_id = ?1, card_number = ?2, rarity = ?3
+                     WHERE id = ?4 AND expansion_id = ?5 AND rarity = ?7
+                       AND card_number IN (?6, 'moving:' || id)",
+                    params![
+                        after.expansion_id,
+                        after.card_number,
+                        after.rarity,
+                        before.id,
+                        before.expansion_id,
+                        before.card_number,
+                        before.rarity
+                    ],
+                )?;
+                if updated == 0 {
+                    return Err(conflict("expansion entry was edited"));
+                }
+            }
+            CardChange::ExpansionRemoved { expansion } => {
+                let deleted = tx.execute(
+                    "DELETE FROM card_expansions
+                     WHERE id = ?1 AND expansion_id = ?2 AND card_number = ?3 AND rarity = ?4",
+                    params![
+                        expansion.id,
+                        expansion.expansion_id,
+                        expansion.card_number,
+                        expansion.rarity
+                    ],
+                )?;
+                if deleted == 0 {
+                    return Err(conflict("expansion entry was edited or removed"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a `card_expansions` row for the `before` side of an event
+    pub fn card_expansion(conn: &Connection, id: usize) -> Result<Option<CardExpansion>> {
+        Ok(conn
+            .query_row(
+                "SELECT id, card_id, expansion_id, card_number, rarity FROM card_expansions WHERE id = ?1",
+                [id],
+                |row| {
+                    Ok(CardExpansion {
+                        id: Some(row.get(0)?),
+                        card_id: row.get(1)?,
+                        expansion_id: row.get(2)?,
+                        card_number: row.get(3)?,
+                        rarity: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::card::Rarity;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned)
+                 VALUES (25, json_object('en', 'Pikachu', 'de', 'Pikachu'), 1, 2, 'A', 1, '', 0)",
+                [],
+            )
+            .unwrap();
+            conn
+        }
+
+        fn expansion(id: usize, card_number: &str) -> CardExpansion {
+            CardExpansion {
+                id: Some(id),
+                card_id: 25,
+                expansion_id: 1,
+                card_number: card_number.to_string(),
+                rarity: Rarity::Common,
+            }
+        }
+
+        /// Applies `changes` as one batch, the way the server functions do
+        fn change(conn: &mut Connection, changes: &[CardChange]) {
+            let tx = conn.transaction().unwrap();
+            let recorder = Recorder::new(&tx, "ash").unwrap();
+            for change in changes {
+                apply(&tx, 25, change).unwrap();
+                recorder.record(&tx, 25, change).unwrap();
+            }
+            tx.commit().unwrap();
+        }
+
+        fn owned(conn: &Connection) -> bool {
+            conn.query_row("SELECT owned FROM cards WHERE id = 25", [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        }
+
+        #[test]
+        fn test_inverse_round_trip() {
+            let change = CardChange::ExpansionUpdated {
+                before: expansion(1, "10"),
+                after: expansion(1, "11"),
+            };
+            assert_eq!(change.inverse().inverse(), change);
+        }
+
+        #[test]
+        fn test_undo_reverts_whole_batch() {
+            let mut conn = setup();
+            change(
+                &mut conn,
+                &[
+                    CardChange::Ownership {
+                        before: false,
+                        after: true,
+                    },
+                    CardChange::ExpansionAdded {
+                        expansion: expansion(7, "58"),
+                    },
+                ],
+            );
+            assert!(owned(&conn));
+
+            let undone = undo_last(&mut conn, 25, "misty").unwrap();
+            assert_eq!(undone, Some(1));
+            assert!(!owned(&conn));
+            assert!(card_expansion(&conn, 7).unwrap().is_none());
+
+            let events = timeline(&conn, 25).unwrap();
+            assert_eq!(events.len(), 4);
+            assert_eq!(events[0].reverts, Some(1));
+            assert_eq!(events[0].profile, "misty");
+        }
+
+        #[test]
+        fn test_undo_walks_back_through_batches() {
+            let mut conn = setup();
+            change(
+                &mut conn,
+                &[CardChange::Ownership {
+                    before: false,
+                    after: true,
+                }],
+            );
+            change(
+                &mut conn,
+                &[CardChange::Ownership {
+                    before: true,
+                    after: false,
+                }],
+            );
+
+            assert_eq!(undo_last(&mut conn, 25, "ash").unwrap(), Some(2));
+            assert!(owned(&conn));
+            assert_eq!(undo_last(&mut conn, 25, "ash").unwrap(), Some(1));
+            assert!(!owned(&conn));
+            assert_eq!(undo_last(&mut conn, 25, "ash").unwrap(), None);
+        }
+
+        #[test]
+        fn test_undo_detects_conflicting_edit() {
+            let mut conn = setup();
+            change(
+                &mut conn,
+                &[CardChange::ExpansionAdded {
+                    expansion: expansion(3, "12"),
+                }],
+            );
+            conn.execute(
+                "UPDATE card_expansions SET card_number = '13' WHERE id = 3",
+                [],
+            )
+            .unwrap();
+
+            let err = undo_last(&mut conn, 25, "ash").unwrap_err();
+            assert!(err.downcast_ref::<UndoConflict>().is_some());
+            assert!(card_expansion(&conn, 3).unwrap().is_some());
+        }
+
+        #[test]
+        fn test_history_is_append_only() {
+            let mut conn = setup();
+            change(
+                &mut conn,
+                &[CardChange::Ownership {
+                    before: false,
+                    after: true,
+                }],
+            );
+            assert!(conn.execute("DELETE FROM card_events", []).is_err());
+            assert!(conn
+                .execute("UPDATE card_events SET profile = 'gary'", [])
+                .is_err());
+        }
+    }
+}
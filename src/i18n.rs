@@ -0,0 +1,259 @@
+//! Translated UI text
+//!
+//! Every language has a message catalog under `i18n/`, one `key = text` line
+//! per message, compiled into the binary. [`t`] looks a key up in the
+//! catalog of the current language and falls back to English, so a missing
+//! German message shows the English one instead of the key. Placeholders
+//! like `{count}` are filled in by [`tf`].
+//!
+//! The language is chosen per profile and stored on the server, see
+//! [`set_language`]. Server side everything stays English, log lines and
+//! error messages included.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use strum::{EnumIter, IntoEnumIterator};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter)]
+pub enum Language {
+    #[default]
+    En,
+    De,
+}
+
+impl Language {
+    /// ISO 639-1 code, as stored
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::De => "de",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::iter().find(|language| language.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    fn catalog_source(&self) -> &'static str {
+        match self {
+            Self::En => include_str!("../i18n/en.txt"),
+            Self::De => include_str!("../i18n/de.txt"),
+        }
+    }
+}
+
+/// The language's own name, e.g. "Deutsch"
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::En => f.write_str("English"),
+            Self::De => f.write_str("Deutsch"),
+        }
+    }
+}
+
+/// Reads `key = text` lines, blank lines and lines starting with `#` are skipped
+fn parse_catalog(source: &str) -> Result<HashMap<&str, &str>, String> {
+    let mut messages = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, text)) = line.split_once('=') else {
+            return Err(format!("line {}: expected `key = text`", number + 1));
+        };
+        if messages.insert(key.trim(), text.trim()).is_some() {
+            return Err(format!(
+                "line {}: `{}` is defined twice",
+                number + 1,
+                key.trim()
+            ));
+        }
+    }
+    Ok(messages)
+}
+
+fn catalog(language: Language) -> &'static HashMap<&'static str, &'static str> {
+    static CATALOGS: OnceLock<HashMap<Language, HashMap<&'static str, &'static str>>> =
+        OnceLock::new();
+    let catalogs = CATALOGS.get_or_init(|| {
+        Language::iter()
+            .map(|language| {
+                // The catalogs are checked by the tests, a broken one is a build mistake
+                let messages = parse_catalog(language.catalog_source())
+                    .unwrap_or_else(|e| panic!("i18n/{}.txt: {e}", language.code()));
+                (language, messages)
+            })
+            .collect()
+    });
+    &catalogs[&language]
+}
+
+/// Language of the UI
+///
+/// Components don't subscribe to it, the router is remounted when it
+/// changes. Kept outside a signal so `Display` impls can read it anywhere.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// The current language as a signal, for views that have to rerender on a switch
+pub static LANGUAGE: dioxus::prelude::GlobalSignal<Language> =
+    dioxus::prelude::Signal::global(Language::default);
+
+pub fn language() -> Language {
+    Language::iter()
+        .nth(CURRENT.load(Ordering::Relaxed) as usize)
+        .unwrap_or_default()
+}
+
+/// Switches the UI of this client to `language`
+pub fn set_language(language: Language) {
+    let index = Language::iter()
+        .position(|l| l == language)
+        .unwrap_or_default();
+    CURRENT.store(index as u8, Ordering::Relaxed);
+    *LANGUAGE.write() = language;
+}
+
+/// The message `key` in the current language
+pub fn t(key: &str) -> &str {
+    t_in(language(), key)
+}
+
+/// [`t`] with every `{name}` replaced by the matching argument
+pub fn tf(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    tf_in(language(), key, args)
+}
+
+/// The message `key` in `language`
+pub fn t_in(language: Language, key: &str) -> &str {
+    catalog(language)
+        .get(key)
+        .or_else(|| catalog(Language::En).get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// [`t_in`] with every `{name}` replaced by the matching argument
+pub fn tf_in(language: Language, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(t_in(language, key).to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use anyhow::Result;
+    use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+    use rusqlite::{params, Connection, OptionalExtension, ToSql};
+
+    impl ToSql for Language {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(ToSqlOutput::from(self.code()))
+        }
+    }
+
+    impl FromSql for Language {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            let code = value.as_str()?;
+            Language::from_code(code)
+                .ok_or_else(|| FromSqlError::Other(format!("unknown language `{code}`").into()))
+        }
+    }
+
+    /// Language chosen by `profile`, `None` if it never chose one
+    pub fn stored_language(conn: &Connection, profile: &str) -> Result<Option<Language>> {
+        Ok(conn
+            .query_row(
+                "SELECT language FROM profile_settings WHERE profile = ?1",
+                [profile],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn store_language(conn: &Connection, profile: &str, language: Language) -> Result<()> {
+        conn.execute(
+            "INSERT INTO profile_settings (profile, language) VALUES (?1, ?2)
+             ON CONFLICT (profile) DO UPDATE SET language = excluded.language",
+            params![profile, language],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_catalogs_translate_the_same_messages() {
+        let english = parse_catalog(Language::En.catalog_source()).unwrap();
+        for language in Language::iter() {
+            let messages = parse_catalog(language.catalog_source()).unwrap();
+            let mut missing: Vec<&&str> = english
+                .keys()
+                .filter(|key| !messages.contains_key(*key))
+                .collect();
+            let mut unknown: Vec<&&str> = messages
+                .keys()
+                .filter(|key| !english.contains_key(*key))
+                .collect();
+            missing.sort();
+            unknown.sort();
+            assert!(missing.is_empty(), "{language} lacks {missing:?}");
+            assert!(unknown.is_empty(), "{language} has unknown {unknown:?}");
+            for (key, text) in &messages {
+                assert_eq!(
+                    placeholders(text),
+                    placeholders(english[key]),
+                    "placeholders of {key} in {language}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_catalog() {
+        let messages = parse_catalog("# comment\n\na.b = One = two\n").unwrap();
+        assert_eq!(messages["a.b"], "One = two");
+        assert!(parse_catalog("a = 1\na = 2").is_err());
+        assert!(parse_catalog("no separator").is_err());
+    }
+
+    #[test]
+    fn test_lookup_and_placeholders() {
+        assert_eq!(t("no.such.key"), "no.such.key");
+        assert_eq!(Language::from_code("DE"), Some(Language::De));
+        assert_eq!(Language::from_code("fr"), None);
+
+        assert_eq!(t_in(Language::De, "rarity.common"), "Häufig ●");
+        assert_eq!(t_in(Language::En, "rarity.common"), "Common ●");
+
+        let english = parse_catalog(Language::En.catalog_source()).unwrap();
+        let (key, _) = english
+            .iter()
+            .find(|(_, text)| placeholders(text) == ["count"])
+            .expect("a message with a count");
+        let message = tf(key, &[("count", &3)]);
+        assert!(message.contains('3') && !message.contains("{count}"));
+    }
+}
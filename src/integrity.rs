@@ -0,0 +1,441 @@
+//! Consistency checks of the stored collection
+//!
+//! Rows written by older builds, by hand or by a restore can disagree with
+//! what the app assumes: a card stored at a position the current layout puts
+//! elsewhere, prints of cards or expansions that no longer exist, or values
+//! the UI can't show. [`check`] lists every such [`Violation`], [`repair`]
+//! fixes one [`ViolationKind`] where that can be done without guessing.
+
+use crate::i18n::t;
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, EnumIter,
+)]
+pub enum ViolationKind {
+    /// Book, page, side or entry differ from the binder layout
+    Position,
+    /// Print of a card that is not stored
+    OrphanPrint,
+    /// Print in an expansion that is not stored
+    UnknownExpansion,
+    /// Print of a card that is not marked as owned
+    PrintOnUnownedCard,
+    /// Card number above the expansion's cards plus secret cards, or above
+    /// the size of its subset
+    CardNumberOutOfRange,
+    /// Rarity text that is none of the known rarities
+    UnknownRarity,
+}
+
+impl ViolationKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Position => t("integrity.position"),
+            Self::OrphanPrint => t("integrity.orphan_print"),
+            Self::UnknownExpansion => t("integrity.unknown_expansion"),
+            Self::PrintOnUnownedCard => t("integrity.print_on_unowned_card"),
+            Self::CardNumberOutOfRange => t("integrity.card_number_out_of_range"),
+            Self::UnknownRarity => t("integrity.unknown_rarity"),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Position => t("integrity.position_description"),
+            Self::OrphanPrint => t("integrity.orphan_print_description"),
+            Self::UnknownExpansion => t("integrity.unknown_expansion_description"),
+            Self::PrintOnUnownedCard => t("integrity.print_on_unowned_card_description"),
+            Self::CardNumberOutOfRange => t("integrity.card_number_out_of_range_description"),
+            Self::UnknownRarity => t("integrity.unknown_rarity_description"),
+        }
+    }
+
+    /// True if [`repair`] can fix it without losing anything the user entered
+    pub fn repairable(&self) -> bool {
+        matches!(
+            self,
+            Self::Position | Self::OrphanPrint | Self::UnknownExpansion
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub card_id: usize,
+    /// The `card_expansions` row, `None` for violations of the card itself
+    pub print_id: Option<usize>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub violations: Vec<Violation>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Violations grouped by kind, kinds without violations are left out
+    pub fn by_kind(&self) -> Vec<(ViolationKind, Vec<&Violation>)> {
+        ViolationKind::iter()
+            .map(|kind| {
+                let violations = self.violations.iter().filter(|v| v.kind == kind).collect();
+                (kind, violations)
+            })
+            .filter(|(_, violations): &(_, Vec<_>)| !violations.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::card::{Book, Entry, Index, Page, Rarity, Side};
+    use crate::card_number::NumberScheme;
+    use anyhow::{bail, Result};
+    use rusqlite::{Connection, TransactionBehavior};
+
+    /// Where the binder layout puts a stored card
+    struct Misplaced {
+        card_id: usize,
+        book: Book,
+        page: Page,
+        side: Side,
+        entry: Entry,
+        detail: String,
+    }
+
+    fn misplaced(conn: &Connection) -> Result<Vec<Misplaced>> {
+        let rows: Vec<(usize, i64, i64, String, i64)> = conn
+            .prepare("SELECT id, CAST(book AS INTEGER), CAST(page AS INTEGER), side, CAST(entry AS INTEGER)
+                 FROM cards WHERE id >= 1 ORDER BY id")?
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<_, rusqlite::Error>>()?;
+
+        let mut cards = Vec::new();
+        for (card_id, book, page, side, entry) in rows {
+            let index = Index(card_id);
+            let expected_side = Side::from(&index);
+            let expected = Misplaced {
+                card_id,
+                book: Book::from(&index),
+                page: Page::relative(&index),
+                entry: Entry::new(&index, &Page::absolut(&index), &expected_side),
+                side: expected_side,
+                detail: String::new(),
+            };
+            let stored = (book, page, side, entry);
+            let wanted = (
+                expected.book.0 as i64,
+                expected.page.0 as i64,
+                expected.side.to_string(),
+                expected.entry.0 as i64,
+            );
+            if stored != wanted {
+                cards.push(Misplaced {
+                    detail: format!(
+                        "Stored at book {}, page {}, side {}, entry {}, \
+                         belongs at book {}, page {}, side {}, entry {}",
+                        stored.0,
+                        stored.1,
+                        stored.2,
+                        stored.3,
+                        wanted.0,
+                        wanted.1,
+                        wanted.2,
+                        wanted.3
+                    ),
+                    ..expected
+                });
+            }
+        }
+        Ok(cards)
+    }
+
+    /// Prints matched by `sql`, which selects the print id, card id and detail
+    fn prints(conn: &Connection, kind: ViolationKind, sql: &str) -> Result<Vec<Violation>> {
+        Ok(conn
+            .prepare(sql)?
+            .query_map([], |row| {
+                Ok(Violation {
+                    kind,
+                    print_id: Some(row.get(0)?),
+                    card_id: row.get(1)?,
+                    detail: row.get(2)?,
+                })
+            })?
+            .collect::<Result<_, rusqlite::Error>>()?)
+    }
+
+    /// Every violation in the database, ordered by kind and card
+    pub fn check(conn: &Connection) -> Result<IntegrityReport> {
+        let mut violations: Vec<Violation> = misplaced(conn)?
+            .into_iter()
+            .map(|card| Violation {
+                kind: ViolationKind::Position,
+                card_id: card.card_id,
+                print_id: None,
+                detail: card.detail,
+            })
+            .collect();
+
+        violations.extend(prints(
+            conn,
+            ViolationKind::OrphanPrint,
+            "SELECT ce.id, ce.card_id, 'Card #' || ce.card_id || ' is not stored'
+             FROM card_expansions ce LEFT JOIN cards c ON c.id = ce.card_id
+             WHERE c.id IS NULL ORDER BY ce.card_id, ce.id",
+        )?);
+        violations.extend(prints(
+            conn,
+            ViolationKind::UnknownExpansion,
+            "SELECT ce.id, ce.card_id, 'Expansion #' || ce.expansion_id || ' is not stored'
+             FROM card_expansions ce LEFT JOIN expansions e ON e.id = ce.expansion_id
+             WHERE e.id IS NULL ORDER BY ce.card_id, ce.id",
+        )?);
+        violations.extend(prints(
+            conn,
+            ViolationKind::PrintOnUnownedCard,
+            "SELECT ce.id, ce.card_id, e.abbreviation || ' ' || ce.card_number
+             FROM card_expansions ce
+             JOIN cards c ON c.id = ce.card_id
+             JOIN expansions e ON e.id = ce.expansion_id
+             WHERE c.owned = 0 ORDER BY ce.card_id, ce.id",
+        )?);
+
+        let numbered: Vec<(usize, usize, String, String, usize, usize, NumberScheme)> = conn
+            .prepare(
+                "SELECT ce.id, ce.card_id, ce.card_number, e.abbreviation, e.cards, e.secret_cards,
+                        e.number_scheme
+                 FROM card_expansions ce JOIN expansions e ON e.id = ce.expansion_id
+                 ORDER BY ce.card_id, ce.id",
+            )?
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                ))
+            })?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        // Numbers the scheme can't read at all (placeholders, old free text) have no bound
+        violations.extend(numbered.into_iter().filter_map(
+            |(print_id, card_id, number, abbreviation, cards, secret_cards, numbers)| {
+                numbers.parse(&number).ok()?;
+                let reason = numbers.check(&number, cards, secret_cards).err()?;
+                Some(Violation {
+                    kind: ViolationKind::CardNumberOutOfRange,
+                    card_id,
+                    print_id: Some(print_id),
+                    detail: format!("{abbreviation} {number}: {reason}"),
+                })
+            },
+        ));
+
+        let known: Vec<String> = Rarity::iter().map(|r| r.stored().to_string()).collect();
+        let rarities: Vec<(usize, usize, String)> = conn
+            .prepare("SELECT id, card_id, rarity FROM card_expansions ORDER BY card_id, id")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        violations.extend(
+            rarities
+                .into_iter()
+                .filter(|(_, _, rarity)| !known.contains(rarity))
+                .map(|(print_id, card_id, rarity)| Violation {
+                    kind: ViolationKind::UnknownRarity,
+                    card_id,
+                    print_id: Some(print_id),
+                    detail: format!("\"{rarity}\""),
+                }),
+        );
+
+        Ok(IntegrityReport { violations })
+    }
+
+    /// Fixes every violation of `kind`, returns how many rows changed
+    ///
+    /// Repairs are not recorded in the change history, undoing them would
+    /// bring the violation back.
+    pub fn repair(conn: &mut Connection, kind: ViolationKind) -> Result<usize> {
+        if !kind.repairable() {
+            bail!("{} can't be repaired automatically", kind.title());
+        }
+
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let changed = match kind {
+            ViolationKind::Position => {
+                let cards = misplaced(&tx)?;
+                for card in &cards {
+                    tx.execute(
+                        "UPDATE cards SET book = ?1, page = ?2, side = ?3, entry = ?4 WHERE id = ?5",
+                        rusqlite::params![card.book, card.page, card.side, card.entry, card.card_id],
+                    )?;
+                }
+                cards.len()
+            }
+            ViolationKind::OrphanPrint => tx.execute(
+                "DELETE FROM card_expansions WHERE card_id NOT IN (SELECT id FROM cards)",
+                [],
+            )?,
+            ViolationKind::UnknownExpansion => tx.execute(
+                "DELETE FROM card_expansions WHERE expansion_id NOT IN (SELECT id FROM expansions)",
+                [],
+            )?,
+            _ => unreachable!("checked by repairable"),
+        };
+        tx.commit()?;
+
+        tracing::info!(kind = ?kind, changed, "integrity repair applied");
+        Ok(changed)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            // Databases edited elsewhere or restored may not have enforced them
+            conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+            conn
+        }
+
+        fn insert_card(conn: &Connection, id: usize, owned: bool) {
+            let index = Index(id);
+            let side = Side::from(&index);
+            conn.execute(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES (?1, json_object('en', 'name'), ?2, ?3, ?4, ?5, 'sprite.png', ?6)",
+                rusqlite::params![
+                    id,
+                    Book::from(&index),
+                    Page::relative(&index),
+                    side,
+                    Entry::new(&index, &Page::absolut(&index), &side),
+                    owned
+                ],
+            )
+            .unwrap();
+        }
+
+        fn insert_print(
+            conn: &Connection,
+            card_id: usize,
+            expansion_id: i64,
+            number: &str,
+            rarity: &str,
+        ) {
+            conn.execute(
+                "INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![card_id, expansion_id, number, rarity],
+            )
+            .unwrap();
+        }
+
+        fn base_set(conn: &Connection) -> i64 {
+            conn.query_row(
+                "SELECT id FROM expansions WHERE abbreviation = 'BS'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+        }
+
+        fn kinds(report: &IntegrityReport) -> Vec<ViolationKind> {
+            report.violations.iter().map(|v| v.kind).collect()
+        }
+
+        #[test]
+        fn test_consistent_collection_is_clean() {
+            let conn = setup();
+            let bs = base_set(&conn);
+            insert_card(&conn, 1, true);
+            insert_card(&conn, 25, false);
+            insert_print(&conn, 1, bs, "44", "Common ●");
+            insert_print(&conn, 1, bs, "SVP 1", "Promo");
+
+            assert!(check(&conn).unwrap().is_clean());
+        }
+
+        #[test]
+        fn test_check_lists_each_kind() {
+            let conn = setup();
+            let bs = base_set(&conn);
+            insert_card(&conn, 1, true);
+            insert_card(&conn, 4, false);
+            conn.execute("UPDATE cards SET entry = entry + 1 WHERE id = 1", [])
+                .unwrap();
+            insert_print(&conn, 7, bs, "63", "Common ●");
+            insert_print(&conn, 1, 9999, "1", "Common ●");
+            insert_print(&conn, 4, bs, "46", "Common ●");
+            insert_print(&conn, 1, bs, "103", "Rare ★");
+            insert_print(&conn, 1, bs, "44", "Shiny");
+
+            let report = check(&conn).unwrap();
+            assert_eq!(
+                kinds(&report),
+                vec![
+                    ViolationKind::Position,
+                    ViolationKind::OrphanPrint,
+                    ViolationKind::UnknownExpansion,
+                    ViolationKind::PrintOnUnownedCard,
+                    ViolationKind::CardNumberOutOfRange,
+                    ViolationKind::UnknownRarity,
+                ]
+            );
+            assert_eq!(report.violations[1].card_id, 7);
+            assert_eq!(report.by_kind().len(), 6);
+        }
+
+        #[test]
+        fn test_repair_fixes_safe_kinds_only() {
+            let mut conn = setup();
+            let bs = base_set(&conn);
+            insert_card(&conn, 25, true);
+            conn.execute(
+                "UPDATE cards SET book = 9, page = 9, side = 'B' WHERE id = 25",
+                [],
+            )
+            .unwrap();
+            insert_print(&conn, 25, bs, "58", "Common ●");
+            insert_print(&conn, 26, bs, "14", "Holo Rare ★H");
+            insert_print(&conn, 25, 9999, "1", "Common ●");
+            insert_print(&conn, 25, bs, "999", "Common ●");
+
+            assert_eq!(repair(&mut conn, ViolationKind::Position).unwrap(), 1);
+            assert_eq!(repair(&mut conn, ViolationKind::OrphanPrint).unwrap(), 1);
+            assert_eq!(
+                repair(&mut conn, ViolationKind::UnknownExpansion).unwrap(),
+                1
+            );
+            assert!(repair(&mut conn, ViolationKind::CardNumberOutOfRange).is_err());
+
+            let report = check(&conn).unwrap();
+            assert_eq!(kinds(&report), vec![ViolationKind::CardNumberOutOfRange]);
+            let prints: i64 = conn
+                .query_row("SELECT COUNT(*) FROM card_expansions", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(prints, 2);
+        }
+    }
+}
@@ -1,25 +1,39 @@
-use crate::{card::Card, components::*};
+use crate::components::*;
 use dioxus::prelude::*;
 
 mod backend;
+mod backup;
+mod bulk;
 mod card;
+mod card_number;
+#[cfg(feature = "server")]
+mod catalog;
 mod components;
+mod config;
 mod csv_record;
+#[cfg(feature = "server")]
+mod db;
 mod expansion;
+mod expansion_admin;
+mod history;
+mod i18n;
+mod integrity;
 mod logging;
+#[cfg(feature = "server")]
+mod migrations;
+mod names;
+mod offline;
+mod ownership;
+mod page_view;
+mod paging;
 mod pokeapi;
+mod query;
+mod saved_queries;
+mod search;
 mod statistics;
-mod utils;
-
-pub const BASE_URL: &str = "https://pokeapi.co/api/v2/pokemon/";
-pub const LANGUAGE_URL: &str = "https://raw.githubusercontent.com/PokeAPI/pokeapi/refs/heads/master/data/v2/csv/pokemon_species_names.csv";
-pub const SPRITE_URL: &str =
-    "https://raw.githubusercontent.com/PokeAPI/sprites/master/sprites/pokemon/";
-pub const CARDS_PER_BOOK: usize = 576;
-pub const BOOKS: usize = 2;
-pub const CARDS_PER_DOUBLE_PAGE: usize = 24;
-pub const MAX_POKEMON: usize = 1025;
-pub const TOTAL_PAGES: usize = MAX_POKEMON.div_ceil(CARDS_PER_DOUBLE_PAGE);
+mod store;
+mod sync;
+mod tags;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 static STYLE: Asset = asset!("/assets/style.css");
@@ -34,35 +48,87 @@ pub enum Route {
     #[route("/collection")]
     Collection,
 
+    #[route("/binder/:id")]
+    SavedBinderView { id: usize },
+
     #[route("/statistics")]
     Statistics,
+
+    #[route("/expansions")]
+    Expansions,
+
+    #[route("/admin")]
+    Admin,
 }
 
 fn main() {
+    // Load configuration before anything else so bad values fail fast
+    #[cfg(feature = "server")]
+    let config = match config::init() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load configuration: {e}");
+            std::process::exit(1);
+        }
+    };
+
     // Initialize logging
     #[cfg(feature = "server")]
-    init_server_logging();
+    init_server_logging(&config.log);
+
+    #[cfg(feature = "server")]
+    if std::env::args().any(|arg| arg == "--migrations-dry-run") {
+        print_pending_migrations();
+        return;
+    }
+
+    #[cfg(feature = "server")]
+    {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(position) = args
+            .iter()
+            .position(|arg| arg == "--sync-catalog" || arg == "--catalog-dry-run")
+        {
+            let path = args.get(position + 1).filter(|arg| !arg.starts_with("--"));
+            sync_catalog(path.map(String::as_str), args[position] == "--sync-catalog");
+            return;
+        }
+        if args.iter().any(|arg| arg == "--fetch-names") {
+            fetch_names(&config.pokeapi.language_url);
+            return;
+        }
+        if args.iter().any(|arg| arg == "--fetch-types") {
+            fetch_types(&config.pokeapi.types_url);
+            return;
+        }
+    }
+
+    // Open the pool and migrate before any request can reach the database
+    #[cfg(feature = "server")]
+    if let Err(e) = db::init() {
+        tracing::error!(error = ?e, "failed to initialize database");
+        std::process::exit(1);
+    }
+
+    #[cfg(feature = "server")]
+    backup::start_scheduler();
+
+    #[cfg(feature = "server")]
+    load_missing_types(&config.pokeapi.types_url);
 
     #[cfg(not(feature = "server"))]
-    dioxus::fullstack::set_server_url("https://fs-chaot-production.up.railway.app");
+    dioxus::fullstack::set_server_url(config::SERVER_URL);
 
     dioxus::launch(App);
 }
 
 /// Initialize server-side logging with tracing-subscriber
 #[cfg(feature = "server")]
-fn init_server_logging() {
+fn init_server_logging(log: &config::LogConfig) {
     use tracing_subscriber::{fmt, EnvFilter};
 
-    let filter = if cfg!(feature = "dev") {
-        // Dev: Show debug and above, exclude noisy crates
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-            EnvFilter::new("debug,hyper=info,tower=info,tokio=info,dioxus_core=info")
-        })
-    } else {
-        // Prod: Show info and above by default
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
-    };
+    // RUST_LOG wins over the configured level, the level was validated on load
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&log.level));
 
     fmt()
         .with_env_filter(filter)
@@ -75,15 +141,161 @@ fn init_server_logging() {
     tracing::info!("Logging initialized");
 }
 
-pub static CARDS: GlobalSignal<Vec<(usize, Card)>> = Signal::global(Vec::new);
+/// Prints the migrations that would run on the next start and exits
+#[cfg(feature = "server")]
+fn print_pending_migrations() {
+    match db::pending_migrations() {
+        Ok(pending) if pending.is_empty() => println!("No pending migrations"),
+        Ok(pending) => {
+            println!("{} pending migration(s):", pending.len());
+            for migration in pending {
+                println!("  {} ({})", migration.label(), &migration.checksum[..12]);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to list pending migrations: {e:?}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints what the expansion catalog at `path` changes and applies it unless
+/// `apply` is false, then exits
+///
+/// Without a path the catalog compiled into the binary is used.
+#[cfg(feature = "server")]
+fn sync_catalog(path: Option<&str>, apply: bool) {
+    let run = || -> anyhow::Result<()> {
+        let content = match path {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {path}: {e}"))?,
+            None => catalog::EMBEDDED.to_string(),
+        };
+        let catalog = catalog::parse(&content)?;
+        if !apply {
+            print!("{}", db::catalog_diff(&catalog)?);
+            return Ok(());
+        }
+
+        db::init()?;
+        let mut conn = db::get()?;
+        let diff = catalog::diff(&conn, &catalog)?;
+        print!("{diff}");
+        if diff.is_empty() {
+            println!("Nothing to apply");
+        } else {
+            println!("Applied {} change(s)", catalog::apply(&mut conn, &diff)?);
+        }
+        Ok(())
+    };
+    if let Err(e) = run() {
+        eprintln!("Failed to sync the expansion catalog: {e:?}");
+        std::process::exit(1);
+    }
+}
+
+/// Adds the Pokemon names in every language to cards stored with only
+/// English and German names, then exits
+#[cfg(feature = "server")]
+fn fetch_names(language_url: &str) {
+    let run = || -> anyhow::Result<()> {
+        db::init()?;
+        let csv = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(pokeapi::PokeApi::species_csv(language_url))?;
+        let mut conn = db::get()?;
+        let changed = pokeapi::PokeApi::fill_missing_names(&mut conn, &csv)?;
+        println!("Added names to {changed} card(s)");
+        Ok(())
+    };
+    if let Err(e) = run() {
+        eprintln!("Failed to fetch Pokemon names: {e:?}");
+        std::process::exit(1);
+    }
+}
+
+/// Replaces the stored Pokemon types with the ones PokeAPI lists, returns
+/// how many Pokemon have types
+#[cfg(feature = "server")]
+fn store_types(types_url: &str) -> anyhow::Result<usize> {
+    let csv = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(pokeapi::PokeApi::types_csv(types_url))?;
+    let mut conn = db::get()?;
+    pokeapi::PokeApi::store_types(&mut conn, &csv)
+}
+
+/// Refreshes the stored Pokemon types, then exits
+#[cfg(feature = "server")]
+fn fetch_types(types_url: &str) {
+    let run = || -> anyhow::Result<()> {
+        db::init()?;
+        let pokemon = store_types(types_url)?;
+        println!("Stored the types of {pokemon} Pokemon");
+        Ok(())
+    };
+    if let Err(e) = run() {
+        eprintln!("Failed to fetch Pokemon types: {e:?}");
+        std::process::exit(1);
+    }
+}
+
+/// Fetches the Pokemon types in the background while none are stored, so
+/// `type:` filters work on a fresh database without `--fetch-types`
+#[cfg(feature = "server")]
+fn load_missing_types(types_url: &'static str) {
+    match db::get().and_then(|conn| pokeapi::PokeApi::types_stored(&conn)) {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => {
+            tracing::error!(error = ?e, "failed to check stored Pokemon types");
+            return;
+        }
+    }
+
+    let spawned = std::thread::Builder::new()
+        .name("types-loader".to_string())
+        .spawn(move || match store_types(types_url) {
+            Ok(pokemon) => tracing::info!(pokemon, "stored Pokemon types from PokeAPI"),
+            Err(e) => tracing::warn!(
+                error = ?e,
+                "failed to fetch Pokemon types, `type:` filters fail until the next start"
+            ),
+        });
+    if let Err(e) = spawned {
+        tracing::error!(error = %e, "failed to start Pokemon types loader");
+    }
+}
+
 pub static IS_AUTHENTICATED: GlobalSignal<bool> = Signal::global(|| false);
+/// Bumped after ownership or saved search changes, views showing counts reload on it,
+/// see [`store`]
+pub static COLLECTION_REVISION: GlobalSignal<u64> = Signal::global(|| 0);
 
 #[component]
 fn App() -> Element {
+    // Binder layout and name languages come from the server config, install them before
+    // rendering pages, nothing may read the config until then
+    let mut config_loaded = use_signal(|| false);
+    use_effect(move || {
+        spawn(async move {
+            store::load_client_config().await;
+            config_loaded.set(true);
+        });
+    });
+    // Text is looked up while rendering, remount everything on a language switch
+    let language = i18n::LANGUAGE().code();
+
     rsx! {
         document::Stylesheet { href: STYLE }
         document::Stylesheet { href: THEME }
         document::Link { rel: "icon", href: FAVICON }
-        Router::<Route> {}
+        if config_loaded() {
+            Router::<Route> { key: "{language}" }
+        } else {
+            div { class: "app-loading", {i18n::t("common.loading")} }
+        }
     }
 }
@@ -0,0 +1,404 @@
+//! Versioned schema migrations
+//!
+//! Migrations are plain SQL files in `migrations/` named `NNNN_description.sql`.
+//! The directory is embedded at compile time, applied in version order and
+//! each file runs inside its own transaction. Every applied migration is
+//! recorded in the `migrations` table together with a SHA-256 checksum of its
+//! contents, so editing a migration after it shipped is detected at startup.
+
+use anyhow::{anyhow, bail, Context, Result};
+use include_dir::{include_dir, Dir};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+static MIGRATIONS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/migrations");
+
+/// A migration file embedded in the binary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub version: u32,
+    pub name: String,
+    pub sql: &'static str,
+    pub checksum: String,
+}
+
+impl Migration {
+    /// Parses `NNNN_description.sql` into a migration
+    fn from_file(file_name: &str, sql: &'static str) -> Result<Self> {
+        let stem = file_name
+            .strip_suffix(".sql")
+            .ok_or_else(|| anyhow!("Migration file `{}` must end in .sql", file_name))?;
+        let (version, name) = stem.split_once('_').ok_or_else(|| {
+            anyhow!(
+                "Migration file `{}` must be named NNNN_description.sql",
+                file_name
+            )
+        })?;
+        let version = version
+            .parse::<u32>()
+            .with_context(|| format!("Migration file `{}` has no numeric version", file_name))?;
+
+        Ok(Self {
+            version,
+            name: name.to_string(),
+            sql,
+            checksum: checksum(sql),
+        })
+    }
+
+    /// File name this migration was loaded from, used in logs and errors
+    pub fn label(&self) -> String {
+        format!("{:04}_{}", self.version, self.name)
+    }
+}
+
+/// A row from the `migrations` table
+///
+/// Rows written before versioned migrations existed only carry a name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedMigration {
+    pub name: String,
+    pub version: Option<u32>,
+    pub checksum: Option<String>,
+}
+
+/// What has to happen to bring a database up to date
+#[derive(Debug, Default, PartialEq)]
+pub struct MigrationPlan {
+    /// Migrations recorded by name only, which get their version and checksum backfilled
+    pub adopt: Vec<Migration>,
+    /// Migrations that have not been applied yet, in version order
+    pub pending: Vec<Migration>,
+}
+
+/// SHA-256 of the migration contents, with line endings normalised so a
+/// checkout with CRLF line endings doesn't count as an edit
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha256::digest(sql.replace("\r\n", "\n").as_bytes()))
+}
+
+/// Loads all embedded migrations sorted by version
+pub fn embedded() -> Result<Vec<Migration>> {
+    let mut migrations = MIGRATIONS_DIR
+        .files()
+        .filter(|file| file.path().extension().is_some_and(|ext| ext == "sql"))
+        .map(|file| {
+            let file_name = file
+                .path()
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| anyhow!("Invalid migration path {}", file.path().display()))?;
+            let sql = file
+                .contents_utf8()
+                .ok_or_else(|| anyhow!("Migration `{}` is not valid UTF-8", file_name))?;
+            Migration::from_file(file_name, sql)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    migrations.sort_by_key(|m| m.version);
+
+    for pair in migrations.windows(2) {
+        if pair[0].version == pair[1].version {
+            bail!(
+                "Duplicate migration version {:04}: `{}` and `{}`",
+                pair[0].version,
+                pair[0].label(),
+                pair[1].label()
+            );
+        }
+    }
+
+    Ok(migrations)
+}
+
+/// Compares embedded migrations against the applied ones
+///
+/// Fails if an applied migration's checksum no longer matches its file.
+pub fn plan(migrations: &[Migration], applied: &[AppliedMigration]) -> Result<MigrationPlan> {
+    let applied_by_name: HashMap<&str, &AppliedMigration> =
+        applied.iter().map(|a| (a.name.as_str(), a)).collect();
+
+    let mut plan = MigrationPlan::default();
+
+    for migration in migrations {
+        match applied_by_name.get(migration.name.as_str()) {
+            Some(AppliedMigration {
+                checksum: Some(recorded),
+                ..
+            }) => {
+                if *recorded != migration.checksum {
+                    bail!(
+                        "Migration `{}` was modified after it was applied (recorded checksum {}, file checksum {}). \
+                         Add a new migration instead of editing an applied one.",
+                        migration.label(),
+                        recorded,
+                        migration.checksum
+                    );
+                }
+            }
+            Some(_) => plan.adopt.push(migration.clone()),
+            None => plan.pending.push(migration.clone()),
+        }
+    }
+
+    for applied in applied {
+        if !migrations.iter().any(|m| m.name == applied.name) {
+            tracing::warn!(
+                migration = %applied.name,
+                version = ?applied.version,
+                "applied migration has no matching file in this build"
+            );
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Reads the `migrations` table without modifying the database
+///
+/// Works on databases that predate the `version` and `checksum` columns and
+/// on databases without a `migrations` table at all.
+pub fn applied(conn: &Connection) -> Result<Vec<AppliedMigration>> {
+    let table_exists = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'migrations'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    let sql = if has_column(conn, "migrations", "checksum")? {
+        "SELECT name, version, checksum FROM migrations ORDER BY id"
+    } else {
+        "SELECT name, NULL, NULL FROM migrations ORDER BY id"
+    };
+
+    let rows = conn
+        .prepare(sql)?
+        .query_map([], |row| {
+            Ok(AppliedMigration {
+                name: row.get(0)?,
+                version: row.get(1)?,
+                checksum: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+    Ok(rows)
+}
+
+/// Lists migrations that [`run`] would apply, without touching the database
+pub fn pending(conn: &Connection) -> Result<Vec<Migration>> {
+    let plan = plan(&embedded()?, &applied(conn)?)?;
+    Ok(plan.pending)
+}
+
+/// Brings the database schema up to date
+pub fn run(conn: &mut Connection) -> Result<()> {
+    ensure_migrations_table(conn)?;
+
+    let migrations = embedded()?;
+    let plan = plan(&migrations, &applied(conn)?)?;
+
+    for migration in &plan.adopt {
+        tracing::info!(
+            migration = %migration.label(),
+            "recording checksum for previously applied migration"
+        );
+        conn.execute(
+            "UPDATE migrations SET version = ?1, checksum = ?2 WHERE name = ?3",
+            params![migration.version, migration.checksum, migration.name],
+        )?;
+    }
+
+    if plan.pending.is_empty() {
+        tracing::debug!(count = migrations.len(), "schema up to date");
+        return Ok(());
+    }
+
+    for migration in &plan.pending {
+        tracing::info!(migration = %migration.label(), "Running migration");
+
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql)
+            .with_context(|| format!("Migration `{}` failed", migration.label()))?;
+        tx.execute(
+            "INSERT INTO migrations (name, version, checksum) VALUES (?1, ?2, ?3)",
+            params![migration.name, migration.version, migration.checksum],
+        )?;
+        tx.commit()?;
+
+        tracing::info!(migration = %migration.label(), "Migration completed successfully");
+    }
+
+    Ok(())
+}
+
+/// Creates the bookkeeping table and adds the columns introduced with
+/// versioned migrations to tables created by older builds
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS migrations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            applied_at DATETIME DEFAULT (datetime('now', 'localtime'))
+        );",
+    )?;
+
+    if !has_column(conn, "migrations", "version")? {
+        conn.execute_batch("ALTER TABLE migrations ADD COLUMN version INTEGER;")?;
+    }
+    if !has_column(conn, "migrations", "checksum")? {
+        conn.execute_batch("ALTER TABLE migrations ADD COLUMN checksum TEXT;")?;
+    }
+
+    Ok(())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    Ok(names.iter().any(|name| name == column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migration(version: u32, name: &str, sql: &'static str) -> Migration {
+        Migration {
+            version,
+            name: name.to_string(),
+            sql,
+            checksum: checksum(sql),
+        }
+    }
+
+    #[test]
+    fn test_parse_migration_file_name() {
+        let m = Migration::from_file("0002_add_card_number.sql", "SELECT 1;").unwrap();
+        assert_eq!(m.version, 2);
+        assert_eq!(m.name, "add_card_number");
+        assert_eq!(m.label(), "0002_add_card_number");
+    }
+
+    #[test]
+    fn test_parse_migration_file_name_rejects_bad_names() {
+        assert!(Migration::from_file("add_card_number.sql", "").is_err());
+        assert!(Migration::from_file("0002_add_card_number.txt", "").is_err());
+        assert!(Migration::from_file("0002.sql", "").is_err());
+    }
+
+    #[test]
+    fn test_checksum_ignores_line_endings() {
+        assert_eq!(
+            checksum("SELECT 1;\nSELECT 2;"),
+            checksum("SELECT 1;\r\nSELECT 2;")
+        );
+        assert_ne!(checksum("SELECT 1;"), checksum("SELECT 2;"));
+    }
+
+    #[test]
+    fn test_embedded_migrations_are_ordered() {
+        let migrations = embedded().unwrap();
+        assert!(!migrations.is_empty());
+        assert!(migrations.windows(2).all(|w| w[0].version < w[1].version));
+    }
+
+    #[test]
+    fn test_plan_pending_and_adopted() {
+        let migrations = vec![
+            migration(1, "initial_schema", "CREATE TABLE a (id INTEGER);"),
+            migration(2, "legacy", "CREATE TABLE b (id INTEGER);"),
+            migration(3, "new", "CREATE TABLE c (id INTEGER);"),
+        ];
+        let applied = vec![
+            AppliedMigration {
+                name: "initial_schema".to_string(),
+                version: Some(1),
+                checksum: Some(migrations[0].checksum.clone()),
+            },
+            AppliedMigration {
+                name: "legacy".to_string(),
+                version: None,
+                checksum: None,
+            },
+        ];
+
+        let plan = plan(&migrations, &applied).unwrap();
+        assert_eq!(plan.adopt, vec![migrations[1].clone()]);
+        assert_eq!(plan.pending, vec![migrations[2].clone()]);
+    }
+
+    #[test]
+    fn test_plan_rejects_edited_migration() {
+        let migrations = vec![migration(
+            1,
+            "initial_schema",
+            "CREATE TABLE a (id INTEGER);",
+        )];
+        let applied = vec![AppliedMigration {
+            name: "initial_schema".to_string(),
+            version: Some(1),
+            checksum: Some(checksum("CREATE TABLE a (id TEXT);")),
+        }];
+
+        let err = plan(&migrations, &applied).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("was modified after it was applied"));
+    }
+
+    #[test]
+    fn test_run_on_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(pending(&conn).unwrap().len(), embedded().unwrap().len());
+
+        run(&mut conn).unwrap();
+        assert!(pending(&conn).unwrap().is_empty());
+
+        // Running again is a no-op
+        run(&mut conn).unwrap();
+        let applied = applied(&conn).unwrap();
+        assert_eq!(applied.len(), embedded().unwrap().len());
+        assert!(applied.iter().all(|a| a.checksum.is_some()));
+    }
+
+    #[test]
+    fn test_run_adopts_legacy_migrations_table() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE migrations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                applied_at DATETIME DEFAULT (datetime('now', 'localtime'))
+            );
+            INSERT INTO migrations (name) VALUES ('add_card_number_to_unique_constraint');",
+        )
+        .unwrap();
+
+        let pending_names: Vec<String> = pending(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert!(!pending_names.contains(&"add_card_number_to_unique_constraint".to_string()));
+
+        run(&mut conn).unwrap();
+        let legacy = applied(&conn)
+            .unwrap()
+            .into_iter()
+            .find(|a| a.name == "add_card_number_to_unique_constraint")
+            .unwrap();
+        assert_eq!(legacy.version, Some(2));
+        assert!(legacy.checksum.is_some());
+    }
+}
@@ -0,0 +1,210 @@
+//! Forgiving comparison of Pokemon names
+//!
+//! Names are spelled differently by PokeAPI slugs, the species CSV and the
+//! people typing them: "mr-mime", "Mr. Mime" and "mr mime", or "Farfetch’d"
+//! and "farfetchd". [`normalize`] folds all of these to one form, exact
+//! matches compare normalized names and [`suggest`] ranks near misses by
+//! edit distance for "did you mean" hints. Japanese names are stored in
+//! katakana, hiragana input is folded to katakana so either spelling matches.
+
+use dioxus::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+
+/// Suggestions offered when a name matches nothing exactly
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// Lowercases, folds diacritics, kana and gender signs and drops punctuation
+///
+/// Apostrophes vanish ("farfetch’d" → "farfetchd"), other punctuation
+/// separates words ("type: null" → "type null"), ♀ and ♂ become the
+/// trailing "f" and "m" PokeAPI uses ("nidoran♀" → "nidoran f").
+pub fn normalize(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase).map(katakana) {
+        match c {
+            '\'' | '’' | '‘' | '`' | '´' | 'ʼ' => {}
+            '♀' => folded.push_str(" f "),
+            '♂' => folded.push_str(" m "),
+            c if c.is_alphanumeric() => match fold_diacritic(c) {
+                Some(base) => folded.push_str(base),
+                None => folded.push(c),
+            },
+            _ => folded.push(' '),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fold_diacritic(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' => "a",
+        'æ' => "ae",
+        'ç' | 'č' => "c",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+        'ñ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' => "o",
+        'œ' => "oe",
+        'ß' => "ss",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' => "u",
+        'ý' | 'ÿ' => "y",
+        _ => return None,
+    })
+}
+
+/// The katakana for a hiragana character, other characters are returned as is
+pub fn katakana(c: char) -> char {
+    match c {
+        // The two blocks share their layout, katakana start 0x60 later
+        '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+        c => c,
+    }
+}
+
+/// The PokeAPI path segment for a name, e.g. "Mr. Mime" → "mr-mime"
+pub fn api_slug(name: &str) -> String {
+    normalize(name).replace(' ', "-")
+}
+
+/// Whether two names are the same after [`normalize`]
+pub fn same_name(a: &str, b: &str) -> bool {
+    normalize(a) == normalize(b)
+}
+
+/// Levenshtein distance in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Candidates close to `query`, nearest first
+///
+/// A candidate qualifies within one edit per three characters of the query,
+/// at least one. Each name is returned once, as first spelled in `candidates`.
+pub fn suggest<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let max_distance = (query.chars().count() / 3).max(1);
+
+    let mut ranked: Vec<(usize, String, &str)> = Vec::new();
+    for candidate in candidates {
+        let normalized = normalize(candidate);
+        if ranked.iter().any(|(_, seen, _)| *seen == normalized) {
+            continue;
+        }
+        let distance = edit_distance(&query, &normalized);
+        if distance <= max_distance {
+            ranked.push((distance, normalized, candidate));
+        }
+    }
+    ranked.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, _, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// No Pokemon has the name, possibly with spellings that come close
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, thiserror::Error)]
+#[error("No Pokémon named `{query}`")]
+pub struct NameNotFound {
+    pub query: String,
+    pub suggestions: Vec<String>,
+}
+
+impl NameNotFound {
+    /// HTTP status used for unknown names
+    pub const STATUS: u16 = 404;
+
+    /// Extracts the suggestions from a failed lookup, empty for other errors
+    pub fn suggestions(error: &ServerFnError) -> Vec<String> {
+        match error {
+            ServerFnError::ServerError {
+                code: Self::STATUS,
+                details: Some(details),
+                ..
+            } => serde_json::from_value::<NameNotFound>(details.clone())
+                .map(|not_found| not_found.suggestions)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The server error sent for this lookup
+    pub fn into_error(self) -> ServerFnError {
+        ServerFnError::ServerError {
+            message: self.to_string(),
+            code: Self::STATUS,
+            details: serde_json::to_value(&self).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_spelling_variants() {
+        assert_eq!(normalize("Mr. Mime"), "mr mime");
+        assert_eq!(normalize("mr-mime"), "mr mime");
+        assert_eq!(normalize("Farfetch’d"), "farfetchd");
+        assert_eq!(normalize("Farfetch'd"), "farfetchd");
+        assert_eq!(normalize("Nidoran♀"), "nidoran f");
+        assert_eq!(normalize("nidoran-m"), "nidoran m");
+        assert_eq!(normalize("Flabébé"), "flabebe");
+        assert_eq!(normalize("Type: Null"), "type null");
+        assert_eq!(normalize("  Tapu   Koko "), "tapu koko");
+        assert_eq!(normalize("ぴかちゅう"), "ピカチュウ");
+        assert_eq!(normalize("ピカチュウ"), "ピカチュウ");
+    }
+
+    #[test]
+    fn test_api_slug() {
+        assert_eq!(api_slug("Mr. Mime"), "mr-mime");
+        assert_eq!(api_slug("Nidoran f"), "nidoran-f");
+        assert_eq!(api_slug("Flabébé"), "flabebe");
+        assert_eq!(api_slug("Sirfetch’d"), "sirfetchd");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("pikachu", "pikachu"), 0);
+        assert_eq!(edit_distance("pikchu", "pikachu"), 1);
+        assert_eq!(edit_distance("glurak", "glumanda"), 4);
+    }
+
+    #[test]
+    fn test_suggest_ranks_by_distance() {
+        let names = ["Pikachu", "Raichu", "pikachu", "Pichu", "Bisasam"];
+        assert_eq!(suggest("pikchu", names), vec!["Pichu", "Pikachu"]);
+        assert_eq!(suggest("Raichuu", names), vec!["Raichu"]);
+        assert!(suggest("zzz", names).is_empty());
+        assert!(suggest("", names).is_empty());
+    }
+
+    #[test]
+    fn test_not_found_round_trip_through_server_error() {
+        let error = NameNotFound {
+            query: "pikchu".to_string(),
+            suggestions: vec!["Pikachu".to_string()],
+        }
+        .into_error();
+        assert_eq!(NameNotFound::suggestions(&error), vec!["Pikachu"]);
+    }
+}
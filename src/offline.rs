@@ -0,0 +1,470 @@
+//! Working without a connection
+//!
+//! The client keeps a [`Snapshot`] of the collection in local storage and
+//! queues ownership edits it could not send in a [`MutationQueue`]. Every
+//! queued edit remembers the state it started from, so once the server is
+//! reachable again an edit that conflicts with changes made elsewhere in the
+//! meantime is merged with [`EditConflict::merge`] and sent again. The queue
+//! is plain data driven by [`MutationQueue::settle`], the client only feeds
+//! it the results of [`crate::backend::save_card_ownership_db`].
+
+use crate::expansion::{CardExpansion, Expansion};
+use crate::ownership::{EditConflict, EditProblem, OwnershipEdit, SavedOwnership};
+use crate::page_view::PocketSummary;
+use dioxus::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+
+/// Sends of one edit before it is given up, every conflict costs one
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// The collection as last seen online
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Summaries of the stored cards, pockets without a card are left out
+    pub pockets: Vec<PocketSummary>,
+    pub prints: Vec<CardExpansion>,
+    pub expansions: Vec<Expansion>,
+}
+
+/// How the pocket of an edited card looks once the edit is saved
+pub fn summary(edit: &OwnershipEdit) -> PocketSummary {
+    PocketSummary {
+        index: edit.card.index.0,
+        card: Some(edit.card.clone()),
+        highest_rarity: edit.expansions.iter().map(|e| e.rarity.clone()).max(),
+        prints: edit.expansions.len(),
+    }
+}
+
+/// True if the server could not be reached or failed on its side
+///
+/// Edits that fail like this stay queued, anything the server answered
+/// itself is final.
+pub fn is_unreachable(error: &ServerFnError) -> bool {
+    !matches!(error, ServerFnError::ServerError { code, .. } if *code < 500)
+}
+
+/// An edit waiting to be sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedEdit {
+    pub id: u64,
+    pub edit: OwnershipEdit,
+    /// The card and prints as the server last confirmed them
+    pub base: SavedOwnership,
+    /// Conflicts merged so far
+    pub attempts: u32,
+}
+
+/// What sending the front of the queue came to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Replayed {
+    /// Saved, or the stored state already matched the edit
+    Saved(SavedOwnership),
+    /// Conflicted and was merged with the stored state, send it again
+    Merged,
+    /// The server refused the edit, it was removed from the queue
+    Dropped { card: usize, reason: String },
+    /// Still offline, the edit stays queued
+    Unreachable,
+    /// A newer edit of the card was queued while this one was sent
+    Superseded,
+}
+
+/// Ownership edits made offline, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MutationQueue {
+    next_id: u64,
+    edits: Vec<QueuedEdit>,
+}
+
+impl MutationQueue {
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn edits(&self) -> &[QueuedEdit] {
+        &self.edits
+    }
+
+    /// The edit to send next
+    pub fn front(&self) -> Option<&QueuedEdit> {
+        self.edits.first()
+    }
+
+    /// Queues `edit`, replacing a queued edit of the same card
+    ///
+    /// Edits carry the complete state of a card, so only the latest one is
+    /// sent. It keeps the base of the replaced edit, that is the last state
+    /// the server confirmed.
+    pub fn push(&mut self, edit: OwnershipEdit, base: SavedOwnership) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let card = edit.card.index.0;
+        match self.edits.iter_mut().find(|q| q.edit.card.index.0 == card) {
+            Some(queued) => {
+                queued.id = id;
+                queued.edit = edit;
+            }
+            None => self.edits.push(QueuedEdit {
+                id,
+                edit,
+                base,
+                attempts: 0,
+            }),
+        }
+        id
+    }
+
+    /// Records the server's answer to sending edit `id`
+    pub fn settle(&mut self, id: u64, result: Result<SavedOwnership, ServerFnError>) -> Replayed {
+        let Some(position) = self.edits.iter().position(|q| q.id == id) else {
+            return Replayed::Superseded;
+        };
+        let error = match result {
+            Ok(saved) => {
+                self.edits.remove(position);
+                return Replayed::Saved(saved);
+            }
+            Err(error) if is_unreachable(&error) => return Replayed::Unreachable,
+            Err(error) => error,
+        };
+
+        let card = self.edits[position].edit.card.index.0;
+        if let Some(conflict) = EditConflict::from_error(&error) {
+            let queued = &mut self.edits[position];
+            let merged = conflict.merge(&queued.base, &queued.edit);
+            if merged.card.owned == conflict.current.card.owned
+                && merged.expansions == conflict.current.expansions
+            {
+                self.edits.remove(position);
+                return Replayed::Saved(conflict.current);
+            }
+            if queued.attempts + 1 >= MAX_ATTEMPTS {
+                self.edits.remove(position);
+                return Replayed::Dropped {
+                    card,
+                    reason: format!("still conflicting after {MAX_ATTEMPTS} attempts"),
+                };
+            }
+            queued.attempts += 1;
+            queued.edit = merged;
+            queued.base = conflict.current;
+            return Replayed::Merged;
+        }
+
+        self.edits.remove(position);
+        let problems = EditProblem::from_error(&error);
+        let reason = if problems.is_empty() {
+            error.to_string()
+        } else {
+            problems
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+        Replayed::Dropped { card, reason }
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::{config, expansion_admin, page_view};
+    use anyhow::Result;
+    use rusqlite::Connection;
+
+    /// Everything the client needs to show the collection offline
+    pub fn snapshot(conn: &Connection) -> Result<Snapshot> {
+        let pockets = page_view::summaries(conn, 1, config::layout().max_pokemon)?
+            .into_iter()
+            .filter(|pocket| pocket.card.is_some())
+            .collect();
+        let prints = conn
+            .prepare(
+                "SELECT id, card_id, expansion_id, card_number, rarity FROM card_expansions ORDER BY card_id, id",
+            )?
+            .query_map([], |row| {
+                Ok(CardExpansion {
+                    id: Some(row.get(0)?),
+                    card_id: row.get(1)?,
+                    expansion_id: row.get(2)?,
+                    card_number: row.get(3)?,
+                    rarity: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        let expansions = expansion_admin::list(conn)?;
+
+        Ok(Snapshot {
+            pockets,
+            prints,
+            expansions,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::card::{Bool, Card, Index, Name, NameLanguage, Names, Rarity};
+        use crate::ownership::{constraint_problem, save, SaveOutcome};
+
+        fn entry(id: Option<usize>, card_number: &str) -> CardExpansion {
+            CardExpansion {
+                id,
+                card_id: 25,
+                expansion_id: 1,
+                card_number: card_number.to_string(),
+                rarity: Rarity::Common,
+            }
+        }
+
+        /// What the server function answers for `edit`
+        fn send(
+            conn: &mut Connection,
+            edit: OwnershipEdit,
+        ) -> Result<SavedOwnership, ServerFnError> {
+            match save(conn, edit, "ash") {
+                Ok(SaveOutcome::Saved(saved)) => Ok(saved),
+                Ok(SaveOutcome::Rejected(problems)) => Err(EditProblem::into_error(&problems)),
+                Ok(SaveOutcome::Conflict(conflict)) => Err(conflict.into_error()),
+                Err(err) => match constraint_problem(&err) {
+                    Some(problem) => Err(EditProblem::into_error(&[problem])),
+                    None => panic!("save failed: {err}"),
+                },
+            }
+        }
+
+        /// Saves the card online, as the base of later offline edits
+        fn stored(conn: &mut Connection) -> SavedOwnership {
+            send(
+                conn,
+                OwnershipEdit {
+                    card: Card {
+                        index: Index(25),
+                        owned: Bool(true),
+                        ..Card::default()
+                    },
+                    expansions: vec![entry(None, "58")],
+                },
+            )
+            .unwrap()
+        }
+
+        fn replay(conn: &mut Connection, queue: &mut MutationQueue) -> Vec<Replayed> {
+            let mut outcomes = Vec::new();
+            while let Some(queued) = queue.front().cloned() {
+                let result = send(conn, queued.edit);
+                let replayed = queue.settle(queued.id, result);
+                if replayed == Replayed::Unreachable {
+                    break;
+                }
+                outcomes.push(replayed);
+            }
+            outcomes
+        }
+
+        #[test]
+        fn test_push_keeps_first_base() {
+            let mut conn = test_connection();
+            let base = stored(&mut conn);
+            let mut queue = MutationQueue::default();
+
+            let mut first = OwnershipEdit {
+                card: base.card.clone(),
+                expansions: base.expansions.clone(),
+            };
+            first.expansions.push(entry(None, "59"));
+            queue.push(first.clone(), base.clone());
+            let mut second = first.clone();
+            second.expansions.push(entry(None, "60"));
+            let other = Card {
+                index: Index(26),
+                ..Card::default()
+            };
+            queue.push(
+                second.clone(),
+                SavedOwnership {
+                    card: base.card.clone(),
+                    expansions: first.expansions.clone(),
+                },
+            );
+            queue.push(
+                OwnershipEdit {
+                    card: other.clone(),
+                    expansions: vec![],
+                },
+                SavedOwnership {
+                    card: other,
+                    expansions: vec![],
+                },
+            );
+
+            assert_eq!(queue.len(), 2);
+            assert_eq!(queue.edits()[0].edit, second);
+            assert_eq!(queue.edits()[0].base, base);
+        }
+
+        #[test]
+        fn test_queued_edit_with_old_card_names_still_loads() {
+            let card = Card {
+                index: Index(1),
+                names: Names::from([
+                    (NameLanguage::En, Name::new("bulbasaur")),
+                    (NameLanguage::De, Name::new("bisasam")),
+                ]),
+                owned: Bool(true),
+                ..Card::default()
+            };
+            let queued = QueuedEdit {
+                id: 3,
+                edit: OwnershipEdit {
+                    card: card.clone(),
+                    expansions: vec![entry(None, "44")],
+                },
+                base: SavedOwnership {
+                    card,
+                    expansions: vec![],
+                },
+                attempts: 1,
+            };
+
+            // Queued before cards kept names in every language
+            let mut old = serde_json::to_value(&queued).unwrap();
+            for path in ["/edit/card", "/base/card"] {
+                let card = old.pointer_mut(path).unwrap().as_object_mut().unwrap();
+                card.remove("names");
+                card.insert("name_en".to_string(), "bulbasaur".into());
+                card.insert("name_de".to_string(), "bisasam".into());
+            }
+
+            assert_eq!(serde_json::from_value::<QueuedEdit>(old).unwrap(), queued);
+        }
+
+        #[test]
+        fn test_replay_merges_changes_made_elsewhere() {
+            let mut conn = test_connection();
+            let base = stored(&mut conn);
+
+            // Offline: add a print
+            let mut queue = MutationQueue::default();
+            let mut mine = OwnershipEdit {
+                card: base.card.clone(),
+                expansions: base.expansions.clone(),
+            };
+            mine.expansions.push(entry(None, "59"));
+            queue.push(mine, base.clone());
+
+            // Meanwhile another device adds a different print
+            let mut theirs = OwnershipEdit {
+                card: base.card.clone(),
+                expansions: base.expansions.clone(),
+            };
+            theirs.expansions.push(entry(None, "60"));
+            send(&mut conn, theirs).unwrap();
+
+            let outcomes = replay(&mut conn, &mut queue);
+            assert_eq!(outcomes.len(), 2);
+            assert_eq!(outcomes[0], Replayed::Merged);
+            let Replayed::Saved(saved) = &outcomes[1] else {
+                panic!("merged edit was not saved: {outcomes:?}");
+            };
+            let numbers: Vec<&str> = saved
+                .expansions
+                .iter()
+                .map(|e| e.card_number.as_str())
+                .collect();
+            assert_eq!(numbers, vec!["58", "60", "59"]);
+            assert!(queue.is_empty());
+        }
+
+        #[test]
+        fn test_replay_drops_refused_and_keeps_unsent_edits() {
+            let mut conn = test_connection();
+            let base = stored(&mut conn);
+            let mut queue = MutationQueue::default();
+
+            // Owned without prints is refused by the server
+            let id = queue.push(
+                OwnershipEdit {
+                    card: base.card.clone(),
+                    expansions: vec![],
+                },
+                base.clone(),
+            );
+            let unavailable = ServerFnError::ServerError {
+                message: "Service Unavailable".to_string(),
+                code: 503,
+                details: None,
+            };
+            assert_eq!(queue.settle(id, Err(unavailable)), Replayed::Unreachable);
+            assert_eq!(queue.len(), 1);
+
+            let outcomes = replay(&mut conn, &mut queue);
+            assert!(matches!(
+                outcomes.as_slice(),
+                [Replayed::Dropped { card: 25, .. }]
+            ));
+            assert!(queue.is_empty());
+        }
+
+        #[test]
+        fn test_replay_drops_edits_violating_a_constraint() {
+            let mut conn = test_connection();
+            let base = stored(&mut conn);
+            conn.execute_batch(
+                "CREATE UNIQUE INDEX one_print_per_number ON card_expansions (expansion_id, card_number)",
+            )
+            .unwrap();
+            send(
+                &mut conn,
+                OwnershipEdit {
+                    card: Card {
+                        index: Index(26),
+                        owned: Bool(true),
+                        ..Card::default()
+                    },
+                    expansions: vec![entry(None, "59")],
+                },
+            )
+            .unwrap();
+
+            let mut queue = MutationQueue::default();
+            let mut mine = OwnershipEdit {
+                card: base.card.clone(),
+                expansions: base.expansions.clone(),
+            };
+            mine.expansions.push(entry(None, "59"));
+            let id = queue.push(mine.clone(), base);
+
+            let error = send(&mut conn, mine).unwrap_err();
+            assert!(!is_unreachable(&error));
+            let Replayed::Dropped { card, reason } = queue.settle(id, Err(error)) else {
+                panic!("edit was not dropped");
+            };
+            assert_eq!(card, 25);
+            assert!(reason.starts_with("The database refused the change: UNIQUE"));
+            assert!(queue.is_empty());
+        }
+
+        #[test]
+        fn test_snapshot_holds_stored_cards_only() {
+            let mut conn = test_connection();
+            stored(&mut conn);
+
+            let snapshot = snapshot(&conn).unwrap();
+            assert_eq!(snapshot.pockets.len(), 1);
+            assert_eq!(snapshot.pockets[0].prints, 1);
+            assert_eq!(snapshot.prints.len(), 1);
+            assert!(!snapshot.expansions.is_empty());
+        }
+    }
+}
@@ -0,0 +1,1116 @@
+//! Saving a card together with its expansion entries
+//!
+//! The card dialog edits ownership and the full list of prints at once. The
+//! edit is validated as a whole and applied in a single transaction, so a
+//! failure never leaves a card owned without prints or with half of them
+//! deleted. Validation problems travel to the client as JSON in the
+//! `details` of the server error, see [`EditProblem::from_error`].
+//!
+//! Every edit carries the card version it was based on. If the card or one
+//! of its prints changed since, the save is refused with an [`EditConflict`]
+//! holding what is stored now, so the client can reload or merge.
+
+use crate::card::Card;
+use crate::expansion::CardExpansion;
+use dioxus::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The complete desired state of one card
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnershipEdit {
+    pub card: Card,
+    /// Entries with an `id` are kept or updated, entries without are inserted
+    /// and existing entries missing from the list are deleted
+    pub expansions: Vec<CardExpansion>,
+}
+
+/// The card and its expansion entries as stored after the save
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedOwnership {
+    pub card: Card,
+    pub expansions: Vec<CardExpansion>,
+}
+
+/// Why an edit was rejected, `entry` is the position in [`OwnershipEdit::expansions`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum EditProblem {
+    NoExpansions,
+    ExpansionsOnUnownedCard,
+    EmptyCardNumber {
+        entry: usize,
+    },
+    UnknownExpansion {
+        entry: usize,
+        expansion_id: usize,
+    },
+    UnknownEntry {
+        entry: usize,
+        id: usize,
+    },
+    DuplicateEntry {
+        entry: usize,
+        expansion_id: usize,
+        card_number: String,
+    },
+    InvalidCardNumber {
+        entry: usize,
+        card_number: String,
+        reason: String,
+    },
+    /// A constraint the checks above missed, e.g. a UNIQUE index
+    ConstraintViolation {
+        reason: String,
+    },
+}
+
+impl Display for EditProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditProblem::NoExpansions => write!(f, "At least one expansion is required"),
+            EditProblem::ExpansionsOnUnownedCard => {
+                write!(f, "A card that is not owned can't have expansions")
+            }
+            EditProblem::EmptyCardNumber { entry } => {
+                write!(f, "Expansion {} has no card number", entry + 1)
+            }
+            EditProblem::UnknownExpansion {
+                entry,
+                expansion_id,
+            } => write!(
+                f,
+                "Expansion {} refers to unknown expansion {expansion_id}",
+                entry + 1
+            ),
+            EditProblem::UnknownEntry { entry, id } => write!(
+                f,
+                "Expansion {} refers to entry {id}, which does not belong to this card",
+                entry + 1
+            ),
+            EditProblem::DuplicateEntry {
+                entry, card_number, ..
+            } => write!(
+                f,
+                "Expansion {} duplicates card number {card_number} in the same expansion",
+                entry + 1
+            ),
+            EditProblem::InvalidCardNumber {
+                entry,
+                card_number,
+                reason,
+            } => write!(
+                f,
+                "Expansion {} has card number {card_number}: {reason}",
+                entry + 1
+            ),
+            EditProblem::ConstraintViolation { reason } => {
+                write!(f, "The database refused the change: {reason}")
+            }
+        }
+    }
+}
+
+impl EditProblem {
+    /// HTTP status used for rejected edits
+    pub const STATUS: u16 = 422;
+
+    /// Extracts the validation problems from a failed save, empty for other errors
+    pub fn from_error(error: &ServerFnError) -> Vec<EditProblem> {
+        match error {
+            ServerFnError::ServerError {
+                code: Self::STATUS,
+                details: Some(details),
+                ..
+            } => serde_json::from_value(details.clone()).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The server error sent for `problems`
+    pub fn into_error(problems: &[EditProblem]) -> ServerFnError {
+        ServerFnError::ServerError {
+            message: problems
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; "),
+            code: Self::STATUS,
+            details: serde_json::to_value(problems).ok(),
+        }
+    }
+}
+
+/// The card changed since the edit was loaded, e.g. on another device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditConflict {
+    /// Card version the edit was based on
+    pub expected: u32,
+    /// The card and its prints as stored now
+    pub current: SavedOwnership,
+}
+
+impl Display for EditConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Card #{} was changed elsewhere since it was loaded",
+            self.current.card.index
+        )
+    }
+}
+
+impl std::error::Error for EditConflict {}
+
+impl EditConflict {
+    /// HTTP status used for stale edits
+    pub const STATUS: u16 = 409;
+
+    /// Extracts the conflict from a failed save, `None` for other errors
+    pub fn from_error(error: &ServerFnError) -> Option<EditConflict> {
+        match error {
+            ServerFnError::ServerError {
+                code: Self::STATUS,
+                details: Some(details),
+                ..
+            } => serde_json::from_value(details.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// The server error sent for this conflict
+    pub fn into_error(&self) -> ServerFnError {
+        ServerFnError::ServerError {
+            message: self.to_string(),
+            code: Self::STATUS,
+            details: serde_json::to_value(self).ok(),
+        }
+    }
+
+    /// Replays the changes `mine` made to `base` on top of the stored state
+    ///
+    /// A print changed on one side takes that change, one changed on both
+    /// sides keeps mine. A print removed on one side and edited on the other
+    /// is kept.
+    /// The result is based on the current version and can be saved as is.
+    pub fn merge(&self, base: &SavedOwnership, mine: &OwnershipEdit) -> OwnershipEdit {
+        let base_entry =
+            |id: Option<usize>| base.expansions.iter().find(|b| id.is_some() && b.id == id);
+        let mine_entry =
+            |id: Option<usize>| mine.expansions.iter().find(|m| id.is_some() && m.id == id);
+        let mut expansions = Vec::new();
+
+        for theirs in &self.current.expansions {
+            match (base_entry(theirs.id), mine_entry(theirs.id)) {
+                // Edited by me
+                (Some(before), Some(edited)) if edited != before => expansions.push(edited.clone()),
+                // Removed by me and left alone by them
+                (Some(before), None) if before == theirs => {}
+                _ => expansions.push(theirs.clone()),
+            }
+        }
+
+        for edited in &mine.expansions {
+            let stored = self
+                .current
+                .expansions
+                .iter()
+                .any(|t| edited.id.is_some() && t.id == edited.id);
+            if stored {
+                continue;
+            }
+            // Entries they removed come back only if I changed them
+            let changed = match base_entry(edited.id) {
+                Some(before) => before != edited,
+                None => true,
+            };
+            let duplicate = expansions.iter().any(|e| {
+                e.expansion_id == edited.expansion_id
+                    && e.card_number.trim() == edited.card_number.trim()
+            });
+            if changed && !duplicate {
+                expansions.push(CardExpansion {
+                    id: None,
+                    ..edited.clone()
+                });
+            }
+        }
+
+        let owned = if mine.card.owned != base.card.owned {
+            mine.card.owned.clone()
+        } else {
+            self.current.card.owned.clone()
+        };
+        OwnershipEdit {
+            card: Card {
+                owned,
+                version: self.current.card.version,
+                ..mine.card.clone()
+            },
+            expansions,
+        }
+    }
+}
+
+impl OwnershipEdit {
+    /// Checks everything that does not need the database
+    pub fn problems(&self) -> Vec<EditProblem> {
+        let mut problems = Vec::new();
+
+        match (self.card.owned.0, self.expansions.is_empty()) {
+            (true, true) => problems.push(EditProblem::NoExpansions),
+            (false, false) => problems.push(EditProblem::ExpansionsOnUnownedCard),
+            _ => {}
+        }
+
+        for (entry, expansion) in self.expansions.iter().enumerate() {
+            if expansion.card_number.trim().is_empty() {
+                problems.push(EditProblem::EmptyCardNumber { entry });
+            }
+            let duplicate = self.expansions[..entry].iter().any(|earlier| {
+                earlier.expansion_id == expansion.expansion_id
+                    && earlier.card_number.trim() == expansion.card_number.trim()
+            });
+            if duplicate {
+                problems.push(EditProblem::DuplicateEntry {
+                    entry,
+                    expansion_id: expansion.expansion_id,
+                    card_number: expansion.card_number.trim().to_string(),
+                });
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::bulk::UNKNOWN_CARD_NUMBER;
+    use crate::expansion_admin;
+    use crate::history::{CardChange, Recorder};
+    use crate::log_ownership_change;
+    use anyhow::Result;
+    use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+    use std::collections::HashMap;
+
+    /// Outcome of [`save`], validation problems and conflicts are not an `Err`
+    pub enum SaveOutcome {
+        Saved(SavedOwnership),
+        Rejected(Vec<EditProblem>),
+        Conflict(EditConflict),
+    }
+
+    pub(crate) fn stored_card(conn: &Connection, index: usize) -> Result<Option<Card>> {
+        Ok(conn
+            .query_row(
+                "SELECT id, names, book, page, side, entry, img_url, owned, version FROM cards WHERE id = ?1",
+                [index],
+                |row| {
+                    Ok(Card {
+                        index: row.get(0)?,
+                        names: row.get(1)?,
+                        book: row.get(2)?,
+                        page: row.get(3)?,
+                        side: row.get(4)?,
+                        entry: row.get(5)?,
+                        img_url: row.get(6)?,
+                        owned: row.get(7)?,
+                        version: row.get(8)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// The conflict if a stored card moved past `expected`
+    ///
+    /// Cards that are not stored yet never conflict, whatever `expected` is.
+    pub(crate) fn check_version(
+        conn: &Connection,
+        card_id: usize,
+        expected: u32,
+    ) -> Result<Option<EditConflict>> {
+        let Some(card) = stored_card(conn, card_id)? else {
+            return Ok(None);
+        };
+        if card.version == expected {
+            return Ok(None);
+        }
+        Ok(Some(EditConflict {
+            expected,
+            current: SavedOwnership {
+                expansions: card_expansions(conn, card_id)?,
+                card,
+            },
+        }))
+    }
+
+    pub(crate) fn card_expansions(conn: &Connection, card_id: usize) -> Result<Vec<CardExpansion>> {
+        Ok(conn
+            .prepare(
+                "SELECT id, card_id, expansion_id, card_number, rarity FROM card_expansions WHERE card_id = ?1 ORDER BY id",
+            )?
+            .query_map([card_id], |row| {
+                Ok(CardExpansion {
+                    id: Some(row.get(0)?),
+                    card_id: row.get(1)?,
+                    expansion_id: row.get(2)?,
+                    card_number: row.get(3)?,
+                    rarity: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?)
+    }
+
+    /// The problem behind a write the database refused, `None` for other errors
+    pub fn constraint_problem(err: &anyhow::Error) -> Option<EditProblem> {
+        match err.downcast_ref::<rusqlite::Error>()? {
+            rusqlite::Error::SqliteFailure(failure, message)
+                if failure.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                Some(EditProblem::ConstraintViolation {
+                    reason: message.clone().unwrap_or_else(|| failure.to_string()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Validates `edit` and applies it in one transaction
+    ///
+    /// All changes are recorded as a single history batch, so one undo
+    /// reverts the whole save.
+    pub fn save(conn: &mut Connection, edit: OwnershipEdit, profile: &str) -> Result<SaveOutcome> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let outcome = apply_edit(&tx, edit, profile)?;
+        if let SaveOutcome::Saved(_) = outcome {
+            tx.commit()?;
+        }
+        Ok(outcome)
+    }
+
+    /// Validates and applies `edit` inside a transaction or savepoint owned
+    /// by the caller, recording the changes as one history batch
+    pub(crate) fn apply_edit(
+        tx: &Connection,
+        edit: OwnershipEdit,
+        profile: &str,
+    ) -> Result<SaveOutcome> {
+        let card = edit.card;
+        let card_id = card.index.0;
+        if let Some(conflict) = check_version(tx, card_id, card.version)? {
+            return Ok(SaveOutcome::Conflict(conflict));
+        }
+
+        let mut desired: Vec<CardExpansion> = edit
+            .expansions
+            .into_iter()
+            .map(|expansion| CardExpansion {
+                card_id,
+                card_number: expansion.card_number.trim().to_string(),
+                ..expansion
+            })
+            .collect();
+
+        let existing = card_expansions(tx, card_id)?;
+        let existing_by_id: HashMap<usize, &CardExpansion> = existing
+            .iter()
+            .filter_map(|e| e.id.map(|id| (id, e)))
+            .collect();
+
+        // New and changed numbers must fit the expansion's scheme and are
+        // stored in its canonical form, untouched entries keep theirs
+        let mut stored_problems = Vec::new();
+        for (entry, expansion) in desired.iter_mut().enumerate() {
+            let Some(stored) = expansion_admin::get(tx, expansion.expansion_id)? else {
+                stored_problems.push(EditProblem::UnknownExpansion {
+                    entry,
+                    expansion_id: expansion.expansion_id,
+                });
+                continue;
+            };
+            let unchanged = expansion
+                .id
+                .and_then(|id| existing_by_id.get(&id))
+                .is_some_and(|existing| {
+                    existing.expansion_id == expansion.expansion_id
+                        && existing.card_number == expansion.card_number
+                });
+            if unchanged || matches!(expansion.card_number.as_str(), "" | UNKNOWN_CARD_NUMBER) {
+                continue;
+            }
+            match stored.check_number(&expansion.card_number) {
+                Ok(number) => expansion.card_number = number.to_string(),
+                Err(reason) => stored_problems.push(EditProblem::InvalidCardNumber {
+                    entry,
+                    card_number: expansion.card_number.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        let mut problems = OwnershipEdit {
+            card: card.clone(),
+            expansions: desired.clone(),
+        }
+        .problems();
+        problems.extend(stored_problems);
+        for (entry, expansion) in desired.iter().enumerate() {
+            if let Some(id) = expansion.id {
+                if !existing_by_id.contains_key(&id) {
+                    problems.push(EditProblem::UnknownEntry { entry, id });
+                }
+            }
+        }
+        if !problems.is_empty() {
+            return Ok(SaveOutcome::Rejected(problems));
+        }
+
+        let recorder = Recorder::new(tx, profile)?;
+
+        // Card row, keeping created_at of an existing row
+        let old_owned = tx
+            .query_row("SELECT owned FROM cards WHERE id = ?1", [card_id], |row| {
+                row.get::<_, bool>(0)
+            })
+            .optional()?
+            .unwrap_or(false);
+        tx.execute(
+            "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET names = excluded.names, book = excluded.book, page = excluded.page,
+                 side = excluded.side, entry = excluded.entry, img_url = excluded.img_url, owned = excluded.owned",
+            params![card.index, card.names, card.book, card.page, card.side, card.entry, card.img_url, card.owned],
+        )?;
+        if old_owned != card.owned.0 {
+            log_ownership_change!(card_id, old_owned, card.owned.0);
+            recorder.record(
+                tx,
+                card_id,
+                &CardChange::Ownership {
+                    before: old_owned,
+                    after: card.owned.0,
+                },
+            )?;
+        }
+
+        // Deletes first and kept prints changing expansion or number move to a
+        // placeholder number, so neither swapped numbers nor a new print taking
+        // a number another print gives up can trip the UNIQUE constraint
+        for old in &existing {
+            if !desired.iter().any(|d| d.id == old.id) {
+                tx.execute("DELETE FROM card_expansions WHERE id = ?1", [old.id])?;
+                recorder.record(
+                    tx,
+                    card_id,
+                    &CardChange::ExpansionRemoved {
+                        expansion: old.clone(),
+                    },
+                )?;
+            }
+        }
+
+        for expansion in &desired {
+            if let Some(&before) = expansion.id.and_then(|id| existing_by_id.get(&id)) {
+                if before.expansion_id != expansion.expansion_id
+                    || before.card_number != expansion.card_number
+                {
+                    tx.execute(
+                        "UPDATE card_expansions SET card_number = 'moving:' || id WHERE id = ?1",
+                        [expansion.id],
+                    )?;
+                }
+            }
+        }
+
+        for expansion in desired {
+            match expansion.id.and_then(|id| existing_by_id.get(&id)) {
+                Some(&before) if *before == expansion => {}
+                Some(&before) => {
+                    tx.execute(
+                        "UPDATE card_expansions SET expansion_id = ?1, card_number = ?2, rarity = ?3 WHERE id = ?4",
+                        params![expansion.expansion_id, expansion.card_number, expansion.rarity, expansion.id],
+                    )?;
+                    recorder.record(
+                        tx,
+                        card_id,
+                        &CardChange::ExpansionUpdated {
+                            before: before.clone(),
+                            after: expansion,
+                        },
+                    )?;
+                }
+                None => {
+                    tx.execute(
+                        "INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES (?1, ?2, ?3, ?4)",
+                        params![card_id, expansion.expansion_id, expansion.card_number, expansion.rarity],
+                    )?;
+                    let inserted = CardExpansion {
+                        id: Some(tx.last_insert_rowid() as usize),
+                        ..expansion
+                    };
+                    recorder.record(
+                        tx,
+                        card_id,
+                        &CardChange::ExpansionAdded {
+                            expansion: inserted,
+                        },
+                    )?;
+                }
+            }
+        }
+
+        // The triggers bumped the version, hand back the stored one
+        let saved = SavedOwnership {
+            expansions: card_expansions(tx, card_id)?,
+            card: Card {
+                version: tx.query_row(
+                    "SELECT version FROM cards WHERE id = ?1",
+                    [card_id],
+                    |row| row.get(0),
+                )?,
+                ..card
+            },
+        };
+        Ok(SaveOutcome::Saved(saved))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::card::{Bool, Index, Rarity};
+
+        fn card(owned: bool) -> Card {
+            Card {
+                index: Index(25),
+                owned: Bool(owned),
+                ..Card::default()
+            }
+        }
+
+        fn entry(id: Option<usize>, card_number: &str) -> CardExpansion {
+            CardExpansion {
+                id,
+                card_id: 25,
+                expansion_id: 1,
+                card_number: card_number.to_string(),
+                rarity: Rarity::Common,
+            }
+        }
+
+        fn saved(outcome: SaveOutcome) -> SavedOwnership {
+            match outcome {
+                SaveOutcome::Saved(saved) => saved,
+                SaveOutcome::Rejected(problems) => panic!("edit rejected: {problems:?}"),
+                SaveOutcome::Conflict(conflict) => panic!("edit conflicts: {conflict}"),
+            }
+        }
+
+        fn event_count(conn: &Connection) -> usize {
+            conn.query_row("SELECT COUNT(*) FROM card_events", [], |row| row.get(0))
+                .unwrap()
+        }
+
+        #[test]
+        fn test_problems_without_database() {
+            let edit = OwnershipEdit {
+                card: card(true),
+                expansions: vec![entry(None, "58"), entry(None, " 58 "), entry(None, "")],
+            };
+            assert_eq!(
+                edit.problems(),
+                vec![
+                    EditProblem::DuplicateEntry {
+                        entry: 1,
+                        expansion_id: 1,
+                        card_number: "58".to_string()
+                    },
+                    EditProblem::EmptyCardNumber { entry: 2 },
+                ]
+            );
+
+            let edit = OwnershipEdit {
+                card: card(true),
+                expansions: vec![],
+            };
+            assert_eq!(edit.problems(), vec![EditProblem::NoExpansions]);
+        }
+
+        #[test]
+        fn test_problems_round_trip_through_server_error() {
+            let problems = vec![
+                EditProblem::NoExpansions,
+                EditProblem::EmptyCardNumber { entry: 0 },
+            ];
+            let error = EditProblem::into_error(&problems);
+            assert_eq!(EditProblem::from_error(&error), problems);
+        }
+
+        #[test]
+        fn test_save_add_edit_and_remove() {
+            let mut conn = test_connection();
+
+            let first = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![entry(None, "58"), entry(None, "59")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            assert_eq!(first.expansions.len(), 2);
+            assert_eq!(event_count(&conn), 3);
+
+            // Keep the first print, change the second, add a third
+            let mut changed = first.expansions[1].clone();
+            changed.rarity = Rarity::Rare;
+            let second = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: first.card.clone(),
+                        expansions: vec![first.expansions[0].clone(), changed, entry(None, "60")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            assert_eq!(second.expansions.len(), 3);
+            assert_eq!(second.expansions[0], first.expansions[0]);
+            assert_eq!(event_count(&conn), 5);
+
+            let removed = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: Card {
+                            owned: Bool(false),
+                            ..second.card.clone()
+                        },
+                        expansions: vec![],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            assert!(removed.expansions.is_empty());
+            assert!(!removed.card.owned.0);
+        }
+
+        #[test]
+        fn test_save_swaps_and_reuses_card_numbers() {
+            let mut conn = test_connection();
+            let first = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![entry(None, "58"), entry(None, "59")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            let numbers = |saved: &SavedOwnership| -> Vec<(Option<usize>, String)> {
+                saved
+                    .expansions
+                    .iter()
+                    .map(|e| (e.id, e.card_number.clone()))
+                    .collect()
+            };
+
+            // 58 <-> 59 on the prints that are kept
+            let mut swapped = first.expansions.clone();
+            swapped[0].card_number = "59".to_string();
+            swapped[1].card_number = "58".to_string();
+            let second = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: first.card.clone(),
+                        expansions: swapped,
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            let mut expected = vec![
+                (first.expansions[0].id, "59".to_string()),
+                (first.expansions[1].id, "58".to_string()),
+            ];
+            expected.sort();
+            let mut stored = numbers(&second);
+            stored.sort();
+            assert_eq!(stored, expected);
+
+            // A new print takes 58 while the print holding it moves to 60
+            let mut moved = second
+                .expansions
+                .iter()
+                .find(|e| e.card_number == "58")
+                .unwrap()
+                .clone();
+            let kept = second
+                .expansions
+                .iter()
+                .find(|e| e.card_number == "59")
+                .unwrap()
+                .clone();
+            moved.card_number = "60".to_string();
+            let third = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: second.card.clone(),
+                        expansions: vec![entry(None, "58"), kept, moved.clone()],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            let mut stored: Vec<String> = numbers(&third).into_iter().map(|(_, n)| n).collect();
+            stored.sort();
+            assert_eq!(stored, vec!["58", "59", "60"]);
+            assert!(third
+                .expansions
+                .iter()
+                .any(|e| e.id == moved.id && e.card_number == "60"));
+        }
+
+        #[test]
+        fn test_undo_restores_swapped_card_numbers() {
+            let numbers = |conn: &Connection| -> Vec<(usize, String)> {
+                conn.prepare("SELECT id, card_number FROM card_expansions ORDER BY id")
+                    .unwrap()
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap()
+            };
+            type Edit = fn(&[CardExpansion]) -> Vec<CardExpansion>;
+            // 58 <-> 59, and a new print taking 58 while the print holding it moves to 60
+            let edits: [Edit; 2] = [
+                |kept| {
+                    kept.iter()
+                        .map(|e| CardExpansion {
+                            card_number: if e.card_number == "58" { "59" } else { "58" }
+                                .to_string(),
+                            ..e.clone()
+                        })
+                        .collect()
+                },
+                |kept| {
+                    let mut edited = vec![entry(None, "58")];
+                    edited.extend(kept.iter().map(|e| CardExpansion {
+                        card_number: if e.card_number == "58" { "60" } else { "59" }.to_string(),
+                        ..e.clone()
+                    }));
+                    edited
+                },
+            ];
+
+            for edit in edits {
+                let mut conn = test_connection();
+                let first = saved(
+                    save(
+                        &mut conn,
+                        OwnershipEdit {
+                            card: card(true),
+                            expansions: vec![entry(None, "58"), entry(None, "59")],
+                        },
+                        "ash",
+                    )
+                    .unwrap(),
+                );
+                let before = numbers(&conn);
+                saved(
+                    save(
+                        &mut conn,
+                        OwnershipEdit {
+                            card: first.card.clone(),
+                            expansions: edit(&first.expansions),
+                        },
+                        "ash",
+                    )
+                    .unwrap(),
+                );
+                assert_ne!(numbers(&conn), before);
+
+                crate::history::undo_last(&mut conn, 25, "ash").unwrap();
+                assert_eq!(numbers(&conn), before);
+            }
+        }
+
+        #[test]
+        fn test_rejected_edit_changes_nothing() {
+            let mut conn = test_connection();
+            let outcome = save(
+                &mut conn,
+                OwnershipEdit {
+                    card: card(true),
+                    expansions: vec![CardExpansion {
+                        expansion_id: 999_999,
+                        ..entry(None, "1")
+                    }],
+                },
+                "ash",
+            )
+            .unwrap();
+
+            assert!(matches!(
+                outcome,
+                SaveOutcome::Rejected(problems) if problems == vec![EditProblem::UnknownExpansion { entry: 0, expansion_id: 999_999 }]
+            ));
+            let cards: usize = conn
+                .query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(cards, 0);
+            assert_eq!(event_count(&conn), 0);
+        }
+
+        #[test]
+        fn test_card_numbers_follow_the_expansion_scheme() {
+            let mut conn = test_connection();
+            let brilliant_stars = expansion_admin::list(&conn)
+                .unwrap()
+                .into_iter()
+                .find(|e| e.name == "Brilliant Stars")
+                .unwrap()
+                .id;
+            let print = |card_number: &str| CardExpansion {
+                expansion_id: brilliant_stars,
+                ..entry(None, card_number)
+            };
+
+            let outcome = save(
+                &mut conn,
+                OwnershipEdit {
+                    card: card(true),
+                    expansions: vec![print("TG31"), print("999")],
+                },
+                "ash",
+            )
+            .unwrap();
+            let SaveOutcome::Rejected(problems) = outcome else {
+                panic!("the edit was not rejected");
+            };
+            let rejected: Vec<(usize, &str)> = problems
+                .iter()
+                .filter_map(|problem| match problem {
+                    EditProblem::InvalidCardNumber {
+                        entry, card_number, ..
+                    } => Some((*entry, card_number.as_str())),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(rejected, vec![(0, "TG31"), (1, "999")]);
+
+            let stored = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![print("tg05"), print("049/172")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            let numbers: Vec<&str> = stored
+                .expansions
+                .iter()
+                .map(|e| e.card_number.as_str())
+                .collect();
+            assert_eq!(numbers, vec!["TG5", "49"]);
+        }
+
+        #[test]
+        fn test_single_undo_reverts_whole_save() {
+            let mut conn = test_connection();
+            saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![entry(None, "58"), entry(None, "59")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+
+            crate::history::undo_last(&mut conn, 25, "ash").unwrap();
+
+            let owned: bool = conn
+                .query_row("SELECT owned FROM cards WHERE id = 25", [], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            let prints: usize = conn
+                .query_row("SELECT COUNT(*) FROM card_expansions", [], |row| row.get(0))
+                .unwrap();
+            assert!(!owned);
+            assert_eq!(prints, 0);
+        }
+
+        #[test]
+        fn test_prints_bump_card_version() {
+            let mut conn = test_connection();
+            let first = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![entry(None, "58")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            assert_eq!(
+                stored_card(&conn, 25).unwrap().unwrap().version,
+                first.card.version
+            );
+
+            conn.execute("UPDATE card_expansions SET card_number = '58a'", [])
+                .unwrap();
+            let stored = stored_card(&conn, 25).unwrap().unwrap();
+            assert_eq!(stored.version, first.card.version + 1);
+        }
+
+        #[test]
+        fn test_stale_edit_conflicts_and_changes_nothing() {
+            let mut conn = test_connection();
+            let first = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: card(true),
+                        expansions: vec![entry(None, "58")],
+                    },
+                    "ash",
+                )
+                .unwrap(),
+            );
+            // Another device adds a print
+            let theirs = saved(
+                save(
+                    &mut conn,
+                    OwnershipEdit {
+                        card: first.card.clone(),
+                        expansions: vec![first.expansions[0].clone(), entry(None, "59")],
+                    },
+                    "misty",
+                )
+                .unwrap(),
+            );
+            let events = event_count(&conn);
+
+            let outcome = save(
+                &mut conn,
+                OwnershipEdit {
+                    card: Card {
+                        owned: Bool(false),
+                        ..first.card.clone()
+                    },
+                    expansions: vec![],
+                },
+                "ash",
+            )
+            .unwrap();
+
+            let SaveOutcome::Conflict(conflict) = outcome else {
+                panic!("stale edit was not refused");
+            };
+            assert_eq!(conflict.expected, first.card.version);
+            assert_eq!(conflict.current, theirs);
+            assert_eq!(event_count(&conn), events);
+            assert_eq!(
+                EditConflict::from_error(&conflict.into_error()),
+                Some(conflict)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{Bool, Index, Rarity};
+
+    fn entry(id: Option<usize>, card_number: &str, rarity: Rarity) -> CardExpansion {
+        CardExpansion {
+            id,
+            card_id: 25,
+            expansion_id: 1,
+            card_number: card_number.to_string(),
+            rarity,
+        }
+    }
+
+    fn state(version: u32, expansions: Vec<CardExpansion>) -> SavedOwnership {
+        SavedOwnership {
+            card: Card {
+                index: Index(25),
+                owned: Bool(true),
+                version,
+                ..Card::default()
+            },
+            expansions,
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_both_sides() {
+        let base = state(
+            3,
+            vec![
+                entry(Some(1), "58", Rarity::Common),
+                entry(Some(2), "59", Rarity::Common),
+                entry(Some(3), "60", Rarity::Common),
+            ],
+        );
+        // I changed 1, removed 2 and added 61
+        let mine = OwnershipEdit {
+            card: base.card.clone(),
+            expansions: vec![
+                entry(Some(1), "58", Rarity::Rare),
+                entry(Some(3), "60", Rarity::Common),
+                entry(None, "61", Rarity::Common),
+            ],
+        };
+        // They changed 3, removed 1 and added 62
+        let conflict = EditConflict {
+            expected: 3,
+            current: state(
+                5,
+                vec![
+                    entry(Some(2), "59", Rarity::Common),
+                    entry(Some(3), "60", Rarity::HoloRare),
+                    entry(Some(4), "62", Rarity::Common),
+                ],
+            ),
+        };
+
+        let merged = conflict.merge(&base, &mine);
+        assert_eq!(merged.card.version, 5);
+        assert!(merged.card.owned.0);
+        assert_eq!(
+            merged.expansions,
+            vec![
+                entry(Some(3), "60", Rarity::HoloRare),
+                entry(Some(4), "62", Rarity::Common),
+                entry(None, "58", Rarity::Rare),
+                entry(None, "61", Rarity::Common),
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Everything the book view shows for a range of pockets, in one request
+//!
+//! Rendering a double page used to cost one expansion lookup per card to
+//! colour it by rarity. [`PocketSummary`] bundles the stored card with its
+//! highest rarity and print count, computed by a single SQL query over the
+//! whole range.
+
+use crate::card::{Card, Rarity};
+use serde::{Deserialize, Serialize};
+
+/// Most pockets summarised by one request
+pub const MAX_POCKETS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PocketSummary {
+    pub index: usize,
+    /// The stored card, `None` if it was never looked up
+    pub card: Option<Card>,
+    /// `None` when the card has no prints
+    pub highest_rarity: Option<Rarity>,
+    pub prints: usize,
+}
+
+impl PocketSummary {
+    pub fn owned(&self) -> bool {
+        self.card.as_ref().is_some_and(|card| card.owned.0)
+    }
+
+    /// Sprite URL of the stored card
+    pub fn img_url(&self) -> Option<&str> {
+        self.card.as_ref().map(|card| card.img_url.as_str())
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use anyhow::Result;
+    use rusqlite::{params, Connection};
+    use strum::IntoEnumIterator;
+
+    /// SQL expression ranking `column` by [`Rarity`] order, unknown text ranks -1
+    pub(crate) fn rarity_rank(column: &str) -> String {
+        let cases: String = Rarity::iter()
+            .enumerate()
+            .map(|(rank, rarity)| {
+                format!(
+                    " WHEN '{}' THEN {rank}",
+                    rarity.stored().replace('\'', "''")
+                )
+            })
+            .collect();
+        format!("CASE {column}{cases} ELSE -1 END")
+    }
+
+    /// The rarity ranked `rank` by [`rarity_rank`]
+    pub(crate) fn rarity_from_rank(rank: Option<i64>) -> Option<Rarity> {
+        let rank = usize::try_from(rank?).ok()?;
+        Rarity::iter().nth(rank)
+    }
+
+    /// One summary per index in `first..=last`, stored or not
+    pub fn summaries(conn: &Connection, first: usize, last: usize) -> Result<Vec<PocketSummary>> {
+        let sql = format!(
+            "WITH RECURSIVE pockets(id) AS (SELECT ?1 UNION ALL SELECT id + 1 FROM pockets WHERE id < ?2)
+             SELECT p.id, c.id, c.names, c.book, c.page, c.side, c.entry, c.img_url, c.owned,
+                    c.version, prints.highest, COALESCE(prints.count, 0)
+             FROM pockets p
+             LEFT JOIN cards c ON c.id = p.id
+             LEFT JOIN (
+                 SELECT card_id, MAX({}) AS highest, COUNT(*) AS count
+                 FROM card_expansions
+                 WHERE card_id BETWEEN ?1 AND ?2
+                 GROUP BY card_id
+             ) prints ON prints.card_id = p.id
+             ORDER BY p.id",
+            rarity_rank("rarity")
+        );
+
+        let summaries = conn
+            .prepare(&sql)?
+            .query_map(params![first, last], |row| {
+                let stored: Option<usize> = row.get(1)?;
+                let card = match stored {
+                    Some(_) => Some(Card {
+                        index: row.get(1)?,
+                        names: row.get(2)?,
+                        book: row.get(3)?,
+                        page: row.get(4)?,
+                        side: row.get(5)?,
+                        entry: row.get(6)?,
+                        img_url: row.get(7)?,
+                        owned: row.get(8)?,
+                        version: row.get(9)?,
+                    }),
+                    None => None,
+                };
+                Ok(PocketSummary {
+                    index: row.get(0)?,
+                    card,
+                    highest_rarity: rarity_from_rank(row.get(10)?),
+                    prints: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        Ok(summaries)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        #[test]
+        fn test_summaries_cover_the_whole_range() {
+            let conn = test_connection();
+            conn.execute_batch(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES
+                    (1, json_object('en', 'bulbasaur', 'de', 'bisasam'), 1, 1, 'A', 1, 'sprite/1.png', 1),
+                    (2, json_object('en', 'ivysaur', 'de', 'bisaknosp'), 1, 1, 'A', 2, 'sprite/2.png', 0);
+                 INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES
+                    (1, (SELECT MIN(id) FROM expansions), '44', 'Common ●'),
+                    (1, (SELECT MIN(id) FROM expansions), '1', 'Holo Rare ★H'),
+                    (1, (SELECT MAX(id) FROM expansions), '12', 'Uncommon ♦');",
+            )
+            .unwrap();
+
+            let summaries = summaries(&conn, 1, 3).unwrap();
+            assert_eq!(
+                summaries.iter().map(|s| s.index).collect::<Vec<_>>(),
+                vec![1, 2, 3]
+            );
+
+            assert!(summaries[0].owned());
+            assert_eq!(summaries[0].highest_rarity, Some(Rarity::HoloRare));
+            assert_eq!(summaries[0].prints, 3);
+            assert_eq!(summaries[0].img_url(), Some("sprite/1.png"));
+
+            assert!(!summaries[1].owned());
+            assert_eq!(summaries[1].highest_rarity, None);
+            assert_eq!(summaries[1].prints, 0);
+
+            assert!(summaries[2].card.is_none());
+        }
+
+        #[test]
+        fn test_rarity_rank_round_trip() {
+            for (rank, rarity) in Rarity::iter().enumerate() {
+                assert_eq!(rarity_from_rank(Some(rank as i64)), Some(rarity));
+            }
+            assert_eq!(rarity_from_rank(Some(-1)), None);
+            assert_eq!(rarity_from_rank(None), None);
+        }
+    }
+}
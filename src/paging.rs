@@ -0,0 +1,380 @@
+//! Paginated, filtered and sorted reads of stored cards
+//!
+//! Replaces loading the whole `cards` table at once. A request asks for one
+//! page, either by number or by the opaque cursor of the previous page.
+//! Cursors continue after the last card seen (keyset pagination), so pages
+//! don't shift when cards are added in between. Page numbers are handy to
+//! jump around and are what the book view uses.
+
+use crate::card::{Card, NameLanguage};
+use serde::{Deserialize, Serialize};
+
+/// Largest accepted page size
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardSort {
+    /// Pokedex number, which is also binder order
+    #[default]
+    Index,
+    /// Name in one language, cards without it come first
+    Name(NameLanguage),
+    /// When the card was first stored
+    Added,
+}
+
+/// Which cards to return, all set fields must match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CardFilter {
+    pub owned: Option<bool>,
+    /// Inclusive range of Pokedex numbers
+    pub indices: Option<(usize, usize)>,
+    /// A filter in the search box language, see [`crate::query`]
+    pub query: Option<String>,
+}
+
+impl CardFilter {
+    /// Owned cards on one double page of the binder
+    pub fn owned_on_double_page(page: usize) -> Self {
+        let range = crate::config::layout().double_page(page);
+        Self {
+            owned: Some(true),
+            indices: Some((*range.start(), *range.end())),
+            query: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardPageRequest {
+    pub filter: CardFilter,
+    pub sort: CardSort,
+    pub descending: bool,
+    pub page_size: usize,
+    /// 1-based page number, ignored when `cursor` is set
+    pub page: usize,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+}
+
+impl CardPageRequest {
+    /// First page of `filter` in binder order
+    pub fn new(filter: CardFilter, page_size: usize) -> Self {
+        Self {
+            filter,
+            sort: CardSort::Index,
+            descending: false,
+            page_size,
+            page: 1,
+            cursor: None,
+        }
+    }
+
+    /// Why the request can't be served, `None` if it is valid
+    pub fn problem(&self) -> Option<String> {
+        if self.page_size == 0 || self.page_size > MAX_PAGE_SIZE {
+            return Some(format!("Page size must be between 1 and {MAX_PAGE_SIZE}"));
+        }
+        if self.page == 0 && self.cursor.is_none() {
+            return Some("Pages are numbered from 1".to_string());
+        }
+        if let Some((first, last)) = self.filter.indices {
+            if first > last {
+                return Some(format!("Empty index range {first}-{last}"));
+            }
+        }
+        None
+    }
+}
+
+/// A stored card and when it was first stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardEntry {
+    pub card: Card,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CardPage {
+    pub entries: Vec<CardEntry>,
+    /// Cards matching the filter on all pages
+    pub total: usize,
+    /// Pass as `cursor` to get the following page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Position after the last card of a page, encoded as JSON in the cursor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Cursor {
+    sort: CardSort,
+    descending: bool,
+    key: String,
+    id: usize,
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::query;
+    use anyhow::Result;
+    use rusqlite::{params_from_iter, types::Value, Connection};
+
+    /// The cursor does not belong to this kind of request
+    #[derive(Debug, thiserror::Error)]
+    #[error("Invalid page cursor")]
+    pub struct InvalidCursor;
+
+    impl CardSort {
+        /// Sort expression, text so it can be compared to a cursor key
+        fn column(self) -> String {
+            match self {
+                CardSort::Index => "printf('%08d', c.id)".to_string(),
+                CardSort::Name(language) => format!(
+                    "lower(COALESCE(json_extract(c.names, '$.{}'), ''))",
+                    language.code()
+                ),
+                CardSort::Added => "COALESCE(c.created_at, '')".to_string(),
+            }
+        }
+    }
+
+    /// One page of stored cards
+    ///
+    /// Fails with [`query::QueryError`] for an invalid `query` filter and with
+    /// [`InvalidCursor`] for a cursor of a different sort order.
+    pub fn page(conn: &Connection, request: &CardPageRequest) -> Result<CardPage> {
+        let mut params: Vec<Value> = Vec::new();
+        let mut conditions: Vec<String> = Vec::new();
+
+        if let Some(owned) = request.filter.owned {
+            conditions.push(format!("c.owned = {}", u8::from(owned)));
+        }
+        if let Some((first, last)) = request.filter.indices {
+            params.push(Value::from(first as i64));
+            params.push(Value::from(last as i64));
+            conditions.push(format!(
+                "c.id BETWEEN ?{} AND ?{}",
+                params.len() - 1,
+                params.len()
+            ));
+        }
+        if let Some(input) = request.filter.query.as_deref() {
+            let expr = query::parse(input).map_err(anyhow::Error::from)?;
+            query::check_types(conn, input)?;
+            conditions.push(query::condition(&expr, &mut params));
+        }
+
+        // `d` is the alias the query language conditions refer to
+        let from = "FROM cards c JOIN cards d ON d.id = c.id";
+        let filter = if conditions.is_empty() {
+            "1".to_string()
+        } else {
+            conditions.join(" AND ")
+        };
+
+        let total: usize = conn.query_row(
+            &format!("SELECT COUNT(*) {from} WHERE {filter}"),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+
+        let column = request.sort.column();
+        let (order, after) = if request.descending {
+            ("DESC", "<")
+        } else {
+            ("ASC", ">")
+        };
+
+        let mut page_filter = filter;
+        let mut offset = (request.page.max(1) - 1) * request.page_size;
+        if let Some(cursor) = request.cursor.as_deref() {
+            let cursor: Cursor = serde_json::from_str(cursor).map_err(|_| InvalidCursor)?;
+            if cursor.sort != request.sort || cursor.descending != request.descending {
+                return Err(InvalidCursor.into());
+            }
+            params.push(Value::from(cursor.key));
+            params.push(Value::from(cursor.id as i64));
+            page_filter = format!(
+                "{page_filter} AND ({column}, c.id) {after} (?{}, ?{})",
+                params.len() - 1,
+                params.len()
+            );
+            offset = 0;
+        }
+
+        // One extra row tells whether another page follows
+        let sql = format!(
+            "SELECT c.id, c.names, c.book, c.page, c.side, c.entry, c.img_url, c.owned,
+                    c.version, COALESCE(c.created_at, ''), {column}
+             {from} WHERE {page_filter}
+             ORDER BY {column} {order}, c.id {order}
+             LIMIT {} OFFSET {offset}",
+            request.page_size + 1
+        );
+        let mut rows = conn
+            .prepare(&sql)?
+            .query_map(params_from_iter(params.iter()), |row| {
+                let entry = CardEntry {
+                    card: Card {
+                        index: row.get(0)?,
+                        names: row.get(1)?,
+                        book: row.get(2)?,
+                        page: row.get(3)?,
+                        side: row.get(4)?,
+                        entry: row.get(5)?,
+                        img_url: row.get(6)?,
+                        owned: row.get(7)?,
+                        version: row.get(8)?,
+                    },
+                    created_at: row.get(9)?,
+                };
+                let key: String = row.get(10)?;
+                Ok((entry, key))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let next_cursor = if rows.len() > request.page_size {
+            rows.truncate(request.page_size);
+            rows.last().map(|(entry, key)| {
+                serde_json::to_string(&Cursor {
+                    sort: request.sort,
+                    descending: request.descending,
+                    key: key.clone(),
+                    id: entry.card.index.0,
+                })
+                .unwrap_or_default()
+            })
+        } else {
+            None
+        };
+
+        Ok(CardPage {
+            entries: rows.into_iter().map(|(entry, _)| entry).collect(),
+            total,
+            next_cursor,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::query::QueryError;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute_batch(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned, created_at) VALUES
+                    (1, json_object('en', 'Bulbasaur', 'de', 'Bisasam'), 1, 1, 'A', 1, '', 1, '2024-01-03'),
+                    (4, json_object('en', 'Charmander', 'de', 'Glumanda'), 1, 1, 'A', 4, '', 1, '2024-01-01'),
+                    (7, json_object('en', 'Squirtle', 'de', 'Schiggy'), 1, 1, 'A', 7, '', 0, '2024-01-02'),
+                    (25, json_object('en', 'Pikachu', 'de', 'Pikachu'), 1, 2, 'A', 1, '', 1, '2024-01-04'),
+                    (26, json_object('en', 'Raichu', 'de', 'Raichu'), 1, 2, 'A', 2, '', 1, '2024-01-04');",
+            )
+            .unwrap();
+            conn
+        }
+
+        fn ids(page: &CardPage) -> Vec<usize> {
+            page.entries
+                .iter()
+                .map(|entry| entry.card.index.0)
+                .collect()
+        }
+
+        #[test]
+        fn test_double_page_filter() {
+            let conn = setup();
+            let request = CardPageRequest::new(CardFilter::owned_on_double_page(1), 24);
+            let page = page(&conn, &request).unwrap();
+            assert_eq!(ids(&page), vec![1, 4]);
+            assert_eq!(page.total, 2);
+            assert_eq!(page.next_cursor, None);
+        }
+
+        #[test]
+        fn test_cursor_walks_all_pages() {
+            let conn = setup();
+            let mut request = CardPageRequest::new(CardFilter::default(), 2);
+            request.sort = CardSort::Added;
+            request.descending = true;
+
+            let mut seen = Vec::new();
+            loop {
+                let page = page(&conn, &request).unwrap();
+                assert_eq!(page.total, 5);
+                seen.extend(ids(&page));
+                match page.next_cursor {
+                    Some(cursor) => request.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(seen, vec![26, 25, 1, 7, 4]);
+        }
+
+        #[test]
+        fn test_page_numbers_and_query_filter() {
+            let conn = setup();
+            let mut request = CardPageRequest::new(
+                CardFilter {
+                    owned: Some(true),
+                    indices: None,
+                    query: Some("gen:1 -name:raichu".to_string()),
+                },
+                2,
+            );
+            request.sort = CardSort::Name(NameLanguage::De);
+            request.page = 2;
+
+            let page = page(&conn, &request).unwrap();
+            assert_eq!(page.total, 3);
+            assert_eq!(ids(&page), vec![25]);
+        }
+
+        #[test]
+        fn test_rejects_foreign_cursor_and_bad_query() {
+            let conn = setup();
+            let mut request = CardPageRequest::new(CardFilter::default(), 2);
+            let cursor = page(&conn, &request).unwrap().next_cursor;
+
+            request.sort = CardSort::Name(NameLanguage::En);
+            request.cursor = cursor;
+            let err = page(&conn, &request).unwrap_err();
+            assert!(err.downcast_ref::<InvalidCursor>().is_some());
+
+            request.cursor = Some("garbage".to_string());
+            assert!(page(&conn, &request).is_err());
+
+            let mut request = CardPageRequest::new(CardFilter::default(), 2);
+            request.filter.query = Some("colour:red".to_string());
+            let err = page(&conn, &request).unwrap_err();
+            assert!(err.downcast_ref::<QueryError>().is_some());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_problems() {
+        let mut request = CardPageRequest::new(CardFilter::default(), 24);
+        assert_eq!(request.problem(), None);
+
+        request.page_size = MAX_PAGE_SIZE + 1;
+        assert!(request.problem().is_some());
+
+        request.page_size = 24;
+        request.page = 0;
+        assert!(request.problem().is_some());
+
+        request.page = 1;
+        request.filter.indices = Some((30, 20));
+        assert!(request.problem().is_some());
+    }
+}
@@ -4,11 +4,15 @@ use std::collections::HashMap;
 use std::sync::OnceLock;
 
 use crate::{
-    card::{Index, Name},
-    csv_record,
+    card::{Index, Name, NameLanguage, Names},
+    config, csv_record,
+    names::{self, NameNotFound},
 };
 
 /// Global cache for Pokemon name overrides (for CSV lookups)
+///
+/// Spelling variants are handled by [`names::normalize`], the file is only a
+/// last resort for species whose API and CSV names differ in other ways.
 static NAME_OVERRIDES: OnceLock<HashMap<usize, String>> = OnceLock::new();
 
 /// Loads the Pokemon name override lookup table from the JSON file
@@ -43,19 +47,25 @@ fn get_name_overrides() -> &'static HashMap<usize, String> {
     NAME_OVERRIDES.get_or_init(load_name_overrides)
 }
 
+/// Pokemon types as PokeAPI names them, the type ID is the position plus one
+pub const POKEMON_TYPES: [&str; 18] = [
+    "normal", "fighting", "flying", "poison", "ground", "rock", "bug", "ghost", "steel", "fire",
+    "water", "grass", "electric", "psychic", "ice", "dragon", "dark", "fairy",
+];
+
 #[derive(Debug, Clone)]
 pub struct PokeApi;
 
 impl PokeApi {
-    // Language IDs in the CSV data
-    const GERMAN_LANG_ID: u8 = 6;
-    const ENGLISH_LANG_ID: u8 = 9;
-
+    /// Resolves a name in any language of the species CSV to its Pokedex number
+    ///
+    /// Fails with [`NameNotFound`], including spelling suggestions, when no
+    /// name matches.
     pub async fn get_id(base_url: &str, language_url: &str, name: &Name) -> Result<usize> {
-        let name_normalized = name.0.replace(" ", "-");
+        let slug = names::api_slug(&name.0);
 
         // Try to fetch directly with the provided name (assuming English)
-        match PokeApi::fetch_pokemon_json(base_url, &name_normalized).await {
+        match PokeApi::fetch_pokemon_json(base_url, &slug).await {
             Ok(value) => {
                 let id = value["id"].to_string().parse::<usize>()?;
                 Ok(id)
@@ -65,19 +75,20 @@ impl PokeApi {
                 let csv_content = PokeApi::make_reqwest(language_url).await?;
 
                 // Find species ID from the provided name
-                let species_id = PokeApi::find_species_id_by_name(&csv_content, &name_normalized)?
-                    .ok_or_else(|| {
-                        anyhow!(
-                            "No german or english version found for name: {}",
-                            name_normalized
-                        )
-                    })?;
+                let Some(species_id) = PokeApi::find_species_id_by_name(&csv_content, &name.0)?
+                else {
+                    return Err(NameNotFound {
+                        query: name.0.clone(),
+                        suggestions: PokeApi::suggest_names(&csv_content, &name.0)?,
+                    }
+                    .into());
+                };
 
                 // Get the English name for this species
                 let english_name = PokeApi::find_name_by_species_id(
                     &csv_content,
                     species_id,
-                    Self::ENGLISH_LANG_ID,
+                    NameLanguage::En.pokeapi_id(),
                 )?
                 .ok_or_else(|| {
                     anyhow!(
@@ -87,19 +98,18 @@ impl PokeApi {
                 })?;
 
                 // Fetch the pokemon data using the English name
-                let english_name_normalized = english_name.replace(" ", "-");
-                let value = PokeApi::fetch_pokemon_json(base_url, &english_name_normalized).await?;
+                let value =
+                    PokeApi::fetch_pokemon_json(base_url, &names::api_slug(&english_name)).await?;
                 let id = value["id"].to_string().parse::<usize>()?;
                 Ok(id)
             }
         }
     }
 
-    pub async fn get_names(
-        index: &Index,
-        base_url: &str,
-        language_url: &str,
-    ) -> Result<Vec<String>> {
+    /// The names of a Pokemon in every [`NameLanguage`] the species CSV has
+    ///
+    /// The English name is the PokeAPI species name, all names are lowercase.
+    pub async fn get_names(index: &Index, base_url: &str, language_url: &str) -> Result<Names> {
         // Fetch English name from the API
         let url = format!("{}{}/", base_url, index.0);
         let resp = PokeApi::make_reqwest(&url).await?;
@@ -113,7 +123,7 @@ impl PokeApi {
             .replace("\"", "")
             .to_lowercase();
 
-        // Fetch CSV data to get the German name
+        // Fetch CSV data to get the other languages
         let csv_content = PokeApi::make_reqwest(language_url).await?;
 
         // Find the species ID for this English name
@@ -145,19 +155,9 @@ impl PokeApi {
             }
         };
 
-        // Get the German name for this species
-        let german_name =
-            PokeApi::find_name_by_species_id(&csv_content, species_id, Self::GERMAN_LANG_ID)?
-                .ok_or_else(|| {
-                    anyhow!(
-                "Couldn't find german name in csv file: URL: {}\nspecies ID: {}\nenglish name: {}",
-                language_url,
-                species_id,
-                english_name
-            )
-                })?;
-
-        Ok(vec![english_name, german_name.to_lowercase()])
+        let mut names = PokeApi::find_names_by_species_id(&csv_content, species_id)?;
+        names.insert(NameLanguage::En, Name(english_name));
+        Ok(names)
     }
 
     async fn make_reqwest(url: &str) -> Result<String> {
@@ -169,18 +169,46 @@ impl PokeApi {
             .context("Couldn't fetch text from URL")
     }
 
-    /// Searches CSV data for a pokemon species ID by name (case-insensitive)
+    /// Searches CSV data for a pokemon species ID by name, comparing
+    /// [`names::normalize`]d names
     fn find_species_id_by_name(csv_content: &str, search_name: &str) -> Result<Option<u32>> {
+        let search_name = names::normalize(search_name);
+        if search_name.is_empty() {
+            return Ok(None);
+        }
         let mut rdr = csv::Reader::from_reader(csv_content.as_bytes());
         for result in rdr.deserialize() {
             let record: csv_record::Record = result.context("Couldn't parse csv record")?;
-            if search_name.to_lowercase() == record.name.to_lowercase() {
+            if search_name == names::normalize(&record.name) {
                 return Ok(Some(record.pokemon_species_id));
             }
         }
         Ok(None)
     }
 
+    /// Names in the CSV data close to `search_name`, in the languages names
+    /// are shown in, see [`config::NamesConfig`]
+    fn suggest_names(csv_content: &str, search_name: &str) -> Result<Vec<String>> {
+        let shown: Vec<u8> = config::get()
+            .names
+            .shown()
+            .iter()
+            .map(NameLanguage::pokeapi_id)
+            .collect();
+        let mut rdr = csv::Reader::from_reader(csv_content.as_bytes());
+        let mut candidates = Vec::new();
+        for result in rdr.deserialize() {
+            let record: csv_record::Record = result.context("Couldn't parse csv record")?;
+            if shown.contains(&record.local_language_id) {
+                candidates.push(record.name);
+            }
+        }
+        Ok(names::suggest(
+            search_name,
+            candidates.iter().map(String::as_str),
+        ))
+    }
+
     /// Finds a pokemon name in the CSV data by species ID and language ID
     fn find_name_by_species_id(
         csv_content: &str,
@@ -197,6 +225,30 @@ impl PokeApi {
         Ok(None)
     }
 
+    /// Every name of a species in the CSV data, lowercase, keyed by language
+    fn find_names_by_species_id(csv_content: &str, species_id: u32) -> Result<Names> {
+        Ok(PokeApi::names_by_species(csv_content)?
+            .remove(&species_id)
+            .unwrap_or_default())
+    }
+
+    /// The names of every species in the CSV data, see
+    /// [`PokeApi::find_names_by_species_id`]
+    fn names_by_species(csv_content: &str) -> Result<HashMap<u32, Names>> {
+        let mut species: HashMap<u32, Names> = HashMap::new();
+        let mut rdr = csv::Reader::from_reader(csv_content.as_bytes());
+        for result in rdr.deserialize() {
+            let record: csv_record::Record = result.context("Couldn't parse csv record")?;
+            if let Some(language) = NameLanguage::from_pokeapi_id(record.local_language_id) {
+                species
+                    .entry(record.pokemon_species_id)
+                    .or_default()
+                    .insert(language, Name(record.name.to_lowercase()));
+            }
+        }
+        Ok(species)
+    }
+
     /// Fetches pokemon data from the API and parses the JSON response
     async fn fetch_pokemon_json(base_url: &str, name: &str) -> Result<Value> {
         let url = format!("{}{}/", base_url, name);
@@ -208,6 +260,166 @@ impl PokeApi {
     }
 }
 
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use rusqlite::{params, Connection};
+
+    impl PokeApi {
+        /// Downloads the species CSV for [`PokeApi::fill_missing_names`]
+        pub async fn species_csv(language_url: &str) -> Result<String> {
+            PokeApi::make_reqwest(language_url).await
+        }
+
+        /// Adds the names missing from stored cards, returns how many cards changed
+        ///
+        /// Cards stored before every language was kept only have English and
+        /// German names. Stored names are never replaced, the Pokedex number
+        /// of a card is its species ID.
+        pub fn fill_missing_names(conn: &mut Connection, csv_content: &str) -> Result<usize> {
+            let species = PokeApi::names_by_species(csv_content)?;
+
+            let tx = conn.transaction()?;
+            let cards = tx
+                .prepare("SELECT id, names FROM cards ORDER BY id")?
+                .query_map([], |row| {
+                    Ok((row.get::<_, u32>(0)?, row.get::<_, Names>(1)?))
+                })?
+                .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+            let mut changed = 0;
+            for (id, mut names) in cards {
+                let before = names.0.len();
+                for (language, name) in species.get(&id).into_iter().flat_map(|n| &n.0) {
+                    names.0.entry(*language).or_insert_with(|| name.clone());
+                }
+                if names.0.len() != before {
+                    tx.execute(
+                        "UPDATE cards SET names = ?1 WHERE id = ?2",
+                        params![names, id],
+                    )?;
+                    changed += 1;
+                }
+            }
+            tx.commit()?;
+            Ok(changed)
+        }
+
+        /// Downloads the Pokemon types CSV for [`PokeApi::store_types`]
+        pub async fn types_csv(types_url: &str) -> Result<String> {
+            PokeApi::make_reqwest(types_url).await
+        }
+
+        /// True once any Pokemon types are stored
+        pub fn types_stored(conn: &Connection) -> Result<bool> {
+            Ok(
+                conn.query_row("SELECT EXISTS (SELECT 1 FROM pokemon_types)", [], |row| {
+                    row.get(0)
+                })?,
+            )
+        }
+
+        /// Replaces the stored Pokemon types, returns how many Pokemon have one
+        ///
+        /// Only default forms are kept, their ID is the Pokedex number. Types
+        /// missing from [`POKEMON_TYPES`], like `stellar`, are skipped.
+        pub fn store_types(conn: &mut Connection, csv_content: &str) -> Result<usize> {
+            let mut rdr = csv::Reader::from_reader(csv_content.as_bytes());
+            let mut rows = Vec::new();
+            for result in rdr.deserialize() {
+                let record: csv_record::TypeRecord = result.context("Couldn't parse csv record")?;
+                let known = record
+                    .type_id
+                    .checked_sub(1)
+                    .and_then(|i| POKEMON_TYPES.get(i));
+                // Alternate forms have IDs from 10001 on
+                match known {
+                    Some(name) if record.pokemon_id < 10_000 => {
+                        rows.push((record.pokemon_id, record.slot, *name))
+                    }
+                    _ => {}
+                }
+            }
+
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM pokemon_types", [])?;
+            for (pokemon_id, slot, name) in &rows {
+                tx.execute(
+                    "INSERT INTO pokemon_types (pokemon_id, slot, type) VALUES (?1, ?2, ?3)",
+                    params![pokemon_id, slot, name],
+                )?;
+            }
+            let pokemon = tx.query_row(
+                "SELECT COUNT(DISTINCT pokemon_id) FROM pokemon_types",
+                [],
+                |row| row.get(0),
+            )?;
+            tx.commit()?;
+            Ok(pokemon)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        #[test]
+        fn test_fill_missing_names_keeps_stored_names() {
+            let mut conn = test_connection();
+            conn.execute_batch(
+                r#"INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES
+                    (1, '{"en": "bulbasaur", "de": "bisasam"}', 1, 1, 'A', 1, '', 1),
+                    (4, '{"en": "charmander", "de": "glumanda"}', 1, 1, 'A', 4, '', 0);"#,
+            )
+            .unwrap();
+            let csv_data = "pokemon_species_id,local_language_id,name,genus\n1,1,フシギダネ,たねポケモン\n1,6,Bisasam-Typo,Samen-Pokémon\n1,9,Bulbasaur,Seed Pokémon";
+
+            assert_eq!(PokeApi::fill_missing_names(&mut conn, csv_data).unwrap(), 1);
+            let names: Names = conn
+                .query_row("SELECT names FROM cards WHERE id = 1", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(names.get(NameLanguage::Ja), Some(&Name::new("フシギダネ")));
+            assert_eq!(names.get(NameLanguage::De), Some(&Name::new("bisasam")));
+            assert_eq!(PokeApi::fill_missing_names(&mut conn, csv_data).unwrap(), 0);
+
+            let katakana = crate::search::search(&conn, "フシギ", 10).unwrap();
+            assert_eq!(katakana.len(), 1);
+            assert_eq!(katakana[0].card.index.0, 1);
+        }
+
+        #[test]
+        fn test_store_types_replaces_all_types() {
+            let mut conn = test_connection();
+            assert!(!PokeApi::types_stored(&conn).unwrap());
+            let csv_data = "pokemon_id,type_id,slot\n1,12,1\n1,4,2\n4,10,1\n10034,10,1\n25,19,1";
+
+            assert_eq!(PokeApi::store_types(&mut conn, csv_data).unwrap(), 2);
+            assert!(PokeApi::types_stored(&conn).unwrap());
+            let types: Vec<(u32, String)> = conn
+                .prepare("SELECT pokemon_id, type FROM pokemon_types ORDER BY pokemon_id, slot")
+                .unwrap()
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(
+                types,
+                vec![
+                    (1, "grass".to_string()),
+                    (1, "poison".to_string()),
+                    (4, "fire".to_string())
+                ]
+            );
+
+            assert_eq!(
+                PokeApi::store_types(&mut conn, "pokemon_id,type_id,slot\n7,11,1").unwrap(),
+                1
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,7 +480,7 @@ mod tests {
     fn test_find_name_by_species_id_english() {
         let csv_data = "pokemon_species_id,local_language_id,name,genus\n1,9,bulbasaur,Seed Pokémon\n1,6,bisasam,Samen-Pokémon\n25,9,pikachu,Mouse Pokémon";
 
-        let result = PokeApi::find_name_by_species_id(csv_data, 1, PokeApi::ENGLISH_LANG_ID);
+        let result = PokeApi::find_name_by_species_id(csv_data, 1, NameLanguage::En.pokeapi_id());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), Some("bulbasaur".to_string()));
     }
@@ -277,7 +489,7 @@ mod tests {
     fn test_find_name_by_species_id_german() {
         let csv_data = "pokemon_species_id,local_language_id,name,genus\n1,9,bulbasaur,Seed Pokémon\n1,6,bisasam,Samen-Pokémon\n25,9,pikachu,Mouse Pokémon";
 
-        let result = PokeApi::find_name_by_species_id(csv_data, 1, PokeApi::GERMAN_LANG_ID);
+        let result = PokeApi::find_name_by_species_id(csv_data, 1, NameLanguage::De.pokeapi_id());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), Some("bisasam".to_string()));
     }
@@ -286,7 +498,7 @@ mod tests {
     fn test_find_name_by_species_id_not_found() {
         let csv_data = "pokemon_species_id,local_language_id,name,genus\n1,9,bulbasaur,Seed Pokémon\n1,6,bisasam,Samen-Pokémon";
 
-        let result = PokeApi::find_name_by_species_id(csv_data, 999, PokeApi::ENGLISH_LANG_ID);
+        let result = PokeApi::find_name_by_species_id(csv_data, 999, NameLanguage::En.pokeapi_id());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), None);
     }
@@ -305,7 +517,7 @@ mod tests {
     fn test_find_name_by_species_id_empty_csv() {
         let csv_data = "pokemon_species_id,local_language_id,name,genus\n";
 
-        let result = PokeApi::find_name_by_species_id(csv_data, 1, PokeApi::ENGLISH_LANG_ID);
+        let result = PokeApi::find_name_by_species_id(csv_data, 1, NameLanguage::En.pokeapi_id());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), None);
     }
@@ -336,6 +548,33 @@ mod tests {
         assert_eq!(result.unwrap(), Some(439));
     }
 
+    #[test]
+    fn test_find_species_id_with_spelling_variants() {
+        let csv_data = "pokemon_species_id,local_language_id,name,genus\n29,9,Nidoran♀,Poison Pin Pokémon\n83,9,Farfetch’d,Wild Duck Pokémon\n122,9,Mr. Mime,Barrier Pokémon\n669,9,Flabébé,Single Bloom Pokémon\n772,9,Type: Null,Synthetic Pokémon";
+
+        for (search, id) in [
+            ("nidoran-f", 29),
+            ("Nidoran f", 29),
+            ("farfetchd", 83),
+            ("Farfetch'd", 83),
+            ("mr-mime", 122),
+            ("Mr Mime", 122),
+            ("flabebe", 669),
+            ("type-null", 772),
+        ] {
+            let result = PokeApi::find_species_id_by_name(csv_data, search);
+            assert_eq!(result.unwrap(), Some(id), "searching {search}");
+        }
+    }
+
+    #[test]
+    fn test_suggest_names_only_shown_languages() {
+        let csv_data = "pokemon_species_id,local_language_id,name,genus\n25,9,Pikachu,Mouse Pokémon\n25,6,Pikachu,Maus-Pokémon\n25,5,Pikachoo,Pokémon Souris\n172,9,Pichu,Tiny Mouse Pokémon";
+
+        let result = PokeApi::suggest_names(csv_data, "pikchu");
+        assert_eq!(result.unwrap(), vec!["Pichu", "Pikachu"]);
+    }
+
     #[test]
     fn test_malformed_csv_error_handling() {
         // CSV with missing columns
@@ -364,12 +603,38 @@ mod tests {
         assert_eq!(result.unwrap(), Some("bulbizarre".to_string()));
     }
 
+    #[test]
+    fn test_find_names_by_species_id() {
+        let csv_data = "pokemon_species_id,local_language_id,name,genus\n1,1,フシギダネ,たねポケモン\n1,2,Fushigidane,\n1,3,이상해씨,씨앗포켓몬\n1,5,Bulbizarre,Pokémon Graine\n1,6,Bisasam,Samen-Pokémon\n1,9,Bulbasaur,Seed Pokémon\n4,9,Charmander,Lizard Pokémon";
+
+        let names = PokeApi::find_names_by_species_id(csv_data, 1).unwrap();
+        assert_eq!(names.get(NameLanguage::Ja), Some(&Name::new("フシギダネ")));
+        assert_eq!(names.get(NameLanguage::Ko), Some(&Name::new("이상해씨")));
+        assert_eq!(names.get(NameLanguage::Fr), Some(&Name::new("bulbizarre")));
+        assert_eq!(names.get(NameLanguage::De), Some(&Name::new("bisasam")));
+        assert_eq!(names.get(NameLanguage::En), Some(&Name::new("bulbasaur")));
+        // Romaji has no language of its own, Spanish and Italian are missing here
+        assert_eq!(names.0.len(), 5);
+
+        assert!(PokeApi::find_names_by_species_id(csv_data, 999)
+            .unwrap()
+            .0
+            .is_empty());
+    }
+
     // ==================== Language Constants Tests ====================
 
     #[test]
     fn test_language_constants() {
-        assert_eq!(PokeApi::GERMAN_LANG_ID, 6);
-        assert_eq!(PokeApi::ENGLISH_LANG_ID, 9);
+        assert_eq!(NameLanguage::De.pokeapi_id(), 6);
+        assert_eq!(NameLanguage::En.pokeapi_id(), 9);
+        assert_eq!(NameLanguage::Ja.pokeapi_id(), 1);
+        for language in <NameLanguage as strum::IntoEnumIterator>::iter() {
+            assert_eq!(
+                NameLanguage::from_pokeapi_id(language.pokeapi_id()),
+                Some(language)
+            );
+        }
     }
 
     // ==================== Edge Case Tests ====================
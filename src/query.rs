@@ -0,0 +1,1041 @@
+//! Filter language of the collection search box
+//!
+//! A query like `owned:no gen:3 type:fire rarity>=holo tag:signed` is parsed into an
+//! [`Expr`] and translated to SQL over every Pokedex number of the binder,
+//! so cards that were never looked up still match `owned:no`. Terms are
+//! joined with AND, `OR` and parentheses group them, a leading `-` or `NOT`
+//! negates. A bare word or quoted text filters by name.
+//!
+//! | Field                    | Operators             | Values                          |
+//! |--------------------------|-----------------------|---------------------------------|
+//! | `owned`                  | `:` `=` `!=`          | `yes`, `no`                     |
+//! | `gen`                    | `:` `=` `!=` `<` `<=` `>` `>=` | 1 to 9                 |
+//! | `id`, `book`, `page`     | same as `gen`         | numbers, `page` is a double page|
+//! | `rarity`                 | same as `gen`         | `common` … `promo`, e.g. `holo` |
+//! | `expansion`              | `:` `=` `!=`          | abbreviation or part of the name|
+//! | `number`, `name`         | `:` `=` `!=`          | text, quote values with spaces  |
+//! | `type`                   | `:` `=` `!=`          | a Pokemon type, e.g. `fire`     |
+//! | `tag`                    | `:` `=` `!=`          | a card tag, e.g. `signed`       |
+//!
+//! Rarity and expansion filters match cards with at least one such print.
+//! Types come from [`crate::pokeapi::POKEMON_TYPES`], tags from [`crate::tags`].
+//! Errors carry the byte range of the offending token, see [`QueryError`].
+
+use crate::card::Rarity;
+use crate::pokeapi::POKEMON_TYPES;
+use dioxus::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+/// Most matches returned for one query
+pub const MAX_QUERY_RESULTS: usize = 240;
+
+/// Most parentheses and negations nested in one query
+pub const MAX_QUERY_DEPTH: usize = 64;
+
+/// First and last Pokedex number of each generation
+pub const GENERATIONS: [(u8, usize, usize); 9] = [
+    (1, 1, 151),
+    (2, 152, 251),
+    (3, 252, 386),
+    (4, 387, 493),
+    (5, 494, 649),
+    (6, 650, 721),
+    (7, 722, 809),
+    (8, 810, 905),
+    (9, 906, 1025),
+];
+
+/// Byte range in the query text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Why a query could not be parsed, `span` points at the offending token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message}")]
+pub struct QueryError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl QueryError {
+    /// HTTP status used for invalid queries
+    pub const STATUS: u16 = 400;
+
+    fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Extracts the parse error from a failed query, `None` for other errors
+    pub fn from_error(error: &ServerFnError) -> Option<QueryError> {
+        match error {
+            ServerFnError::ServerError {
+                code: Self::STATUS,
+                details: Some(details),
+                ..
+            } => serde_json::from_value(details.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// The server error sent for this parse error
+    pub fn into_error(self) -> ServerFnError {
+        ServerFnError::ServerError {
+            message: self.message.clone(),
+            code: Self::STATUS,
+            details: serde_json::to_value(&self).ok(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    pub fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+
+    pub fn sql(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Filter {
+    Owned(bool),
+    Generation(Comparison, u8),
+    Id(Comparison, usize),
+    Book(Comparison, usize),
+    Page(Comparison, usize),
+    Rarity(Comparison, Rarity),
+    Expansion(String),
+    Number(String),
+    Name(String),
+    Type(String),
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Filter(Filter),
+}
+
+/// A Pokedex number matching a query, `card` is `None` if it was never stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryMatch {
+    pub index: usize,
+    pub card: Option<crate::card::Card>,
+    /// `None` when the card has no prints
+    pub highest_rarity: Option<Rarity>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryResult {
+    /// All matches, `matches` holds at most [`MAX_QUERY_RESULTS`] of them
+    pub total: usize,
+    pub matches: Vec<QueryMatch>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Word(String),
+    Quoted(String),
+    Op(Comparison),
+    LParen,
+    RParen,
+    Minus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    tok: Tok,
+    span: Span,
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | ':' | '=' | '<' | '>' | '!' | '"')
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token = |tok: Tok, end: usize| Token {
+            tok,
+            span: Span { start, end },
+        };
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                let tok = if c == '(' { Tok::LParen } else { Tok::RParen };
+                tokens.push(token(tok, start + 1));
+            }
+            ':' | '=' => {
+                chars.next();
+                tokens.push(token(Tok::Op(Comparison::Eq), start + 1));
+            }
+            '!' | '<' | '>' => {
+                chars.next();
+                let equals = chars.next_if(|&(_, next)| next == '=').is_some();
+                let op = match (c, equals) {
+                    ('!', true) => Comparison::Ne,
+                    ('<', false) => Comparison::Lt,
+                    ('<', true) => Comparison::Le,
+                    ('>', false) => Comparison::Gt,
+                    ('>', true) => Comparison::Ge,
+                    _ => {
+                        return Err(QueryError::new(
+                            "`!` must be followed by `=`",
+                            Span {
+                                start,
+                                end: start + 1,
+                            },
+                        ))
+                    }
+                };
+                tokens.push(token(Tok::Op(op), start + 1 + usize::from(equals)));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                let mut closed = false;
+                for (i, c) in chars.by_ref() {
+                    if c == '"' {
+                        tokens.push(token(Tok::Quoted(text.clone()), i + 1));
+                        closed = true;
+                        break;
+                    }
+                    text.push(c);
+                }
+                if !closed {
+                    return Err(QueryError::new(
+                        "Unterminated quote",
+                        Span {
+                            start,
+                            end: input.len(),
+                        },
+                    ));
+                }
+            }
+            '-' if chars
+                .clone()
+                .nth(1)
+                .is_some_and(|(_, next)| !next.is_whitespace()) =>
+            {
+                chars.next();
+                tokens.push(token(Tok::Minus, start + 1));
+            }
+            _ => {
+                let mut end = start;
+                while let Some((i, c)) = chars.next_if(|&(_, c)| !is_separator(c)) {
+                    end = i + c.len_utf8();
+                }
+                tokens.push(token(Tok::Word(input[start..end].to_string()), end));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether `input` uses filter syntax rather than being a plain ID or name
+///
+/// True when a known field is directly followed by an operator and a value,
+/// so names like "Type: Null" are still looked up as names.
+pub fn is_query(input: &str) -> bool {
+    let Ok(tokens) = lex(input) else {
+        return false;
+    };
+    tokens.windows(3).any(|window| match window {
+        [Token {
+            tok: Tok::Word(field),
+            span: field_span,
+        }, Token {
+            tok: Tok::Op(_),
+            span: op_span,
+        }, Token {
+            tok: Tok::Word(_) | Tok::Quoted(_),
+            span: value_span,
+        }] => {
+            FIELDS.contains(&field.to_lowercase().as_str())
+                && field_span.end == op_span.start
+                && op_span.end == value_span.start
+        }
+        _ => false,
+    })
+}
+
+const FIELDS: [&str; 11] = [
+    "owned",
+    "gen",
+    "id",
+    "book",
+    "page",
+    "rarity",
+    "expansion",
+    "number",
+    "name",
+    "type",
+    "tag",
+];
+
+/// Parses `input` into an expression tree
+pub fn parse(input: &str) -> Result<Expr, QueryError> {
+    let tokens = lex(input)?;
+    if tokens.is_empty() {
+        return Err(QueryError::new("Empty query", Span { start: 0, end: 0 }));
+    }
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        len: input.len(),
+        depth: 0,
+    };
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(token) => Err(QueryError::new("Unmatched `)`", token.span)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    len: usize,
+    /// Parentheses and negations around the current token
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { tok: Tok::Word(word), .. }) if word == keyword)
+    }
+
+    fn end_span(&self) -> Span {
+        Span {
+            start: self.len,
+            end: self.len,
+        }
+    }
+
+    /// Parses one more level inside the token at `span`
+    fn nested(
+        &mut self,
+        span: Span,
+        parse: fn(&mut Self) -> Result<Expr, QueryError>,
+    ) -> Result<Expr, QueryError> {
+        if self.depth == MAX_QUERY_DEPTH {
+            return Err(QueryError::new(
+                format!("Nested more than {MAX_QUERY_DEPTH} levels deep"),
+                span,
+            ));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut items = vec![self.and()?];
+        while self.is_keyword("OR") {
+            self.next();
+            items.push(self.and()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::Or(items)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                None
+                | Some(Token {
+                    tok: Tok::RParen, ..
+                }) => break,
+                _ if self.is_keyword("OR") => break,
+                _ if self.is_keyword("AND") => {
+                    self.next();
+                }
+                _ => items.push(self.unary()?),
+            }
+        }
+        match items.len() {
+            0 => Err(QueryError::new(
+                "Expected a filter",
+                self.peek().map_or(self.end_span(), |token| token.span),
+            )),
+            1 => Ok(items.remove(0)),
+            _ => Ok(Expr::And(items)),
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, QueryError> {
+        if matches!(
+            self.peek(),
+            Some(Token {
+                tok: Tok::Minus,
+                ..
+            })
+        ) || self.is_keyword("NOT")
+        {
+            let span = self.next().map_or(self.end_span(), |token| token.span);
+            return Ok(Expr::Not(Box::new(self.nested(span, Self::unary)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(QueryError::new("Expected a filter", self.end_span()));
+        };
+        match token.tok {
+            Tok::LParen => {
+                let expr = self.nested(token.span, Self::or)?;
+                match self.next() {
+                    Some(Token {
+                        tok: Tok::RParen, ..
+                    }) => Ok(expr),
+                    _ => Err(QueryError::new("Missing `)`", token.span)),
+                }
+            }
+            Tok::RParen => Err(QueryError::new("Unmatched `)`", token.span)),
+            Tok::Op(_) => Err(QueryError::new(
+                "Expected a field name before the operator",
+                token.span,
+            )),
+            Tok::Minus => Err(QueryError::new("Expected a filter", token.span)),
+            Tok::Quoted(text) => Ok(Expr::Filter(Filter::Name(text))),
+            Tok::Word(word) => match self.peek() {
+                Some(Token {
+                    tok: Tok::Op(op), ..
+                }) => {
+                    let op = *op;
+                    let op_span = self.next().map(|t| t.span).unwrap_or(token.span);
+                    match self.next() {
+                        Some(Token {
+                            tok: Tok::Word(value) | Tok::Quoted(value),
+                            span,
+                        }) => field(&word, token.span, op, &value, span),
+                        _ => Err(QueryError::new(
+                            format!("Expected a value for `{word}`"),
+                            op_span,
+                        )),
+                    }
+                }
+                _ => Ok(Expr::Filter(Filter::Name(word))),
+            },
+        }
+    }
+}
+
+fn field(
+    name: &str,
+    name_span: Span,
+    op: Comparison,
+    value: &str,
+    value_span: Span,
+) -> Result<Expr, QueryError> {
+    let number = || {
+        value
+            .parse::<usize>()
+            .map_err(|_| QueryError::new(format!("`{value}` is not a number"), value_span))
+    };
+    let equality = || match op {
+        Comparison::Eq | Comparison::Ne => Ok(()),
+        _ => Err(QueryError::new(
+            format!("`{name}` only supports `:`, `=` and `!=`"),
+            name_span,
+        )),
+    };
+    let text = |filter: fn(String) -> Filter| -> Result<Expr, QueryError> {
+        equality()?;
+        let expr = Expr::Filter(filter(value.to_string()));
+        Ok(match op {
+            Comparison::Ne => Expr::Not(Box::new(expr)),
+            _ => expr,
+        })
+    };
+
+    let filter = match name.to_lowercase().as_str() {
+        "owned" => {
+            equality()?;
+            let owned = match value.to_lowercase().as_str() {
+                "yes" | "y" | "true" | "1" => true,
+                "no" | "n" | "false" | "0" => false,
+                _ => {
+                    return Err(QueryError::new(
+                        format!("`owned` is `yes` or `no`, not `{value}`"),
+                        value_span,
+                    ))
+                }
+            };
+            Filter::Owned(owned == (op == Comparison::Eq))
+        }
+        "gen" => match value.parse::<u8>() {
+            Ok(generation) if GENERATIONS.iter().any(|(g, _, _)| *g == generation) => {
+                Filter::Generation(op, generation)
+            }
+            _ => {
+                return Err(QueryError::new(
+                    format!(
+                        "`{value}` is not a generation from 1 to {}",
+                        GENERATIONS.len()
+                    ),
+                    value_span,
+                ))
+            }
+        },
+        "id" => Filter::Id(op, number()?),
+        "book" => Filter::Book(op, number()?),
+        "page" => Filter::Page(op, number()?),
+        "rarity" => match parse_rarity(value) {
+            Some(rarity) => Filter::Rarity(op, rarity),
+            None => {
+                return Err(QueryError::new(
+                    format!("Unknown rarity `{value}`"),
+                    value_span,
+                ))
+            }
+        },
+        "expansion" => return text(Filter::Expansion),
+        "number" => return text(Filter::Number),
+        "name" => return text(Filter::Name),
+        "type" => {
+            equality()?;
+            let value = value.to_lowercase();
+            match POKEMON_TYPES.iter().find(|t| **t == value) {
+                Some(pokemon_type) => {
+                    let expr = Expr::Filter(Filter::Type(pokemon_type.to_string()));
+                    return Ok(match op {
+                        Comparison::Ne => Expr::Not(Box::new(expr)),
+                        _ => expr,
+                    });
+                }
+                None => {
+                    return Err(QueryError::new(
+                        format!(
+                            "Unknown type `{value}`, use one of {}",
+                            POKEMON_TYPES.join(", ")
+                        ),
+                        value_span,
+                    ))
+                }
+            }
+        }
+        "tag" => return text(|tag| Filter::Tag(tag.to_lowercase())),
+        _ => {
+            return Err(QueryError::new(
+                format!("Unknown field `{name}`, use one of {}", FIELDS.join(", ")),
+                name_span,
+            ))
+        }
+    };
+    Ok(Expr::Filter(filter))
+}
+
+/// Accepts rarity names without symbols and spaces and short aliases
+fn parse_rarity(value: &str) -> Option<Rarity> {
+    let key: String = value
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let alias = match key.as_str() {
+        "c" => Some(Rarity::Common),
+        "u" => Some(Rarity::Uncommon),
+        "r" => Some(Rarity::Rare),
+        "holo" => Some(Rarity::HoloRare),
+        "reverse" | "reverseholo" | "rh" => Some(Rarity::ReverseHoloRare),
+        "double" | "rr" => Some(Rarity::DoubleRare),
+        "ultra" | "ur" => Some(Rarity::UltraRare),
+        "secret" | "sr" => Some(Rarity::SecretRare),
+        _ => None,
+    };
+    alias.or_else(|| {
+        Rarity::iter().find(|rarity| {
+            // Drop the symbol suffix, "Holo Rare ★H" is spelled "holorare"
+            let name: String = rarity
+                .stored()
+                .split_whitespace()
+                .filter(|word| word.chars().all(char::is_alphabetic))
+                .flat_map(str::chars)
+                .flat_map(char::to_lowercase)
+                .collect();
+            name == key
+        })
+    })
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::card::Card;
+    use crate::{config, page_view};
+    use anyhow::Result;
+    use rusqlite::{params_from_iter, types::Value, Connection};
+
+    /// Appends the SQL condition for `expr` over `d.id` and `cards c`
+    ///
+    /// Numbered parameters continue after the ones already in `params`.
+    pub(crate) fn condition(expr: &Expr, params: &mut Vec<Value>) -> String {
+        let mut param = |value: Value| {
+            params.push(value);
+            format!("?{}", params.len())
+        };
+
+        match expr {
+            Expr::And(items) | Expr::Or(items) => {
+                let joiner = if matches!(expr, Expr::And(_)) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+                let parts: Vec<String> = items.iter().map(|item| condition(item, params)).collect();
+                format!("({})", parts.join(joiner))
+            }
+            Expr::Not(inner) => format!("NOT {}", condition(inner, params)),
+            Expr::Filter(filter) => match filter {
+                Filter::Owned(owned) => format!("(COALESCE(c.owned, 0) = {})", u8::from(*owned)),
+                Filter::Generation(op, generation) => {
+                    let (_, first, last) = GENERATIONS
+                        .iter()
+                        .find(|(g, _, _)| g == generation)
+                        .copied()
+                        .unwrap_or(GENERATIONS[0]);
+                    match op {
+                        Comparison::Eq => format!("(d.id BETWEEN {first} AND {last})"),
+                        Comparison::Ne => format!("(d.id NOT BETWEEN {first} AND {last})"),
+                        Comparison::Lt => format!("(d.id < {first})"),
+                        Comparison::Le => format!("(d.id <= {last})"),
+                        Comparison::Gt => format!("(d.id > {last})"),
+                        Comparison::Ge => format!("(d.id >= {first})"),
+                    }
+                }
+                Filter::Id(op, id) => {
+                    format!("(d.id {} {})", op.sql(), param(Value::from(*id as i64)))
+                }
+                Filter::Book(op, book) => {
+                    let per_book = config::layout().cards_per_book;
+                    format!(
+                        "(((d.id - 1) / {per_book} + 1) {} {})",
+                        op.sql(),
+                        param(Value::from(*book as i64))
+                    )
+                }
+                Filter::Page(op, page) => {
+                    let per_page = config::layout().cards_per_double_page;
+                    format!(
+                        "(((d.id - 1) / {per_page} + 1) {} {})",
+                        op.sql(),
+                        param(Value::from(*page as i64))
+                    )
+                }
+                Filter::Rarity(op, rarity) => {
+                    let names: Vec<String> = Rarity::iter()
+                        .filter(|candidate| op.holds(candidate, rarity))
+                        .map(|candidate| param(Value::from(candidate.stored().to_string())))
+                        .collect();
+                    if names.is_empty() {
+                        "0".to_string()
+                    } else {
+                        format!(
+                            "EXISTS (SELECT 1 FROM card_expansions ce \
+                             WHERE ce.card_id = d.id AND ce.rarity IN ({}))",
+                            names.join(", ")
+                        )
+                    }
+                }
+                Filter::Expansion(expansion) => {
+                    let value = param(Value::from(expansion.clone()));
+                    format!(
+                        "EXISTS (SELECT 1 FROM card_expansions ce \
+                         JOIN expansions e ON e.id = ce.expansion_id \
+                         WHERE ce.card_id = d.id AND (e.abbreviation = {value} COLLATE NOCASE \
+                         OR instr(lower(e.name), lower({value})) > 0))"
+                    )
+                }
+                Filter::Number(number) => format!(
+                    "EXISTS (SELECT 1 FROM card_expansions ce \
+                     WHERE ce.card_id = d.id AND ce.card_number = {} COLLATE NOCASE)",
+                    param(Value::from(number.trim().to_string()))
+                ),
+                Filter::Type(pokemon_type) => format!(
+                    "EXISTS (SELECT 1 FROM pokemon_types pt \
+                     WHERE pt.pokemon_id = d.id AND pt.type = {})",
+                    param(Value::from(pokemon_type.clone()))
+                ),
+                Filter::Tag(tag) => format!(
+                    "EXISTS (SELECT 1 FROM card_tags t WHERE t.card_id = d.id AND t.tag = {})",
+                    param(Value::from(tag.clone()))
+                ),
+                Filter::Name(name) => match crate::search::fts_column_query("names", name) {
+                    Some(query) => format!(
+                        "d.id IN (SELECT rowid FROM card_search WHERE card_search MATCH {})",
+                        param(Value::from(query))
+                    ),
+                    None => "0".to_string(),
+                },
+            },
+        }
+    }
+
+    /// Fails with a [`QueryError`] on the `type` field while no Pokemon types
+    /// are stored, they are fetched from PokeAPI on the first start
+    pub fn check_types(conn: &Connection, input: &str) -> Result<()> {
+        let type_span = lex(input)?.windows(2).find_map(|window| match window {
+            [Token {
+                tok: Tok::Word(field),
+                span,
+            }, Token {
+                tok: Tok::Op(_), ..
+            }] if field.eq_ignore_ascii_case("type") => Some(*span),
+            _ => None,
+        });
+        match type_span {
+            Some(span) if !crate::pokeapi::PokeApi::types_stored(conn)? => Err(QueryError::new(
+                "Pokémon types are not fetched from PokeAPI yet, try `type` again later",
+                span,
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs `expr` over every Pokedex number of the binder, in binder order
+    pub fn run(conn: &Connection, expr: &Expr, limit: usize) -> Result<QueryResult> {
+        let mut params = vec![Value::from(config::layout().max_pokemon as i64)];
+        let condition = condition(expr, &mut params);
+        let from = format!(
+            "WITH RECURSIVE dex(id) AS (SELECT 1 UNION ALL SELECT id + 1 FROM dex WHERE id < ?1) \
+             {{}} FROM dex d LEFT JOIN cards c ON c.id = d.id WHERE {condition}"
+        );
+
+        let total: usize = conn.query_row(
+            &from.replacen("{}", "SELECT COUNT(*)", 1),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+
+        let columns = format!(
+            "SELECT d.id, c.id, c.names, c.book, c.page, c.side, c.entry, c.img_url, \
+             c.owned, c.version, (SELECT MAX({}) FROM card_expansions ce WHERE ce.card_id = d.id)",
+            page_view::rarity_rank("ce.rarity")
+        );
+        let sql = format!(
+            "{} ORDER BY d.id LIMIT {limit}",
+            from.replacen("{}", &columns, 1)
+        );
+        let matches = conn
+            .prepare(&sql)?
+            .query_map(params_from_iter(params.iter()), |row| {
+                let stored: Option<usize> = row.get(1)?;
+                Ok(QueryMatch {
+                    index: row.get(0)?,
+                    card: match stored {
+                        Some(_) => Some(Card {
+                            index: row.get(1)?,
+                            names: row.get(2)?,
+                            book: row.get(3)?,
+                            page: row.get(4)?,
+                            side: row.get(5)?,
+                            entry: row.get(6)?,
+                            img_url: row.get(7)?,
+                            owned: row.get(8)?,
+                            version: row.get(9)?,
+                        }),
+                        None => None,
+                    },
+                    highest_rarity: page_view::rarity_from_rank(row.get(10)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        Ok(QueryResult { total, matches })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute_batch(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES
+                    (4, json_object('en', 'charmander', 'de', 'glumanda'), 1, 1, 'A', 4, '', 1),
+                    (25, json_object('en', 'pikachu', 'de', 'pikachu'), 1, 2, 'A', 1, '', 1),
+                    (255, json_object('en', 'torchic', 'de', 'flemmli'), 1, 11, 'B', 3, '', 0);
+                 INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES
+                    (4, (SELECT MIN(id) FROM expansions), '46', 'Common ●'),
+                    (25, (SELECT MIN(id) FROM expansions), '58', 'Holo Rare ★H');",
+            )
+            .unwrap();
+            conn
+        }
+
+        fn ids(conn: &Connection, query: &str) -> Vec<usize> {
+            run(conn, &parse(query).unwrap(), MAX_QUERY_RESULTS)
+                .unwrap()
+                .matches
+                .iter()
+                .map(|m| m.index)
+                .collect()
+        }
+
+        #[test]
+        fn test_owned_and_generation() {
+            let conn = setup();
+            assert_eq!(ids(&conn, "owned:yes"), vec![4, 25]);
+            assert_eq!(ids(&conn, "owned:yes gen:3"), Vec::<usize>::new());
+
+            let unowned_gen_3 = run(&conn, &parse("owned:no gen:3").unwrap(), 5).unwrap();
+            assert_eq!(unowned_gen_3.total, 386 - 252 + 1);
+            assert_eq!(unowned_gen_3.matches.len(), 5);
+            assert!(unowned_gen_3.matches[0].card.is_none());
+            assert_eq!(ids(&conn, "owned:no gen:3 name:flemmli"), vec![255]);
+        }
+
+        #[test]
+        fn test_prints_and_names() {
+            let conn = setup();
+            let abbreviation: String = conn
+                .query_row(
+                    "SELECT abbreviation FROM expansions ORDER BY id LIMIT 1",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+
+            assert_eq!(ids(&conn, "rarity>=holo"), vec![25]);
+            assert_eq!(ids(&conn, "rarity<holo"), vec![4]);
+            assert_eq!(
+                ids(&conn, &format!("expansion:\"{abbreviation}\"")),
+                vec![4, 25]
+            );
+            assert_eq!(ids(&conn, "number:58"), vec![25]);
+            assert_eq!(ids(&conn, "glum OR pika"), vec![4, 25]);
+            assert_eq!(ids(&conn, "owned:yes -name:pikachu"), vec![4]);
+
+            let result = run(&conn, &parse("id:25").unwrap(), 1).unwrap();
+            assert!(result.matches[0].highest_rarity >= Some(Rarity::HoloRare));
+        }
+
+        #[test]
+        fn test_rarity_binds_stored_names() {
+            let conn = setup();
+            assert_eq!(ids(&conn, "rarity>=holo"), vec![25]);
+            assert_eq!(ids(&conn, "rarity:c"), vec![4]);
+
+            // Not the names shown in the UI language, e.g. "Häufig ●"
+            let mut params = Vec::new();
+            condition(&parse("rarity:c").unwrap(), &mut params);
+            assert_eq!(
+                params,
+                vec![Value::from(Rarity::Common.stored().to_string())]
+            );
+        }
+
+        #[test]
+        fn test_types_and_tags() {
+            let conn = setup();
+            let input = "owned:yes type:fire";
+            let err = check_types(&conn, input).unwrap_err();
+            let err = err.downcast::<QueryError>().unwrap();
+            assert_eq!(&input[err.span.start..err.span.end], "type");
+
+            conn.execute_batch(
+                "INSERT INTO pokemon_types (pokemon_id, slot, type) VALUES
+                    (4, 1, 'fire'), (25, 1, 'electric'), (255, 1, 'fire'), (256, 1, 'fire'), (256, 2, 'fighting');
+                 INSERT INTO card_tags (card_id, tag) VALUES (4, 'signed'), (25, 'graded');",
+            )
+            .unwrap();
+            check_types(&conn, input).unwrap();
+
+            assert_eq!(ids(&conn, "type:fire"), vec![4, 255, 256]);
+            assert_eq!(ids(&conn, "type:Fighting"), vec![256]);
+            assert_eq!(ids(&conn, "owned:no gen:3 type:fire"), vec![255, 256]);
+            assert_eq!(ids(&conn, "owned:yes type!=fire"), vec![25]);
+            assert_eq!(ids(&conn, "tag:Signed"), vec![4]);
+            assert_eq!(ids(&conn, "owned:yes -tag:signed"), vec![25]);
+            assert_eq!(
+                ids(
+                    &conn,
+                    "owned:no gen:3 type:fire rarity>=holo expansion:BS tag:signed"
+                ),
+                Vec::<usize>::new()
+            );
+        }
+
+        #[test]
+        fn test_positions() {
+            let conn = setup();
+            assert_eq!(ids(&conn, "page:2 id<=26 id>=25"), vec![25, 26]);
+            assert_eq!(ids(&conn, "book:2 id:577"), vec![577]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_example_query() {
+        let expr = parse("owned:no gen:3 type:fire rarity>=holo expansion:BS tag:signed").unwrap();
+        assert_eq!(
+            expr,
+            Expr::And(vec![
+                Expr::Filter(Filter::Owned(false)),
+                Expr::Filter(Filter::Generation(Comparison::Eq, 3)),
+                Expr::Filter(Filter::Type("fire".to_string())),
+                Expr::Filter(Filter::Rarity(Comparison::Ge, Rarity::HoloRare)),
+                Expr::Filter(Filter::Expansion("BS".to_string())),
+                Expr::Filter(Filter::Tag("signed".to_string())),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_grouping_and_negation() {
+        let expr = parse("(pikachu OR \"mr mime\") -owned:yes").unwrap();
+        assert_eq!(
+            expr,
+            Expr::And(vec![
+                Expr::Or(vec![
+                    Expr::Filter(Filter::Name("pikachu".to_string())),
+                    Expr::Filter(Filter::Name("mr mime".to_string())),
+                ]),
+                Expr::Not(Box::new(Expr::Filter(Filter::Owned(true)))),
+            ])
+        );
+        assert_eq!(
+            parse("expansion!=BS").unwrap(),
+            Expr::Not(Box::new(Expr::Filter(Filter::Expansion("BS".to_string()))))
+        );
+        assert_eq!(
+            parse("mr-mime").unwrap(),
+            Expr::Filter(Filter::Name("mr-mime".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_token() {
+        let input = "owned:no gen:12";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], "12");
+
+        let input = "owned:no colour:red";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], "colour");
+
+        let input = "type:plasma";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], "plasma");
+
+        let input = "type>fire";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], "type");
+
+        let input = "(gen:1 owned:no";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], "(");
+
+        let input = "rarity>=";
+        let err = parse(input).unwrap_err();
+        assert_eq!(&input[err.span.start..err.span.end], ">=");
+
+        assert!(parse("name:\"mr mime").is_err());
+        assert!(parse("   ").is_err());
+    }
+
+    #[test]
+    fn test_nesting_is_limited() {
+        let nested = |depth: usize| format!("{}gen:1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&nested(MAX_QUERY_DEPTH)).is_ok());
+
+        let input = nested(MAX_QUERY_DEPTH + 1);
+        let err = parse(&input).unwrap_err();
+        assert_eq!(err.span.start, MAX_QUERY_DEPTH);
+        assert_eq!(&input[err.span.start..err.span.end], "(");
+
+        let input = format!("{}owned:no", "NOT ".repeat(MAX_QUERY_DEPTH + 1));
+        let err = parse(&input).unwrap_err();
+        assert_eq!(err.span.start, 4 * MAX_QUERY_DEPTH);
+        assert_eq!(&input[err.span.start..err.span.end], "NOT");
+
+        assert!(parse(&"(".repeat(100_000)).is_err());
+        assert!(parse(&"-(".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_parse_rarity_aliases() {
+        assert_eq!(parse_rarity("holo"), Some(Rarity::HoloRare));
+        assert_eq!(parse_rarity("Holo Rare"), Some(Rarity::HoloRare));
+        assert_eq!(parse_rarity("reverse-holo"), Some(Rarity::ReverseHoloRare));
+        assert_eq!(parse_rarity("Uncommon"), Some(Rarity::Uncommon));
+        assert_eq!(parse_rarity("promo"), Some(Rarity::Promo));
+        assert_eq!(parse_rarity("shiny"), None);
+    }
+
+    #[test]
+    fn test_is_query() {
+        assert!(is_query("owned:no gen:3"));
+        assert!(is_query("rarity>=holo"));
+        assert!(!is_query("pikachu"));
+        assert!(!is_query("25"));
+        assert!(!is_query("Type: Null"));
+        assert!(!is_query("Mr. Mime"));
+    }
+}
@@ -0,0 +1,224 @@
+//! Named filter queries shown as virtual binders
+//!
+//! Only the text of a [`crate::query`] filter is stored, so the cards and
+//! counts of a saved search always follow the current collection. Counts
+//! are recomputed on every read, there is nothing to invalidate when
+//! ownership changes.
+
+use crate::query::QueryResult;
+use serde::{Deserialize, Serialize};
+
+/// Longest accepted name, in characters
+pub const MAX_NAME_LEN: usize = 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub id: usize,
+    pub name: String,
+    pub query: String,
+}
+
+/// A saved search with the number of cards it currently matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedQueryCount {
+    pub saved: SavedQuery,
+    pub total: usize,
+}
+
+/// A saved search together with its current matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBinder {
+    pub saved: SavedQuery,
+    pub result: QueryResult,
+}
+
+/// The trimmed name, or why it can't be used
+pub fn check_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A saved search needs a name".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Names are limited to {MAX_NAME_LEN} characters"));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::query;
+    use anyhow::Result;
+    use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row};
+
+    /// Another saved search already uses the name, names ignore case
+    #[derive(Debug, thiserror::Error)]
+    #[error("A saved search named `{0}` already exists")]
+    pub struct DuplicateName(pub String);
+
+    fn read_saved(row: &Row<'_>) -> rusqlite::Result<SavedQuery> {
+        Ok(SavedQuery {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            query: row.get(2)?,
+        })
+    }
+
+    /// All saved searches, by name
+    pub fn list(conn: &Connection) -> Result<Vec<SavedQuery>> {
+        Ok(conn
+            .prepare("SELECT id, name, query FROM saved_queries ORDER BY name")?
+            .query_map([], read_saved)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?)
+    }
+
+    pub fn get(conn: &Connection, id: usize) -> Result<Option<SavedQuery>> {
+        Ok(conn
+            .query_row(
+                "SELECT id, name, query FROM saved_queries WHERE id = ?1",
+                [id],
+                read_saved,
+            )
+            .optional()?)
+    }
+
+    /// Stores a search, `name` must have passed [`check_name`] and `query` must parse
+    pub fn create(conn: &Connection, name: &str, query: &str) -> Result<SavedQuery> {
+        let inserted = conn.execute(
+            "INSERT INTO saved_queries (name, query) VALUES (?1, ?2)",
+            params![name, query],
+        );
+        match inserted {
+            Ok(_) => Ok(SavedQuery {
+                id: conn.last_insert_rowid() as usize,
+                name: name.to_string(),
+                query: query.to_string(),
+            }),
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                Err(DuplicateName(name.to_string()).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Removes a search, `false` if there was none with the ID
+    pub fn delete(conn: &Connection, id: usize) -> Result<bool> {
+        Ok(conn.execute("DELETE FROM saved_queries WHERE id = ?1", [id])? > 0)
+    }
+
+    /// Current matches of a saved search, at most `limit` cards
+    pub fn matches(conn: &Connection, saved: &SavedQuery, limit: usize) -> Result<QueryResult> {
+        let expr = query::parse(&saved.query)
+            .map_err(|e| anyhow::anyhow!("saved search `{}` no longer parses: {e}", saved.name))?;
+        query::check_types(conn, &saved.query)?;
+        query::run(conn, &expr, limit)
+    }
+
+    /// Every saved search with its current number of matches
+    ///
+    /// Searches that no longer parse are skipped with a warning instead of
+    /// failing the whole list.
+    pub fn counts(conn: &Connection) -> Result<Vec<SavedQueryCount>> {
+        let mut counts = Vec::new();
+        for saved in list(conn)? {
+            match matches(conn, &saved, 0) {
+                Ok(result) => counts.push(SavedQueryCount {
+                    saved,
+                    total: result.total,
+                }),
+                Err(e) => tracing::warn!(id = saved.id, error = %e, "skipping saved search"),
+            }
+        }
+        Ok(counts)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute_batch(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES
+                    (1, json_object('en', 'bulbasaur', 'de', 'bisasam'), 1, 1, 'A', 1, '', 1),
+                    (4, json_object('en', 'charmander', 'de', 'glumanda'), 1, 1, 'A', 4, '', 0);",
+            )
+            .unwrap();
+            conn
+        }
+
+        #[test]
+        fn test_counts_follow_ownership() {
+            let conn = setup();
+            create(&conn, "Missing Gen 1", "owned:no gen:1").unwrap();
+            create(&conn, "Owned", "owned:yes").unwrap();
+
+            let totals = |conn: &Connection| -> Vec<(String, usize)> {
+                counts(conn)
+                    .unwrap()
+                    .into_iter()
+                    .map(|count| (count.saved.name, count.total))
+                    .collect()
+            };
+            assert_eq!(
+                totals(&conn),
+                vec![("Missing Gen 1".to_string(), 150), ("Owned".to_string(), 1)]
+            );
+
+            conn.execute("UPDATE cards SET owned = 1 WHERE id = 4", [])
+                .unwrap();
+            assert_eq!(
+                totals(&conn),
+                vec![("Missing Gen 1".to_string(), 149), ("Owned".to_string(), 2)]
+            );
+        }
+
+        #[test]
+        fn test_names_are_unique_ignoring_case() {
+            let conn = setup();
+            let saved = create(&conn, "Owned", "owned:yes").unwrap();
+            let err = create(&conn, "owned", "owned:no").unwrap_err();
+            assert!(err.downcast_ref::<DuplicateName>().is_some());
+
+            assert!(delete(&conn, saved.id).unwrap());
+            assert!(!delete(&conn, saved.id).unwrap());
+            assert!(get(&conn, saved.id).unwrap().is_none());
+        }
+
+        #[test]
+        fn test_unparsable_searches_are_skipped() {
+            let conn = setup();
+            conn.execute(
+                "INSERT INTO saved_queries (name, query) VALUES ('Broken', 'colour:red')",
+                [],
+            )
+            .unwrap();
+            create(&conn, "Owned", "owned:yes").unwrap();
+
+            let counts = counts(&conn).unwrap();
+            assert_eq!(counts.len(), 1);
+            assert_eq!(counts[0].saved.name, "Owned");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_name() {
+        assert_eq!(
+            check_name("  Missing Gen 1 "),
+            Ok("Missing Gen 1".to_string())
+        );
+        assert!(check_name("   ").is_err());
+        assert!(check_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+//! Full-text search over stored cards
+//!
+//! Backed by the `card_search` FTS5 table, which triggers keep in sync with
+//! `cards`, `card_expansions` and `expansions`. Only cards that were looked up
+//! or collected before are in the index, the exact ID and name lookups still
+//! fall back to PokeAPI. When nothing matches, near misses of the stored
+//! names are offered instead, see [`crate::names::suggest`].
+
+use crate::card::Card;
+use serde::{Deserialize, Serialize};
+
+/// Results returned for one query
+pub const MAX_SUGGESTIONS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub card: Card,
+    /// Excerpt of the best matching column, e.g. the expansion that matched
+    pub context: String,
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix
+///
+/// Everything but letters and digits separates words, so user input can't
+/// produce FTS5 syntax errors. Hiragana is searched as katakana, the script
+/// Japanese names are stored in. Returns `None` when no word is left.
+pub fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = words(input).map(|word| format!("\"{word}\"*")).collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Like [`fts_query`], but every word has to match within `column`
+pub fn fts_column_query(column: &str, input: &str) -> Option<String> {
+    let terms: Vec<String> = words(input)
+        .map(|word| format!("{column} : \"{word}\"*"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" AND "))
+}
+
+fn words(input: &str) -> impl Iterator<Item = String> + '_ {
+    input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.chars().map(crate::names::katakana).collect())
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use crate::names;
+    use anyhow::Result;
+    use rusqlite::{params, Connection, Row};
+
+    fn read_card(row: &Row<'_>) -> rusqlite::Result<Card> {
+        Ok(Card {
+            index: row.get(0)?,
+            names: row.get(1)?,
+            book: row.get(2)?,
+            page: row.get(3)?,
+            side: row.get(4)?,
+            entry: row.get(5)?,
+            img_url: row.get(6)?,
+            owned: row.get(7)?,
+            version: row.get(8)?,
+        })
+    }
+
+    /// Ranked hits for `input`, names weigh more than expansions and numbers
+    pub fn search(conn: &Connection, input: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let Some(query) = fts_query(input) else {
+            return Ok(Vec::new());
+        };
+
+        let hits = conn
+            .prepare(
+                "SELECT c.id, c.names, c.book, c.page, c.side, c.entry, c.img_url, c.owned,
+                        c.version, snippet(card_search, -1, '', '', '…', 8)
+                 FROM card_search JOIN cards c ON c.id = card_search.rowid
+                 WHERE card_search MATCH ?1
+                 ORDER BY bm25(card_search, 10.0, 2.0, 1.0), c.id
+                 LIMIT ?2",
+            )?
+            .query_map(params![query, limit], |row| {
+                Ok(SearchHit {
+                    card: read_card(row)?,
+                    context: row.get(9)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        if hits.is_empty() {
+            return did_you_mean(conn, input, limit);
+        }
+        Ok(hits)
+    }
+
+    /// Stored cards whose name is within a few typos of `input`
+    fn did_you_mean(conn: &Connection, input: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let cards = conn
+            .prepare(
+                "SELECT id, names, book, page, side, entry, img_url, owned, version FROM cards ORDER BY id",
+            )?
+            .query_map([], read_card)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let suggestions = names::suggest(
+            input,
+            cards
+                .iter()
+                .flat_map(|card| card.names.all().map(|name| name.0.as_str())),
+        );
+
+        let mut hits: Vec<SearchHit> = Vec::new();
+        for suggestion in suggestions {
+            let card = cards.iter().find(|card| card.names.matches(&suggestion));
+            if let Some(card) = card {
+                if hits.iter().all(|hit| hit.card.index != card.index) {
+                    hits.push(SearchHit {
+                        card: card.clone(),
+                        context: format!("Did you mean {suggestion}?"),
+                    });
+                }
+            }
+        }
+        hits.truncate(limit);
+        Ok(hits)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute_batch(
+                r#"INSERT INTO cards (id, names, book, page, side, entry, img_url, owned) VALUES
+                    (25, '{"en": "Pikachu", "de": "Pikachu", "ja": "ピカチュウ"}', 1, 2, 'A', 1, '', 1),
+                    (26, '{"en": "Raichu", "de": "Raichu", "ja": "ライチュウ"}', 1, 2, 'A', 2, '', 0),
+                    (669, '{"en": "Flabébé", "de": "Flabébé"}', 2, 5, 'B', 3, '', 0),
+                    (83, '{"en": "Farfetch''d", "de": "Porenta"}', 1, 7, 'A', 11, '', 0);"#,
+            )
+            .unwrap();
+            conn
+        }
+
+        fn ids(hits: &[SearchHit]) -> Vec<usize> {
+            hits.iter().map(|hit| hit.card.index.0).collect()
+        }
+
+        #[test]
+        fn test_fts_query() {
+            assert_eq!(fts_query("pika"), Some("\"pika\"*".to_string()));
+            assert_eq!(
+                fts_query("Farfetch'd \"OR"),
+                Some("\"Farfetch\"* \"d\"* \"OR\"*".to_string())
+            );
+            assert_eq!(fts_query(" - * "), None);
+        }
+
+        #[test]
+        fn test_prefix_match_in_both_languages() {
+            let conn = setup();
+            assert_eq!(ids(&search(&conn, "pik", 10).unwrap()), vec![25]);
+            assert_eq!(ids(&search(&conn, "poren", 10).unwrap()), vec![83]);
+            assert_eq!(ids(&search(&conn, "flabebe", 10).unwrap()), vec![669]);
+        }
+
+        #[test]
+        fn test_katakana_and_hiragana_match_japanese_names() {
+            let conn = setup();
+            assert_eq!(ids(&search(&conn, "ピカ", 10).unwrap()), vec![25]);
+            assert_eq!(ids(&search(&conn, "らいちゅう", 10).unwrap()), vec![26]);
+            assert_eq!(
+                fts_query("ぴか ちゅう"),
+                Some("\"ピカ\"* \"チュウ\"*".to_string())
+            );
+        }
+
+        #[test]
+        fn test_index_follows_expansion_entries() {
+            let conn = setup();
+            let (expansion_id, abbreviation): (usize, String) = conn
+                .query_row(
+                    "SELECT id, abbreviation FROM expansions LIMIT 1",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            conn.execute(
+                "INSERT INTO card_expansions (card_id, expansion_id, card_number, rarity) VALUES (26, ?1, '014', 'Rare')",
+                [expansion_id],
+            )
+            .unwrap();
+
+            assert_eq!(ids(&search(&conn, "014", 10).unwrap()), vec![26]);
+            assert_eq!(ids(&search(&conn, &abbreviation, 10).unwrap()), vec![26]);
+
+            conn.execute("DELETE FROM card_expansions WHERE card_id = 26", [])
+                .unwrap();
+            assert!(search(&conn, "014", 10).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_typos_fall_back_to_suggestions() {
+            let conn = setup();
+            let hits = search(&conn, "raichuu", 10).unwrap();
+            assert_eq!(ids(&hits), vec![26]);
+            assert_eq!(hits[0].context, "Did you mean Raichu?");
+            assert!(search(&conn, "glurak", 10).unwrap().is_empty());
+        }
+
+        #[test]
+        fn test_renamed_card_is_reindexed() {
+            let conn = setup();
+            conn.execute(
+                r#"UPDATE cards SET names = '{"en": "Raichu", "de": "Raichu"}' WHERE id = 25"#,
+                [],
+            )
+            .unwrap();
+            let hits = search(&conn, "raichu", 10).unwrap();
+            assert_eq!(ids(&hits), vec![25, 26]);
+            assert!(search(&conn, "pikachu", 10).unwrap().is_empty());
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use crate::backend::get_expansion_statistics_db;
+use crate::i18n::t;
 use crate::statistics::{
     AxisLabels, BarChartEntry, ChartType, ColorScheme, FilterCriteria, Filterable, SortOrder,
     Sortable, StatWidget, StatisticData, StatisticMetadata,
 };
+use crate::store;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::future::Future;
@@ -16,13 +17,13 @@ impl StatWidget for ExpansionCompletionWidget {
     }
 
     fn title(&self) -> &str {
-        "Expansion Completion"
+        t("stats.completion")
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + Send + '_>> {
         Box::pin(async move {
-            let stats = get_expansion_statistics_db().await?;
+            let stats = store::expansion_stats().await?;
 
             let entries = stats
                 .into_iter()
@@ -46,9 +47,10 @@ impl StatWidget for ExpansionCompletionWidget {
                         "abbreviation".to_string(),
                         stat.expansion.abbreviation.clone(),
                     );
+                    metadata.insert("series".to_string(), stat.expansion.series.clone());
 
                     BarChartEntry {
-                        label: stat.expansion.name.clone(),
+                        label: stat.expansion.localized_name().to_string(),
                         value: stat.completion_percentage(),
                         metadata,
                     }
@@ -62,7 +64,7 @@ impl StatWidget for ExpansionCompletionWidget {
     #[cfg(target_arch = "wasm32")]
     fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + '_>> {
         Box::pin(async move {
-            let stats = get_expansion_statistics_db().await?;
+            let stats = store::expansion_stats().await?;
 
             let entries = stats
                 .into_iter()
@@ -86,9 +88,10 @@ impl StatWidget for ExpansionCompletionWidget {
                         "abbreviation".to_string(),
                         stat.expansion.abbreviation.clone(),
                     );
+                    metadata.insert("series".to_string(), stat.expansion.series.clone());
 
                     BarChartEntry {
-                        label: stat.expansion.name.clone(),
+                        label: stat.expansion.localized_name().to_string(),
                         value: stat.completion_percentage(),
                         metadata,
                     }
@@ -104,11 +107,10 @@ impl StatWidget for ExpansionCompletionWidget {
             chart_type: ChartType::HorizontalBar,
             color_scheme: ColorScheme::CompletionGradient,
             axis_labels: AxisLabels {
-                x_label: Some("Completion Percentage".to_string()),
-                y_label: Some("Expansion".to_string()),
+                x_label: Some(t("stats.completion_axis").to_string()),
+                y_label: Some(t("stats.expansion_axis").to_string()),
             },
-            description: "Track your collection progress across all Pokemon TCG expansions"
-                .to_string(),
+            description: t("stats.completion_description").to_string(),
         }
     }
 }
@@ -159,10 +161,11 @@ impl Filterable for ExpansionCompletionWidget {
                     .filter(|entry| entry.value >= *min_percent)
                     .cloned()
                     .collect(),
-                FilterCriteria::ExpansionSeries(_series) => {
-                    // TODO: Implement series filtering when expansion series data is available
-                    entries.clone()
-                }
+                FilterCriteria::ExpansionSeries(series) => entries
+                    .iter()
+                    .filter(|entry| entry.metadata.get("series") == Some(series))
+                    .cloned()
+                    .collect(),
                 _ => entries.clone(),
             };
 
@@ -1,5 +1,7 @@
 mod expansion_completion_widget;
+mod saved_query_widget;
 mod total_owned_widget;
 
 pub use expansion_completion_widget::*;
+pub use saved_query_widget::*;
 pub use total_owned_widget::*;
@@ -0,0 +1,66 @@
+use crate::backend::get_saved_query_count_db;
+use crate::i18n::{t, tf};
+use crate::saved_queries::SavedQuery;
+use crate::statistics::{
+    AxisLabels, ChartType, ColorScheme, ScalarValue, StatWidget, StatisticData, StatisticMetadata,
+};
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Number of cards a saved search currently matches
+pub struct SavedQueryWidget {
+    pub saved: SavedQuery,
+}
+
+impl SavedQueryWidget {
+    async fn count(&self) -> Result<StatisticData> {
+        let count = get_saved_query_count_db(self.saved.id).await?;
+
+        let value = ScalarValue {
+            value: count.total as f64,
+            label: count.total.to_string(),
+            unit: Some(
+                match count.total {
+                    1 => t("stats.card_unit"),
+                    _ => t("stats.cards_unit"),
+                }
+                .to_string(),
+            ),
+        };
+
+        Ok(StatisticData::Scalar(value))
+    }
+}
+
+impl StatWidget for SavedQueryWidget {
+    fn widget_id(&self) -> &'static str {
+        "saved_query"
+    }
+
+    fn title(&self) -> &str {
+        &self.saved.name
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + Send + '_>> {
+        Box::pin(self.count())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + '_>> {
+        Box::pin(self.count())
+    }
+
+    fn metadata(&self) -> StatisticMetadata {
+        StatisticMetadata {
+            chart_type: ChartType::Scalar,
+            color_scheme: ColorScheme::Default,
+            axis_labels: AxisLabels {
+                x_label: None,
+                y_label: None,
+            },
+            description: tf("stats.saved_description", &[("query", &self.saved.query)]),
+        }
+    }
+}
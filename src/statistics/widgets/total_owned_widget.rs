@@ -1,7 +1,8 @@
-use crate::backend::get_collection_statistics_db;
+use crate::i18n::t;
 use crate::statistics::{
     AxisLabels, ChartType, ColorScheme, ScalarValue, StatWidget, StatisticData, StatisticMetadata,
 };
+use crate::store;
 use anyhow::Result;
 use std::future::Future;
 use std::pin::Pin;
@@ -14,13 +15,13 @@ impl StatWidget for TotalOwnedWidget {
     }
 
     fn title(&self) -> &str {
-        "Total Cards Owned"
+        t("stats.total_owned")
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + Send + '_>> {
         Box::pin(async move {
-            let stats = get_collection_statistics_db().await?;
+            let stats = store::collection_stats().await?;
 
             let value = ScalarValue {
                 value: stats.total_cards_owned as f64,
@@ -28,7 +29,7 @@ impl StatWidget for TotalOwnedWidget {
                     "{} / {}",
                     stats.total_cards_owned, stats.total_unique_pokemon
                 ),
-                unit: Some("cards".to_string()),
+                unit: Some(t("stats.cards_unit").to_string()),
             };
 
             Ok(StatisticData::Scalar(value))
@@ -38,7 +39,7 @@ impl StatWidget for TotalOwnedWidget {
     #[cfg(target_arch = "wasm32")]
     fn calculate(&self) -> Pin<Box<dyn Future<Output = Result<StatisticData>> + '_>> {
         Box::pin(async move {
-            let stats = get_collection_statistics_db().await?;
+            let stats = store::collection_stats().await?;
 
             let value = ScalarValue {
                 value: stats.total_cards_owned as f64,
@@ -46,7 +47,7 @@ impl StatWidget for TotalOwnedWidget {
                     "{} / {}",
                     stats.total_cards_owned, stats.total_unique_pokemon
                 ),
-                unit: Some("cards".to_string()),
+                unit: Some(t("stats.cards_unit").to_string()),
             };
 
             Ok(StatisticData::Scalar(value))
@@ -61,7 +62,7 @@ impl StatWidget for TotalOwnedWidget {
                 x_label: None,
                 y_label: None,
             },
-            description: "Total unique Pokemon cards in your collection".to_string(),
+            description: t("stats.total_owned_description").to_string(),
         }
     }
 }
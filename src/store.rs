@@ -0,0 +1,366 @@
+//! Client-side cache of server data
+//!
+//! Pocket summaries, expansions, the prints of single cards and the
+//! statistics are fetched once and kept in global signals, so views that
+//! mount again render from memory instead of asking the server. Code that
+//! changes the collection calls one of the `*_changed` functions afterwards.
+//! They drop the affected entries and bump [`COLLECTION_REVISION`], views
+//! reading the revision then fetch what they need again.
+//!
+//! A copy of the whole collection is kept in local storage. While the server
+//! can't be reached the cache is filled from it, and ownership edits are
+//! queued there until [`replay_queue`] can send them, see [`crate::offline`].
+
+use crate::{
+    backend::{
+        get_all_expansions_db, get_card_expansions_db, get_client_config,
+        get_collection_statistics_db, get_expansion_statistics_db, get_offline_snapshot_db,
+        get_page_view_db, save_card_db, save_card_ownership_db,
+    },
+    card::{Card, Index, Page},
+    config,
+    expansion::{CardExpansion, Expansion},
+    offline::{self, MutationQueue, Replayed, Snapshot},
+    ownership::{EditConflict, OwnershipEdit, SavedOwnership},
+    page_view::PocketSummary,
+    statistics::{CollectionStats, ExpansionStats},
+    COLLECTION_REVISION,
+};
+use dioxus::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Local storage keys of the offline copy, the queued edits and the client config
+const SNAPSHOT_KEY: &str = "fs-chaot.offline.snapshot";
+const QUEUE_KEY: &str = "fs-chaot.offline.queue";
+const CLIENT_CONFIG_KEY: &str = "fs-chaot.client-config";
+
+/// Pocket summaries by Pokedex number, see [`load_page`]
+static POCKETS: GlobalSignal<HashMap<usize, PocketSummary>> = Signal::global(HashMap::new);
+/// Double pages whose summaries are in [`POCKETS`] or on their way
+static LOADED_PAGES: GlobalSignal<HashSet<usize>> = Signal::global(HashSet::new);
+static EXPANSIONS: GlobalSignal<Option<Vec<Expansion>>> = Signal::global(|| None);
+/// Prints by Pokedex number
+static PRINTS: GlobalSignal<HashMap<usize, Vec<CardExpansion>>> = Signal::global(HashMap::new);
+static COLLECTION_STATS: GlobalSignal<Option<CollectionStats>> = Signal::global(|| None);
+static EXPANSION_STATS: GlobalSignal<Option<Vec<ExpansionStats>>> = Signal::global(|| None);
+/// Ownership edits waiting for the server
+static QUEUE: GlobalSignal<MutationQueue> = Signal::global(MutationQueue::default);
+/// Set while the cache is served from the offline copy
+static OFFLINE: GlobalSignal<bool> = Signal::global(|| false);
+
+/// The cached summary of a pocket, subscribes the caller to changes
+pub fn pocket(index: usize) -> Option<PocketSummary> {
+    POCKETS.read().get(&index).cloned()
+}
+
+/// The stored card of a pocket if its page is loaded
+pub fn stored_card(index: usize) -> Option<Card> {
+    POCKETS
+        .peek()
+        .get(&index)
+        .and_then(|pocket| pocket.card.clone())
+}
+
+/// Fetches the summaries of one double page unless they are cached
+pub async fn load_page(page: usize) -> Result<(), ServerFnError> {
+    if page == 0 || page > config::layout().total_pages() || LOADED_PAGES.peek().contains(&page) {
+        return Ok(());
+    }
+    LOADED_PAGES.write().insert(page);
+
+    let range = config::layout().double_page(page);
+    match get_page_view_db(*range.start(), *range.end()).await {
+        Ok(summaries) => {
+            POCKETS.write().extend(
+                summaries
+                    .into_iter()
+                    .map(|summary| (summary.index, summary)),
+            );
+            Ok(())
+        }
+        Err(e) => {
+            LOADED_PAGES.write().remove(&page);
+            fall_back(e).await
+        }
+    }
+}
+
+/// All expansions, fetched on first use
+pub async fn expansions() -> Result<Vec<Expansion>, ServerFnError> {
+    if let Some(expansions) = EXPANSIONS.peek().clone() {
+        return Ok(expansions);
+    }
+    let expansions = match get_all_expansions_db().await {
+        Ok(expansions) => expansions,
+        Err(e) => {
+            fall_back(e).await?;
+            return Ok(EXPANSIONS.peek().clone().unwrap_or_default());
+        }
+    };
+    *EXPANSIONS.write() = Some(expansions.clone());
+    Ok(expansions)
+}
+
+/// The prints of one card, fetched on first use
+pub async fn card_prints(index: usize) -> Result<Vec<CardExpansion>, ServerFnError> {
+    if let Some(prints) = PRINTS.peek().get(&index).cloned() {
+        return Ok(prints);
+    }
+    let prints = match get_card_expansions_db(index).await {
+        Ok(prints) => prints,
+        Err(e) => {
+            fall_back(e).await?;
+            return Ok(PRINTS.peek().get(&index).cloned().unwrap_or_default());
+        }
+    };
+    PRINTS.write().insert(index, prints.clone());
+    Ok(prints)
+}
+
+pub async fn collection_stats() -> Result<CollectionStats, ServerFnError> {
+    if let Some(stats) = COLLECTION_STATS.peek().clone() {
+        return Ok(stats);
+    }
+    let stats = get_collection_statistics_db().await?;
+    *COLLECTION_STATS.write() = Some(stats.clone());
+    Ok(stats)
+}
+
+pub async fn expansion_stats() -> Result<Vec<ExpansionStats>, ServerFnError> {
+    if let Some(stats) = EXPANSION_STATS.peek().clone() {
+        return Ok(stats);
+    }
+    let stats = get_expansion_statistics_db().await?;
+    *EXPANSION_STATS.write() = Some(stats.clone());
+    Ok(stats)
+}
+
+/// Stores a card fetched from the API, returns it as stored
+///
+/// If another device stored the card in the meantime its copy wins.
+pub async fn save_fetched_card(card: Card) -> Result<Card, ServerFnError> {
+    match save_card_db(card).await {
+        Ok(stored) => Ok(stored),
+        Err(e) => Ok(EditConflict::from_error(&e).ok_or(e)?.current.card),
+    }
+}
+
+/// Call after saving one card, its page and prints are fetched again
+///
+/// The pocket shows the saved card right away, rarity and print count
+/// follow once the page is reloaded.
+pub fn card_changed(card: &Card) {
+    if let Some(pocket) = POCKETS.write().get_mut(&card.index.0) {
+        pocket.card = Some(card.clone());
+    }
+    cards_changed([card.index.0]);
+}
+
+/// Call when cards changed elsewhere, e.g. on another device
+pub fn cards_changed(indices: impl IntoIterator<Item = usize>) {
+    for index in indices {
+        LOADED_PAGES.write().remove(&Page::absolut(&Index(index)).0);
+        PRINTS.write().remove(&index);
+    }
+    drop_statistics();
+    *COLLECTION_REVISION.write() += 1;
+}
+
+/// Call after changes to many cards, e.g. a bulk update or a restore
+pub fn collection_changed() {
+    LOADED_PAGES.write().clear();
+    PRINTS.write().clear();
+    drop_statistics();
+    *COLLECTION_REVISION.write() += 1;
+}
+
+/// Call after expansions were added, edited or removed
+pub fn expansions_changed() {
+    *EXPANSIONS.write() = None;
+    collection_changed();
+}
+
+fn drop_statistics() {
+    *COLLECTION_STATS.write() = None;
+    *EXPANSION_STATS.write() = None;
+}
+
+/// True while the server can't be reached, subscribes the caller
+pub fn offline() -> bool {
+    OFFLINE()
+}
+
+/// Number of edits waiting for the server, subscribes the caller
+pub fn queued_edits() -> usize {
+    QUEUE.read().len()
+}
+
+async fn read_local<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let script = format!(
+        "return localStorage.getItem({});",
+        serde_json::to_string(key).ok()?
+    );
+    let value = document::eval(&script)
+        .join::<Option<String>>()
+        .await
+        .ok()??;
+    match serde_json::from_str(&value) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(key, error = %e, "ignoring unreadable local storage entry");
+            None
+        }
+    }
+}
+
+fn write_local<T: Serialize>(key: &str, value: &T) {
+    let Ok(json) = serde_json::to_string(value) else {
+        return;
+    };
+    let eval = document::eval(
+        r#"
+        const [key, value] = await dioxus.recv();
+        localStorage.setItem(key, value);
+        "#,
+    );
+    if let Err(e) = eval.send((key, json)) {
+        tracing::warn!(key, error = ?e, "failed to write to local storage");
+    }
+}
+
+/// Installs the binder layout and name languages of the server
+///
+/// They are kept in local storage, so the app starts with the last ones
+/// while the server can't be reached.
+pub async fn load_client_config() {
+    let client = match get_client_config().await {
+        Ok(client) => {
+            write_local(CLIENT_CONFIG_KEY, &client);
+            Some(client)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to load client config, using the last one");
+            read_local(CLIENT_CONFIG_KEY).await
+        }
+    };
+    match client {
+        Some(client) => config::init_client(client),
+        None => tracing::error!("no client config stored yet, using defaults"),
+    }
+}
+
+/// Loads the edits queued in an earlier session
+pub async fn restore_queue() {
+    if let Some(queue) = read_local::<MutationQueue>(QUEUE_KEY).await {
+        for queued in queue.edits() {
+            show_edit(&queued.edit);
+        }
+        *QUEUE.write() = queue;
+    }
+}
+
+/// Queues an edit the server could not be reached for and shows it as saved
+///
+/// `base` is the card and prints the edit started from.
+pub fn queue_edit(edit: OwnershipEdit, base: SavedOwnership) {
+    show_edit(&edit);
+    QUEUE.write().push(edit, base);
+    write_local(QUEUE_KEY, &*QUEUE.peek());
+    *OFFLINE.write() = true;
+}
+
+/// Sends the queued edits oldest first
+///
+/// Stops after the first [`Replayed::Unreachable`], the queue is empty otherwise.
+pub async fn replay_queue() -> Vec<Replayed> {
+    let mut outcomes = Vec::new();
+    while let Some(queued) = QUEUE.peek().front().cloned() {
+        let result = save_card_ownership_db(queued.edit).await;
+        let replayed = QUEUE.write().settle(queued.id, result);
+        write_local(QUEUE_KEY, &*QUEUE.peek());
+        match &replayed {
+            Replayed::Saved(saved) => card_changed(&saved.card),
+            Replayed::Dropped { card, .. } => cards_changed([*card]),
+            Replayed::Merged | Replayed::Superseded | Replayed::Unreachable => {}
+        }
+        let unreachable = replayed == Replayed::Unreachable;
+        outcomes.push(replayed);
+        if unreachable {
+            *OFFLINE.write() = true;
+            break;
+        }
+    }
+    outcomes
+}
+
+/// Stores a fresh offline copy of the collection, false if that failed
+///
+/// The first success after being offline reloads everything that was
+/// served from the old copy.
+pub async fn refresh_snapshot() -> bool {
+    match get_offline_snapshot_db().await {
+        Ok(snapshot) => {
+            write_local(SNAPSHOT_KEY, &snapshot);
+            if *OFFLINE.peek() {
+                *OFFLINE.write() = false;
+                collection_changed();
+            }
+            true
+        }
+        Err(e) => {
+            if offline::is_unreachable(&e) {
+                *OFFLINE.write() = true;
+            } else {
+                tracing::warn!(error = %e, "failed to fetch offline snapshot");
+            }
+            false
+        }
+    }
+}
+
+/// Fills the cache from the offline copy if `error` means the server is unreachable
+async fn fall_back(error: ServerFnError) -> Result<(), ServerFnError> {
+    if !offline::is_unreachable(&error) {
+        return Err(error);
+    }
+    let Some(snapshot) = read_local::<Snapshot>(SNAPSHOT_KEY).await else {
+        return Err(error);
+    };
+    *OFFLINE.write() = true;
+    apply_snapshot(snapshot);
+    Ok(())
+}
+
+fn apply_snapshot(snapshot: Snapshot) {
+    let mut prints: HashMap<usize, Vec<CardExpansion>> = snapshot
+        .pockets
+        .iter()
+        .map(|pocket| (pocket.index, Vec::new()))
+        .collect();
+    for print in snapshot.prints {
+        prints.entry(print.card_id).or_default().push(print);
+    }
+
+    *POCKETS.write() = snapshot
+        .pockets
+        .into_iter()
+        .map(|pocket| (pocket.index, pocket))
+        .collect();
+    *PRINTS.write() = prints;
+    *EXPANSIONS.write() = Some(snapshot.expansions);
+    *LOADED_PAGES.write() = (1..=config::layout().total_pages()).collect();
+    for queued in QUEUE.peek().edits() {
+        show_edit(&queued.edit);
+    }
+}
+
+/// Shows an edit that was not saved yet as if it was
+fn show_edit(edit: &OwnershipEdit) {
+    let summary = offline::summary(edit);
+    PRINTS
+        .write()
+        .insert(summary.index, edit.expansions.clone());
+    POCKETS.write().insert(summary.index, summary);
+    drop_statistics();
+}
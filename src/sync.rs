@@ -0,0 +1,328 @@
+//! Live sync of collection changes between open clients
+//!
+//! Server functions that change the collection call [`publish`] once their
+//! transaction committed. It reads the history batches recorded since the
+//! previous call and broadcasts one [`SyncEvent`] per batch. Clients listen
+//! on the [`crate::backend::live_sync`] server-sent events stream, drop the
+//! affected entries from [`crate::store`] and show a toast for changes made
+//! under another profile.
+
+use crate::expansion::Expansion;
+use crate::history::CardChange;
+use crate::i18n::{language, t_in, tf_in, Language};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncEvent {
+    /// One batch of the change history
+    Changes {
+        profile: String,
+        changes: Vec<(usize, CardChange)>,
+        /// The batch reverted an earlier one
+        undo: bool,
+    },
+    /// The database was replaced by a backup
+    Restored { profile: String },
+    /// An expansion was created, edited or archived, named in the language
+    /// of each client
+    ExpansionChanged {
+        profile: String,
+        expansion: Box<Expansion>,
+    },
+    /// The stream dropped events, everything has to be reloaded
+    Lagged,
+}
+
+impl SyncEvent {
+    /// Who made the change, `None` if unknown
+    pub fn profile(&self) -> Option<&str> {
+        match self {
+            SyncEvent::Changes { profile, .. }
+            | SyncEvent::Restored { profile }
+            | SyncEvent::ExpansionChanged { profile, .. } => Some(profile),
+            SyncEvent::Lagged => None,
+        }
+    }
+
+    /// Cards the event touched, `None` if it may have touched any card
+    pub fn card_ids(&self) -> Option<BTreeSet<usize>> {
+        match self {
+            SyncEvent::Changes { changes, .. } => {
+                Some(changes.iter().map(|(card_id, _)| *card_id).collect())
+            }
+            SyncEvent::Restored { .. } | SyncEvent::ExpansionChanged { .. } | SyncEvent::Lagged => {
+                None
+            }
+        }
+    }
+
+    /// Toast text, e.g. "misty marked #25 as owned"
+    pub fn describe(&self) -> String {
+        self.describe_in(language())
+    }
+
+    /// [`Self::describe`] in `language`
+    pub fn describe_in(&self, language: Language) -> String {
+        let tf = |key: &str, args: &[(&str, &dyn std::fmt::Display)]| tf_in(language, key, args);
+        let (profile, changes, undo) = match self {
+            SyncEvent::Changes {
+                profile,
+                changes,
+                undo,
+            } => (profile, changes, *undo),
+            SyncEvent::Restored { profile } => return tf("sync.restored", &[("profile", profile)]),
+            SyncEvent::ExpansionChanged { profile, expansion } => {
+                let expansion = format!(
+                    "{} ({})",
+                    expansion.name_in(language),
+                    expansion.abbreviation
+                );
+                return tf(
+                    "sync.expansion_changed",
+                    &[("profile", profile), ("expansion", &expansion)],
+                );
+            }
+            SyncEvent::Lagged => return t_in(language, "sync.lagged").to_string(),
+        };
+
+        let ids = self.card_ids().unwrap_or_default();
+        let target = match (ids.len(), ids.first()) {
+            (1, Some(id)) => format!("#{id}"),
+            (count, _) => tf("sync.cards", &[("count", &count)]),
+        };
+        let args: [(&str, &dyn std::fmt::Display); 2] = [("profile", profile), ("target", &target)];
+        if undo {
+            return tf("sync.undo", &args);
+        }
+
+        let ownership = changes.iter().find_map(|(_, change)| match change {
+            CardChange::Ownership { after, .. } => Some(*after),
+            _ => None,
+        });
+        match ownership {
+            Some(true) => tf("sync.owned", &args),
+            Some(false) => tf("sync.not_owned", &args),
+            None => tf("sync.prints", &args),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use std::sync::{Mutex, OnceLock, PoisonError};
+    use tokio::sync::broadcast;
+
+    /// Events buffered per client, slower clients get [`SyncEvent::Lagged`]
+    const CHANNEL_CAPACITY: usize = 64;
+
+    struct Channel {
+        sender: broadcast::Sender<SyncEvent>,
+        /// Last history batch that was broadcast
+        published: Mutex<i64>,
+    }
+
+    static CHANNEL: OnceLock<Channel> = OnceLock::new();
+
+    fn channel() -> &'static Channel {
+        CHANNEL.get_or_init(|| Channel {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            published: Mutex::new(0),
+        })
+    }
+
+    fn latest_batch(conn: &Connection) -> Result<i64> {
+        Ok(conn.query_row(
+            "SELECT COALESCE(MAX(batch), 0) FROM card_events",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Receives every event broadcast from now on
+    pub fn subscribe() -> broadcast::Receiver<SyncEvent> {
+        channel().sender.subscribe()
+    }
+
+    /// Treats the recorded history as published, call at startup
+    pub fn init(conn: &Connection) -> Result<()> {
+        let latest = latest_batch(conn)?;
+        *channel()
+            .published
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = latest;
+        Ok(())
+    }
+
+    /// Broadcasts the batches recorded since the last call, returns how many
+    pub fn publish(conn: &Connection) -> Result<usize> {
+        let channel = channel();
+        // Held during the query so concurrent calls don't send a batch twice
+        let mut published = channel
+            .published
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let rows: Vec<(i64, usize, String, String, bool)> = conn
+            .prepare(
+                "SELECT batch, card_id, payload, profile, reverts IS NOT NULL FROM card_events
+                 WHERE batch > ?1 ORDER BY batch, id",
+            )?
+            .query_map([*published], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })?
+            .collect::<Result<_, rusqlite::Error>>()?;
+
+        let mut events: Vec<(i64, SyncEvent)> = Vec::new();
+        for (batch, card_id, payload, profile, reverts) in rows {
+            let change: CardChange = serde_json::from_str(&payload)?;
+            match events.last_mut() {
+                Some((last, SyncEvent::Changes { changes, .. })) if *last == batch => {
+                    changes.push((card_id, change));
+                }
+                _ => events.push((
+                    batch,
+                    SyncEvent::Changes {
+                        profile,
+                        changes: vec![(card_id, change)],
+                        undo: reverts,
+                    },
+                )),
+            }
+        }
+
+        let count = events.len();
+        for (batch, event) in events {
+            *published = batch;
+            // Sending only fails when no client is listening
+            let _ = channel.sender.send(event);
+        }
+        Ok(count)
+    }
+
+    /// Announces a restore, the restored history counts as published
+    pub fn publish_restore(conn: &Connection, profile: &str) -> Result<()> {
+        init(conn)?;
+        let _ = channel().sender.send(SyncEvent::Restored {
+            profile: profile.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Announces a change to an expansion, which is not part of the card history
+    pub fn publish_expansion(profile: &str, expansion: &Expansion) {
+        let _ = channel().sender.send(SyncEvent::ExpansionChanged {
+            profile: profile.to_string(),
+            expansion: Box::new(expansion.clone()),
+        });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+        use crate::history::Recorder;
+
+        #[test]
+        fn test_publish_sends_each_batch_once() {
+            let conn = test_connection();
+            init(&conn).unwrap();
+            let mut events = subscribe();
+
+            let owned = CardChange::Ownership {
+                before: false,
+                after: true,
+            };
+            for card_ids in [vec![1, 4], vec![25]] {
+                let recorder = Recorder::new(&conn, "misty").unwrap();
+                for card_id in card_ids {
+                    recorder.record(&conn, card_id, &owned).unwrap();
+                }
+            }
+
+            assert_eq!(publish(&conn).unwrap(), 2);
+            assert_eq!(publish(&conn).unwrap(), 0);
+
+            let first = events.try_recv().unwrap();
+            assert_eq!(first.card_ids(), Some(BTreeSet::from([1, 4])));
+            assert_eq!(first.profile(), Some("misty"));
+            assert_eq!(
+                events.try_recv().unwrap().describe(),
+                "misty marked #25 as owned"
+            );
+            assert!(events.try_recv().is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let event = SyncEvent::Changes {
+            profile: "brock".to_string(),
+            changes: vec![
+                (
+                    7,
+                    CardChange::Ownership {
+                        before: true,
+                        after: false,
+                    },
+                ),
+                (
+                    8,
+                    CardChange::Ownership {
+                        before: true,
+                        after: false,
+                    },
+                ),
+            ],
+            undo: false,
+        };
+        assert_eq!(event.describe(), "brock marked 2 cards as not owned");
+
+        let SyncEvent::Changes { changes, .. } = event else {
+            unreachable!()
+        };
+        let undo = SyncEvent::Changes {
+            profile: "brock".to_string(),
+            changes: changes[..1].to_vec(),
+            undo: true,
+        };
+        assert_eq!(undo.describe(), "brock undid a change to #7");
+        assert_eq!(SyncEvent::Lagged.card_ids(), None);
+
+        let renamed = SyncEvent::ExpansionChanged {
+            profile: "brock".to_string(),
+            expansion: Box::new(Expansion {
+                name: "Base Set".to_string(),
+                name_de: Some("Grundset".to_string()),
+                abbreviation: "BS".to_string(),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(
+            renamed.describe(),
+            "brock changed the expansion Base Set (BS)"
+        );
+        assert_eq!(
+            renamed.describe_in(Language::De),
+            "brock hat die Erweiterung Grundset (BS) geändert"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Free-form labels on stored cards, such as `signed` or `graded`
+//!
+//! Tags are lowercase words filtered with `tag:` in the search box, see
+//! [`crate::query`]. Setting the tags of a card replaces all of them.
+
+/// Longest accepted tag, in characters
+pub const MAX_TAG_LEN: usize = 30;
+/// Most tags on one card
+pub const MAX_TAGS: usize = 20;
+
+/// The tags as stored, lowercase, sorted and without duplicates, or why
+/// they can't be used
+///
+/// Tags are single words of letters, digits, `-` and `_` so `tag:` filters
+/// need no quoting. Blank entries are dropped.
+pub fn check_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut checked: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Tags may only contain letters, digits, `-` and `_`, not `{tag}`"
+            ));
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!("Tags are limited to {MAX_TAG_LEN} characters"));
+        }
+        checked.push(tag);
+    }
+    checked.sort();
+    checked.dedup();
+    if checked.len() > MAX_TAGS {
+        return Err(format!("A card can have at most {MAX_TAGS} tags"));
+    }
+    Ok(checked)
+}
+
+#[cfg(feature = "server")]
+pub use server::*;
+
+#[cfg(feature = "server")]
+mod server {
+    use anyhow::Result;
+    use rusqlite::{params, Connection, OptionalExtension};
+
+    /// Tags need a stored card to hang on
+    #[derive(Debug, thiserror::Error)]
+    #[error("Card {0} is not stored, look it up before tagging it")]
+    pub struct CardNotStored(pub usize);
+
+    /// Tags of a card, sorted
+    pub fn get(conn: &Connection, card_id: usize) -> Result<Vec<String>> {
+        Ok(conn
+            .prepare("SELECT tag FROM card_tags WHERE card_id = ?1 ORDER BY tag")?
+            .query_map([card_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?)
+    }
+
+    /// Replaces the tags of a card, `tags` must have passed [`super::check_tags`]
+    pub fn set(conn: &mut Connection, card_id: usize, tags: &[String]) -> Result<Vec<String>> {
+        let tx = conn.transaction()?;
+        let stored = tx
+            .query_row("SELECT 1 FROM cards WHERE id = ?1", [card_id], |_| Ok(()))
+            .optional()?;
+        if stored.is_none() {
+            return Err(CardNotStored(card_id).into());
+        }
+        tx.execute("DELETE FROM card_tags WHERE card_id = ?1", [card_id])?;
+        for tag in tags {
+            tx.execute(
+                "INSERT INTO card_tags (card_id, tag) VALUES (?1, ?2)",
+                params![card_id, tag],
+            )?;
+        }
+        let tags = get(&tx, card_id)?;
+        tx.commit()?;
+        Ok(tags)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::backend::test_connection;
+
+        fn setup() -> Connection {
+            let conn = test_connection();
+            conn.execute(
+                "INSERT INTO cards (id, names, book, page, side, entry, img_url, owned)
+                 VALUES (25, json_object('en', 'pikachu'), 1, 2, 'A', 1, '', 1)",
+                [],
+            )
+            .unwrap();
+            conn
+        }
+
+        #[test]
+        fn test_set_replaces_tags() {
+            let mut conn = setup();
+            let tags = vec!["signed".to_string(), "graded".to_string()];
+            assert_eq!(set(&mut conn, 25, &tags).unwrap(), vec!["graded", "signed"]);
+
+            assert_eq!(
+                set(&mut conn, 25, &["signed".to_string()]).unwrap(),
+                vec!["signed"]
+            );
+            assert_eq!(get(&conn, 25).unwrap(), vec!["signed"]);
+
+            let err = set(&mut conn, 26, &tags).unwrap_err();
+            assert!(err.downcast_ref::<CardNotStored>().is_some());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_tags() {
+        let tags = |tags: &[&str]| -> Vec<String> { tags.iter().map(|t| t.to_string()).collect() };
+        assert_eq!(
+            check_tags(&tags(&[" Signed", "first-edition", "", "signed"])),
+            Ok(tags(&["first-edition", "signed"]))
+        );
+        assert!(check_tags(&tags(&["first edition"])).is_err());
+        assert!(check_tags(&tags(&[&"x".repeat(MAX_TAG_LEN + 1)])).is_err());
+        let many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{i}")).collect();
+        assert!(check_tags(&many).is_err());
+    }
+}